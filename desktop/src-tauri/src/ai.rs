@@ -0,0 +1,178 @@
+// ============================================
+// ScreenAI Desktop — Asking a vision model about a capture
+// ============================================
+//
+// One request/response round trip, no streaming. Provider, key, base URL and
+// model come from the saved Config so nothing secret lives in the frontend
+// bundle or in this file.
+
+use crate::config::Config;
+use crate::error::AppError;
+use crate::imaging::{self, OutputFormat};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(90);
+const MAX_TOKENS: u32 = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Provider {
+    Anthropic,
+    OpenAi,
+    Ollama,
+}
+
+impl Provider {
+    pub fn parse(provider: &str) -> Result<Self, AppError> {
+        match provider.trim().to_ascii_lowercase().as_str() {
+            "anthropic" | "claude" => Ok(Provider::Anthropic),
+            "openai" => Ok(Provider::OpenAi),
+            "ollama" => Ok(Provider::Ollama),
+            other => Err(AppError::InvalidArgument(format!(
+                "Unknown AI provider \"{}\" (expected anthropic, openai or ollama)",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Provider::Anthropic => "anthropic",
+            Provider::OpenAi => "openai",
+            Provider::Ollama => "ollama",
+        }
+    }
+
+    fn default_base_url(self) -> &'static str {
+        match self {
+            Provider::Anthropic => "https://api.anthropic.com",
+            Provider::OpenAi => "https://api.openai.com",
+            Provider::Ollama => "http://localhost:11434",
+        }
+    }
+
+    fn default_model(self) -> &'static str {
+        match self {
+            Provider::Anthropic => "claude-3-5-sonnet-latest",
+            Provider::OpenAi => "gpt-4o-mini",
+            Provider::Ollama => "llava",
+        }
+    }
+}
+
+/// Image bytes and MIME type in a form every provider accepts
+fn image_part(data_url: &str) -> Result<(String, &'static str), AppError> {
+    let bytes = imaging::data_url_bytes(data_url)?;
+    let (bytes, mime) = match image::guess_format(&bytes) {
+        Ok(image::ImageFormat::Png) => (bytes, "image/png"),
+        Ok(image::ImageFormat::Jpeg) => (bytes, "image/jpeg"),
+        Ok(image::ImageFormat::WebP) => (bytes, "image/webp"),
+        _ => (imaging::transcode(&bytes, OutputFormat::Png)?, "image/png"),
+    };
+    Ok((BASE64.encode(bytes), mime))
+}
+
+/// Send `data_url` and `prompt` to the configured vision model and return its answer.
+/// `provider` overrides `Config::ai_provider` for this call.
+pub async fn analyze(config: &Config, data_url: &str, prompt: &str, provider: Option<&str>) -> Result<String, AppError> {
+    let provider = Provider::parse(provider.unwrap_or(&config.ai_provider))?;
+    let api_key = config.ai_api_key.as_deref().map(str::trim).unwrap_or_default();
+    if api_key.is_empty() && provider != Provider::Ollama {
+        return Err(AppError::InvalidArgument(format!(
+            "No API key configured for {}",
+            provider.as_str()
+        )));
+    }
+    let base_url = config
+        .ai_base_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|u| !u.is_empty())
+        .unwrap_or(provider.default_base_url())
+        .trim_end_matches('/');
+    let model = config
+        .ai_model
+        .as_deref()
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+        .unwrap_or(provider.default_model());
+    let (image_b64, mime) = image_part(data_url)?;
+
+    let client = reqwest::Client::builder()
+        .user_agent("ScreenAI-Desktop")
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| AppError::AnalysisFailed(e.to_string()))?;
+
+    let request = match provider {
+        Provider::Anthropic => client
+            .post(format!("{}/v1/messages", base_url))
+            .header("x-api-key", api_key)
+            .header("anthropic-version", "2023-06-01")
+            .json(&json!({
+                "model": model,
+                "max_tokens": MAX_TOKENS,
+                "messages": [{
+                    "role": "user",
+                    "content": [
+                        { "type": "image", "source": { "type": "base64", "media_type": mime, "data": image_b64 } },
+                        { "type": "text", "text": prompt },
+                    ],
+                }],
+            })),
+        Provider::OpenAi => client
+            .post(format!("{}/v1/chat/completions", base_url))
+            .bearer_auth(api_key)
+            .json(&json!({
+                "model": model,
+                "max_tokens": MAX_TOKENS,
+                "messages": [{
+                    "role": "user",
+                    "content": [
+                        { "type": "text", "text": prompt },
+                        { "type": "image_url", "image_url": { "url": format!("data:{};base64,{}", mime, image_b64) } },
+                    ],
+                }],
+            })),
+        Provider::Ollama => client
+            .post(format!("{}/api/chat", base_url))
+            .json(&json!({
+                "model": model,
+                "stream": false,
+                "messages": [{ "role": "user", "content": prompt, "images": [image_b64] }],
+            })),
+    };
+
+    let response = request.send().await.map_err(|e| {
+        if e.is_timeout() {
+            AppError::AnalysisFailed(format!("{} did not answer within {} s", provider.as_str(), REQUEST_TIMEOUT.as_secs()))
+        } else {
+            AppError::AnalysisFailed(format!("could not reach {}: {}", base_url, e))
+        }
+    })?;
+
+    let status = response.status();
+    let text = response.text().await.map_err(|e| AppError::AnalysisFailed(e.to_string()))?;
+    let body: Value = serde_json::from_str(&text).unwrap_or(Value::Null);
+    if !status.is_success() {
+        // All three put a human-readable message under "error"
+        let message = body["error"]["message"]
+            .as_str()
+            .or_else(|| body["error"].as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| text.chars().take(300).collect());
+        return Err(AppError::AnalysisFailed(format!("{} returned {}: {}", provider.as_str(), status.as_u16(), message)));
+    }
+
+    let answer = match provider {
+        Provider::Anthropic => body["content"]
+            .as_array()
+            .map(|blocks| blocks.iter().filter_map(|b| b["text"].as_str()).collect::<Vec<_>>().join("")),
+        Provider::OpenAi => body["choices"][0]["message"]["content"].as_str().map(str::to_string),
+        Provider::Ollama => body["message"]["content"].as_str().map(str::to_string),
+    };
+    answer
+        .map(|a| a.trim().to_string())
+        .ok_or_else(|| AppError::AnalysisFailed(format!("unexpected response from {}", provider.as_str())))
+}
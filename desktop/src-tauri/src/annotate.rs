@@ -0,0 +1,250 @@
+// ============================================
+// ScreenAI Desktop — Annotations rendered onto captures
+// ============================================
+//
+// Shapes are drawn at the capture's native resolution with imageproc, so they
+// stay crisp regardless of how the webview scaled the preview.
+
+use crate::capture::Rect;
+use crate::error::AppError;
+use image::{Rgba, RgbaImage};
+use imageproc::drawing::{self, Blend};
+use imageproc::point::Point as PolyPoint;
+use rusttype::{Font, Scale};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::OnceLock;
+
+/// Number the next step badge gets. Shared by every `annotate` command call
+/// until `reset_steps`, so badges added one call at a time keep counting.
+static NEXT_STEP: AtomicU32 = AtomicU32::new(1);
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// One drawing operation, tagged by `type` in JSON:
+/// `{ "type": "arrow", "from": {"x":0,"y":0}, "to": {...}, "color": "#ff0000", "width": 4 }`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnnotationOp {
+    Arrow { from: Point, to: Point, color: String, width: f32 },
+    Rect { rect: Rect, color: String, width: f32 },
+    Text { pos: Point, content: String, size: f32, color: String },
+    /// Translucent fill; colors without alpha are drawn at 40% opacity
+    Highlight { rect: Rect, color: String },
+    /// Filled circle centered on `pos` with the next step number in it;
+    /// `size` is its diameter (default 32)
+    StepBadge {
+        pos: Point,
+        color: String,
+        text_color: String,
+        #[serde(default = "default_badge_size")]
+        size: f32,
+    },
+}
+
+fn default_badge_size() -> f32 {
+    32.0
+}
+
+/// Start step badges from 1 again
+pub fn reset_steps() {
+    NEXT_STEP.store(1, Ordering::SeqCst);
+}
+
+/// Parse "#rgb", "#rrggbb" or "#rrggbbaa" (leading '#' optional)
+pub fn parse_color(hex: &str) -> Result<Rgba<u8>, AppError> {
+    let digits = hex.trim().trim_start_matches('#');
+    let invalid = || AppError::InvalidArgument(format!("Invalid color \"{}\" (expected #rrggbb)", hex));
+    // Checked before slicing: the byte offsets below assume one byte per digit
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let channel = |i: usize| u8::from_str_radix(digits.get(i..i + 2).ok_or_else(invalid)?, 16).map_err(|_| invalid());
+    match digits.len() {
+        3 => {
+            let short = |i: usize| u8::from_str_radix(&digits[i..i + 1], 16).map(|v| v * 17).map_err(|_| invalid());
+            Ok(Rgba([short(0)?, short(1)?, short(2)?, 255]))
+        }
+        6 => Ok(Rgba([channel(0)?, channel(2)?, channel(4)?, 255])),
+        8 => Ok(Rgba([channel(0)?, channel(2)?, channel(4)?, channel(6)?])),
+        _ => Err(invalid()),
+    }
+}
+
+/// A sans-serif system font for text ops, loaded once
+pub fn font() -> Option<&'static Font<'static>> {
+    static FONT: OnceLock<Option<Font<'static>>> = OnceLock::new();
+    FONT.get_or_init(|| {
+        const CANDIDATES: &[&str] = &[
+            "C:\\Windows\\Fonts\\segoeui.ttf",
+            "C:\\Windows\\Fonts\\arial.ttf",
+            "/System/Library/Fonts/Supplemental/Arial.ttf",
+            "/Library/Fonts/Arial.ttf",
+            "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+            "/usr/share/fonts/TTF/DejaVuSans.ttf",
+            "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+            "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+        ];
+        CANDIDATES
+            .iter()
+            .filter_map(|path| std::fs::read(path).ok())
+            .find_map(Font::try_from_vec)
+    })
+    .as_ref()
+}
+
+/// Straight line `width` pixels thick, drawn as a filled quad with round caps
+pub fn thick_line(canvas: &mut Blend<RgbaImage>, from: Point, to: Point, width: f32, color: Rgba<u8>) {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let len = (dx * dx + dy * dy).sqrt();
+    let half = (width / 2.0).max(0.5);
+    if len < 0.5 || width <= 1.5 {
+        drawing::draw_line_segment_mut(canvas, (from.x, from.y), (to.x, to.y), color);
+        return;
+    }
+    // Perpendicular offset
+    let (nx, ny) = (-dy / len * half, dx / len * half);
+    let corner = |p: Point, sign: f32| PolyPoint::new((p.x + nx * sign).round() as i32, (p.y + ny * sign).round() as i32);
+    let quad = [corner(from, 1.0), corner(to, 1.0), corner(to, -1.0), corner(from, -1.0)];
+    if quad[0] != quad[3] {
+        drawing::draw_polygon_mut(canvas, &quad, color);
+    }
+    let radius = half.round() as i32;
+    drawing::draw_filled_circle_mut(canvas, (from.x.round() as i32, from.y.round() as i32), radius, color);
+    drawing::draw_filled_circle_mut(canvas, (to.x.round() as i32, to.y.round() as i32), radius, color);
+}
+
+fn arrow(canvas: &mut Blend<RgbaImage>, from: Point, to: Point, width: f32, color: Rgba<u8>) {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let len = (dx * dx + dy * dy).sqrt();
+    if len < 1.0 {
+        return;
+    }
+    let (ux, uy) = (dx / len, dy / len);
+    // Head scales with the stroke but never exceeds the arrow itself
+    let head_len = (width * 4.0).max(10.0).min(len);
+    let head_half = head_len * 0.5;
+    let base = Point { x: to.x - ux * head_len, y: to.y - uy * head_len };
+
+    thick_line(canvas, from, base, width, color);
+    let head = [
+        PolyPoint::new(to.x.round() as i32, to.y.round() as i32),
+        PolyPoint::new((base.x - uy * head_half).round() as i32, (base.y + ux * head_half).round() as i32),
+        PolyPoint::new((base.x + uy * head_half).round() as i32, (base.y - ux * head_half).round() as i32),
+    ];
+    if head[0] != head[2] {
+        drawing::draw_polygon_mut(canvas, &head, color);
+    }
+}
+
+fn outline(canvas: &mut Blend<RgbaImage>, rect: Rect, width: f32, color: Rgba<u8>) {
+    let (l, t) = (rect.x as f32, rect.y as f32);
+    let (r, b) = (l + rect.w as f32, t + rect.h as f32);
+    let corners = [Point { x: l, y: t }, Point { x: r, y: t }, Point { x: r, y: b }, Point { x: l, y: b }];
+    for i in 0..4 {
+        thick_line(canvas, corners[i], corners[(i + 1) % 4], width, color);
+    }
+}
+
+/// Fill `rect` with `color`, blended over the image
+pub fn fill(canvas: &mut Blend<RgbaImage>, rect: Rect, color: Rgba<u8>) {
+    if rect.w == 0 || rect.h == 0 {
+        return;
+    }
+    let area = imageproc::rect::Rect::at(rect.x, rect.y).of_size(rect.w, rect.h);
+    drawing::draw_filled_rect_mut(canvas, area, color);
+}
+
+/// Draw `content` with its top-left corner at `pos`; one line per '\n'
+pub fn text(canvas: &mut Blend<RgbaImage>, pos: Point, content: &str, size: f32, color: Rgba<u8>) -> Result<(), AppError> {
+    let font = font().ok_or_else(|| AppError::InvalidArgument("No font available to draw text".to_string()))?;
+    let scale = Scale::uniform(size.max(1.0));
+    let line_height = (size * 1.2).round() as i32;
+    for (i, line) in content.lines().enumerate() {
+        let y = pos.y.round() as i32 + i as i32 * line_height;
+        drawing::draw_text_mut(canvas, color, pos.x.round() as i32, y, scale, font, line);
+    }
+    Ok(())
+}
+
+/// Circle `diameter` wide centered on `pos`, with `number` centered in it
+fn step_badge(canvas: &mut Blend<RgbaImage>, pos: Point, number: u32, diameter: f32, color: Rgba<u8>, text_color: Rgba<u8>) -> Result<(), AppError> {
+    let font = font().ok_or_else(|| AppError::InvalidArgument("No font available to draw text".to_string()))?;
+    let radius = (diameter / 2.0).max(1.0);
+    let center = (pos.x.round() as i32, pos.y.round() as i32);
+    drawing::draw_filled_circle_mut(canvas, center, radius.round() as i32, color);
+
+    let label = number.to_string();
+    // Longer numbers get a smaller font to stay inside the circle
+    let scale = Scale::uniform(radius * [1.1, 0.95, 0.75][label.len().min(3) - 1]);
+    // Center the digits' ink rather than the font's line box
+    let ascent = font.v_metrics(scale).ascent;
+    let (mut min, mut max) = ((i32::MAX, i32::MAX), (i32::MIN, i32::MIN));
+    for glyph in font.layout(&label, scale, rusttype::point(0.0, ascent)) {
+        if let Some(bb) = glyph.pixel_bounding_box() {
+            min = (min.0.min(bb.min.x), min.1.min(bb.min.y));
+            max = (max.0.max(bb.max.x), max.1.max(bb.max.y));
+        }
+    }
+    if min.0 <= max.0 {
+        let (x, y) = (center.0 - (min.0 + max.0) / 2, center.1 - (min.1 + max.1) / 2);
+        drawing::draw_text_mut(canvas, text_color, x, y, scale, font, &label);
+    }
+    Ok(())
+}
+
+/// Render `ops` in order onto `image`. Step badges take their numbers from
+/// the shared counter, which only moves on once the whole call succeeded.
+pub fn apply(image: RgbaImage, ops: &[AnnotationOp]) -> Result<RgbaImage, AppError> {
+    let mut canvas = Blend(image);
+    let first_step = NEXT_STEP.load(Ordering::SeqCst);
+    let mut steps = 0;
+    for op in ops {
+        match op {
+            AnnotationOp::Arrow { from, to, color, width } => arrow(&mut canvas, *from, *to, *width, parse_color(color)?),
+            AnnotationOp::Rect { rect, color, width } => outline(&mut canvas, *rect, *width, parse_color(color)?),
+            AnnotationOp::Text { pos, content, size, color } => text(&mut canvas, *pos, content, *size, parse_color(color)?)?,
+            AnnotationOp::Highlight { rect, color } => {
+                let mut color = parse_color(color)?;
+                if color.0[3] == 255 {
+                    color.0[3] = 102;
+                }
+                fill(&mut canvas, *rect, color);
+            }
+            AnnotationOp::StepBadge { pos, color, text_color, size } => {
+                let (color, text_color) = (parse_color(color)?, parse_color(text_color)?);
+                step_badge(&mut canvas, *pos, first_step + steps, *size, color, text_color)?;
+                steps += 1;
+            }
+        }
+    }
+    if steps > 0 {
+        // Unless `reset_steps` or another call moved it meanwhile
+        let _ = NEXT_STEP.compare_exchange(first_step, first_step + steps, Ordering::SeqCst, Ordering::SeqCst);
+    }
+    Ok(canvas.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_color;
+    use image::Rgba;
+
+    #[test]
+    fn parses_short_long_and_alpha_forms() {
+        assert_eq!(parse_color("#fff").ok(), Some(Rgba([255, 255, 255, 255])));
+        assert_eq!(parse_color("12ab34").ok(), Some(Rgba([0x12, 0xab, 0x34, 255])));
+        assert_eq!(parse_color(" #11223344 ").ok(), Some(Rgba([0x11, 0x22, 0x33, 0x44])));
+    }
+
+    #[test]
+    fn rejects_malformed_colors() {
+        for bad in ["", "#", "#12345", "#ggg", "#é1", "#1é", "#ff00ffé"] {
+            assert!(parse_color(bad).is_err(), "{:?} should be rejected", bad);
+        }
+    }
+}
@@ -0,0 +1,381 @@
+// ============================================
+// ScreenAI Desktop — Automatic saving of captures
+// ============================================
+//
+// When `Config::auto_save_enabled` is set, every capture is written to
+// `auto_save_dir` (default ~/Documents/ScreenAI/Captures) using the
+// strftime-like `auto_save_pattern`. Patterns with `%n` number captures from
+// `Config::capture_counter`, which is saved after every use so the sequence
+// carries on across restarts. `list_saved` and `delete_saved` let the app
+// manage what has piled up there without a file browser.
+
+use crate::capture::CapturePayload;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::imaging::{self, OutputFormat};
+use chrono::{Datelike, NaiveDateTime, TimeZone, Timelike};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+pub const DEFAULT_PATTERN: &str = "ScreenAI_%Y%m%d_%H%M%S.png";
+/// Numbers tried past a taken name before giving up
+const MAX_COUNTER_SKIP: u64 = 100_000;
+
+/// Files `list_saved` reports: what captures, recordings and replays are saved as
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "avif", "gif"];
+
+/// Held while a counter value is picked and while the config is saved, so two
+/// captures never get the same number and no save puts an older counter back
+pub static COUNTER_LOCK: Mutex<()> = Mutex::new(());
+
+/// Values for the capture-specific fields of a name pattern
+#[derive(Clone, Copy, Default)]
+pub struct NameFields<'a> {
+    pub counter: u64,
+    pub monitor_index: Option<usize>,
+    pub mode: &'a str,
+}
+
+/// Expand `%Y %m %d %H %M %S` (local time) and `%%` in `pattern`.
+/// Path separators are replaced so the result is always a plain file name.
+pub fn format_pattern(pattern: &str, unix_secs: u64) -> String {
+    format_name(pattern, unix_secs, NameFields::default())
+}
+
+/// `format_pattern` plus `%n` (counter, at least 3 digits), `%i` (monitor
+/// index, empty when unknown) and `%t` (capture mode, e.g. "region")
+pub fn format_name(pattern: &str, unix_secs: u64, fields: NameFields) -> String {
+    expand(pattern, unix_secs, fields).replace(['/', '\\'], "_")
+}
+
+/// The field expansion behind `format_name`, with path separators left in,
+/// for text shown on screen rather than used as a file name
+pub fn expand(pattern: &str, unix_secs: u64, fields: NameFields) -> String {
+    let time = chrono::Local
+        .timestamp_opt(unix_secs as i64, 0)
+        .single()
+        .map(|time| time.naive_local())
+        .unwrap_or_else(|| utc(unix_secs));
+    expand_at(pattern, time, fields)
+}
+
+/// `expand` with the date and time fields taken from `unix_secs` as UTC, for
+/// callers that applied a time zone offset of their own
+pub fn expand_utc(pattern: &str, unix_secs: u64, fields: NameFields) -> String {
+    expand_at(pattern, utc(unix_secs), fields)
+}
+
+fn utc(unix_secs: u64) -> NaiveDateTime {
+    chrono::DateTime::from_timestamp(unix_secs as i64, 0).unwrap_or_default().naive_utc()
+}
+
+fn expand_at(pattern: &str, time: NaiveDateTime, fields: NameFields) -> String {
+    let mut out = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => out.push_str(&time.year().to_string()),
+            Some('m') => out.push_str(&format!("{:02}", time.month())),
+            Some('d') => out.push_str(&format!("{:02}", time.day())),
+            Some('H') => out.push_str(&format!("{:02}", time.hour())),
+            Some('M') => out.push_str(&format!("{:02}", time.minute())),
+            Some('S') => out.push_str(&format!("{:02}", time.second())),
+            Some('n') => out.push_str(&format!("{:03}", fields.counter)),
+            Some('i') => out.push_str(&fields.monitor_index.map(|i| i.to_string()).unwrap_or_default()),
+            Some('t') => out.push_str(fields.mode),
+            Some('%') => out.push('%'),
+            Some(other) => { out.push('%'); out.push(other); }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+/// Folder auto-saved captures go to
+pub fn output_dir(config: &Config) -> PathBuf {
+    match config.auto_save_dir.as_deref() {
+        Some(dir) if !dir.trim().is_empty() => PathBuf::from(dir),
+        _ => crate::get_screenai_captures_dir(),
+    }
+}
+
+/// The auto-save file name for the next capture, e.g. "shot_004.png" for
+/// "shot_%n.png". With `%n` in the pattern this is the next counter value
+/// whose file doesn't exist in `dir` yet; the counter itself only moves when
+/// `save` writes the file, and a file saved under this name from elsewhere is
+/// skipped next time because it exists. Names without a known image extension
+/// get `fallback_ext`.
+pub fn next_filename(
+    app: &AppHandle,
+    dir: &Path,
+    mode: &str,
+    monitor_index: Option<usize>,
+    fallback_ext: &str,
+) -> Result<String, AppError> {
+    let _lock = COUNTER_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    pick_filename(&Config::load(app), dir, mode, monitor_index, fallback_ext).map(|(name, _)| name)
+}
+
+/// `next_filename` from `config`, plus the counter value the name uses (`None`
+/// for patterns without `%n`). The caller holds `COUNTER_LOCK`.
+fn pick_filename(
+    config: &Config,
+    dir: &Path,
+    mode: &str,
+    monitor_index: Option<usize>,
+    fallback_ext: &str,
+) -> Result<(String, Option<u64>), AppError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let pattern = match config.auto_save_pattern.as_str() {
+        p if format_pattern(p, now).trim_matches(['.', ' ']).is_empty() => DEFAULT_PATTERN,
+        p => p,
+    };
+    let name = |counter: u64| {
+        let mut path = PathBuf::from(format_name(pattern, now, NameFields { counter, monitor_index, mode }));
+        if OutputFormat::from_extension(&path).is_err() {
+            path.set_extension(fallback_ext);
+        }
+        path.to_string_lossy().to_string()
+    };
+
+    if !pattern.replace("%%", "").contains("%n") {
+        return Ok((name(config.capture_counter), None));
+    }
+    let start = config.capture_counter;
+    let mut counter = start + 1;
+    while dir.join(name(counter)).exists() {
+        counter += 1;
+        if counter - start > MAX_COUNTER_SKIP {
+            return Err(AppError::Io(format!("No free file name for pattern {} in {}", pattern, dir.display())));
+        }
+    }
+    Ok((name(counter), Some(counter)))
+}
+
+/// Extension matching the encoded capture's format
+fn capture_extension(bytes: &[u8]) -> &'static str {
+    match image::guess_format(bytes) {
+        Ok(image::ImageFormat::Jpeg) => "jpg",
+        Ok(image::ImageFormat::WebP) => "webp",
+        Ok(image::ImageFormat::Avif) => "avif",
+        _ => "png",
+    }
+}
+
+/// Write `payload` according to the auto-save settings.
+/// Returns `None` when auto-save is off, otherwise the path written.
+pub fn save(app: &AppHandle, config: &Config, payload: &CapturePayload) -> Result<Option<PathBuf>, AppError> {
+    if !config.auto_save_enabled {
+        return Ok(None);
+    }
+
+    // The pattern's extension picks the file format; without one keep the capture as-is
+    let bytes = imaging::data_url_bytes(&payload.data_url)?;
+    let dir = output_dir(config);
+    let ext = capture_extension(&bytes);
+    std::fs::create_dir_all(&dir).map_err(|e| AppError::Io(e.to_string()))?;
+
+    // The name is claimed with an empty file under the lock, so a capture saved
+    // meanwhile picks the next one; the counter moves once the image is written
+    let (path, format, counter) = {
+        let _lock = COUNTER_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let (name, counter) = pick_filename(&Config::load(app), &dir, &payload.mode, payload.monitor_index, ext)?;
+        let mut path = dir.join(name);
+        let format = OutputFormat::from_extension(&path).ok();
+        if format.is_none() {
+            path.set_extension(ext);
+        }
+
+        // Never overwrite: two captures in the same second get _1, _2, ... (`%n`
+        // names are already free unless the extension was changed above)
+        let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let ext = path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+        let mut n = 1;
+        loop {
+            match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => break,
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    path = dir.join(format!("{}_{}.{}", stem, n, ext));
+                    n += 1;
+                }
+                Err(e) => return Err(AppError::Io(format!("{}: {}", path.display(), e))),
+            }
+        }
+        (path, format, counter)
+    };
+
+    let written = match format {
+        Some(format) => imaging::transcode(&bytes, format),
+        None => Ok(bytes),
+    }
+    .and_then(|bytes| {
+        crate::disk::ensure_space(&dir, bytes.len() as u64)?;
+        std::fs::write(&path, bytes).map_err(|e| AppError::Io(format!("{}: {}", path.display(), e)))
+    });
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&path);
+        return Err(e);
+    }
+
+    if let Some(counter) = counter {
+        let _lock = COUNTER_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut config = Config::load(app);
+        config.capture_counter = config.capture_counter.max(counter);
+        config.write(app)?;
+    }
+    Ok(Some(path))
+}
+
+/// Auto-save in the background and emit "capture-auto-saved" with the path
+pub fn save_in_background(app: &AppHandle, payload: &CapturePayload) {
+    let config = Config::load(app);
+    if !config.auto_save_enabled {
+        return;
+    }
+    let app = app.clone();
+    let payload = payload.clone();
+    tauri::async_runtime::spawn_blocking(move || match save(&app, &config, &payload) {
+        Ok(Some(path)) => {
+            let _ = app.emit_all("capture-auto-saved", path.to_string_lossy().to_string());
+        }
+        Ok(None) => {}
+        Err(e) => log::error!("Auto-save failed: {}", e),
+    });
+}
+
+/// An image file in the output folder, returned by `list_saved_captures`
+#[derive(Clone, Serialize)]
+pub struct SavedCapture {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    /// Unix time in milliseconds
+    pub modified_ms: u64,
+}
+
+/// Order of `list_saved`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortBy {
+    /// Newest first
+    Modified,
+    /// A to Z
+    Name,
+    /// Largest first
+    Size,
+}
+
+impl SortBy {
+    pub fn parse(value: Option<&str>) -> Result<Self, AppError> {
+        match value.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("modified") => Ok(SortBy::Modified),
+            Some("name") => Ok(SortBy::Name),
+            Some("size") => Ok(SortBy::Size),
+            Some(other) => Err(AppError::InvalidArgument(format!(
+                "Unknown sort order \"{}\" (expected modified, name or size)",
+                other
+            ))),
+        }
+    }
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.iter().any(|known| ext.eq_ignore_ascii_case(known)))
+}
+
+/// Image files directly in the output folder. A folder that doesn't exist yet
+/// holds nothing.
+pub fn list_saved(config: &Config, sort: SortBy) -> Result<Vec<SavedCapture>, AppError> {
+    let dir = output_dir(config);
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(AppError::Io(format!("{}: {}", dir.display(), e))),
+    };
+    let mut captures: Vec<SavedCapture> = entries
+        .flatten()
+        .filter(|entry| is_image(&entry.path()))
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok().filter(|m| m.is_file())?;
+            let modified_ms = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_millis() as u64);
+            Some(SavedCapture {
+                name: entry.file_name().to_string_lossy().to_string(),
+                path: entry.path().to_string_lossy().to_string(),
+                size: meta.len(),
+                modified_ms,
+            })
+        })
+        .collect();
+    match sort {
+        SortBy::Modified => captures.sort_by_key(|c| std::cmp::Reverse(c.modified_ms)),
+        SortBy::Name => captures.sort_by_key(|c| c.name.to_lowercase()),
+        SortBy::Size => captures.sort_by_key(|c| std::cmp::Reverse(c.size)),
+    }
+    Ok(captures)
+}
+
+/// Delete one image file from the output folder. `path` is absolute or
+/// relative to the folder; anything that resolves outside it (`..`, symlinks)
+/// or isn't an image file is refused.
+pub fn delete_saved(config: &Config, path: &Path) -> Result<(), AppError> {
+    let dir = output_dir(config);
+    let path = if path.is_absolute() { path.to_path_buf() } else { dir.join(path) };
+    let resolve = |p: &Path| p.canonicalize().map_err(|e| AppError::Io(format!("{}: {}", p.display(), e)));
+    let (dir, file) = (resolve(&dir)?, resolve(&path)?);
+    if !file.starts_with(&dir) || file == dir {
+        return Err(AppError::InvalidArgument(format!("{} is not in the captures folder {}", path.display(), dir.display())));
+    }
+    if !file.is_file() || !is_image(&file) {
+        return Err(AppError::InvalidArgument(format!("{} is not a saved capture", path.display())));
+    }
+    std::fs::remove_file(&file).map_err(|e| AppError::Io(format!("{}: {}", file.display(), e)))?;
+    log::info!("Deleted saved capture {}", file.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand_utc, format_name, format_pattern, NameFields};
+    use chrono::TimeZone;
+
+    const SECS: u64 = 1_700_000_000;
+
+    #[test]
+    fn fills_capture_fields() {
+        let fields = NameFields { counter: 4, monitor_index: Some(2), mode: "region" };
+        assert_eq!(format_name("shot_%n_%i_%t.png", SECS, fields), "shot_004_2_region.png");
+        assert_eq!(format_name("%n", SECS, NameFields { counter: 1234, ..fields }), "1234");
+        assert_eq!(format_name("a%i.png", SECS, NameFields::default()), "a.png");
+    }
+
+    #[test]
+    fn keeps_escapes_and_unknown_fields() {
+        assert_eq!(format_name("100%%_%q_%", SECS, NameFields::default()), "100%_%q_%");
+    }
+
+    #[test]
+    fn never_returns_a_path() {
+        assert_eq!(format_name("a/b\\%t", SECS, NameFields { mode: "x/y", ..Default::default() }), "a_b_x_y");
+    }
+
+    #[test]
+    fn dates_are_local_time() {
+        let expected = chrono::Local.timestamp_opt(SECS as i64, 0).unwrap().format("%Y%m%d_%H%M%S").to_string();
+        assert_eq!(format_pattern("%Y%m%d_%H%M%S", SECS), expected);
+        assert_eq!(expand_utc("%Y-%m-%d %H:%M:%S", SECS, NameFields::default()), "2023-11-14 22:13:20");
+    }
+}
@@ -0,0 +1,139 @@
+// ============================================
+// ScreenAI Desktop — Launch at login
+// ============================================
+//
+// Registers the app with the OS's own autostart mechanism: a value under the
+// per-user Run key on Windows, a LaunchAgent plist on macOS and an XDG
+// `.desktop` file in ~/.config/autostart on Linux. None of them need admin
+// rights. The OS entry is the source of truth; `launchAtLogin` in the config
+// only remembers the choice so the entry can be refreshed when the app moves.
+
+use crate::error::AppError;
+use std::path::PathBuf;
+
+/// Name of the Run value / plist label / desktop file
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+const ENTRY: &str = "com.screenai.app";
+
+/// What the entry should launch. On Linux the AppImage itself, not the
+/// binary mounted from it, which lives under a temporary path.
+fn launch_path() -> Result<PathBuf, AppError> {
+    #[cfg(target_os = "linux")]
+    if let Some(appimage) = std::env::var_os("APPIMAGE") {
+        return Ok(PathBuf::from(appimage));
+    }
+    std::env::current_exe().map_err(|e| AppError::AutostartFailed(format!("could not locate the app: {}", e)))
+}
+
+#[cfg(target_os = "windows")]
+fn run_key(write: bool) -> Result<winreg::RegKey, AppError> {
+    use winreg::enums::{HKEY_CURRENT_USER, KEY_READ, KEY_SET_VALUE};
+    let access = if write { KEY_READ | KEY_SET_VALUE } else { KEY_READ };
+    winreg::RegKey::predef(HKEY_CURRENT_USER)
+        .open_subkey_with_flags(r"Software\Microsoft\Windows\CurrentVersion\Run", access)
+        .map_err(|e| AppError::AutostartFailed(format!("could not open the Run key: {}", e)))
+}
+
+/// Whether the app is registered to start at login for the current user
+#[cfg(target_os = "windows")]
+pub fn is_enabled() -> Result<bool, AppError> {
+    Ok(run_key(false)?.get_value::<String, _>(ENTRY).is_ok())
+}
+
+#[cfg(target_os = "windows")]
+pub fn set(enabled: bool) -> Result<(), AppError> {
+    let key = run_key(true)?;
+    if enabled {
+        let command = format!("\"{}\"", launch_path()?.display());
+        key.set_value(ENTRY, &command)
+            .map_err(|e| AppError::AutostartFailed(format!("could not write the Run key: {}", e)))
+    } else {
+        match key.delete_value(ENTRY) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(AppError::AutostartFailed(format!("could not remove the Run key value: {}", e)))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// ~/Library/LaunchAgents/<ENTRY>.plist or ~/.config/autostart/<ENTRY>.desktop
+#[cfg(not(target_os = "windows"))]
+fn entry_path() -> Result<PathBuf, AppError> {
+    #[cfg(target_os = "macos")]
+    let dir = dirs::home_dir().map(|home| home.join("Library").join("LaunchAgents"));
+    #[cfg(not(target_os = "macos"))]
+    let dir = dirs::config_dir().map(|config| config.join("autostart"));
+    #[cfg(target_os = "macos")]
+    let name = format!("{}.plist", ENTRY);
+    #[cfg(not(target_os = "macos"))]
+    let name = "screenai.desktop".to_string();
+    dir.map(|dir| dir.join(name))
+        .ok_or_else(|| AppError::AutostartFailed("no home directory".to_string()))
+}
+
+#[cfg(target_os = "macos")]
+fn entry_contents(program: &std::path::Path) -> String {
+    let escape = |s: &str| s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+        ENTRY,
+        escape(&program.to_string_lossy())
+    )
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn entry_contents(program: &std::path::Path) -> String {
+    // Exec is quoted for paths with spaces, and `"`, `\`, `` ` `` and `$` are
+    // escaped inside the quotes. The value is then read as a desktop-entry
+    // string, which takes `\\` for each backslash, and `%` starts a field code.
+    let mut quoted = String::new();
+    for c in program.to_string_lossy().chars() {
+        if matches!(c, '"' | '\\' | '`' | '$') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    let exec = quoted.replace('\\', "\\\\").replace('%', "%%");
+    format!(
+        "[Desktop Entry]\nType=Application\nName=ScreenAI\nExec=\"{}\"\nX-GNOME-Autostart-enabled=true\nTerminal=false\n",
+        exec
+    )
+}
+
+/// Whether the app is registered to start at login for the current user
+#[cfg(not(target_os = "windows"))]
+pub fn is_enabled() -> Result<bool, AppError> {
+    Ok(entry_path()?.exists())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn set(enabled: bool) -> Result<(), AppError> {
+    let path = entry_path()?;
+    let failed = |e: std::io::Error| AppError::AutostartFailed(format!("{}: {}", path.display(), e));
+    if enabled {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(failed)?;
+        }
+        std::fs::write(&path, entry_contents(&launch_path()?)).map_err(failed)
+    } else {
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(failed(e)),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,1277 @@
+// ============================================
+// ScreenAI Desktop — Screen capture pipeline
+// ============================================
+//
+// macOS: uses `screencapture` CLI (reliable, handles permissions natively)
+// Windows/Linux: uses the `screenshots` crate
+
+use crate::error::AppError;
+use crate::imaging::{self, ColorMode, JpegSubsampling, OutputFormat, PngCompression};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Clone, Serialize)]
+pub struct CapturePayload {
+    pub data_url: String,
+    pub mode: String,
+    /// Index into `Screen::all()` of the display that was captured, if any
+    pub monitor_index: Option<usize>,
+    /// Image size in physical pixels
+    pub width: u32,
+    pub height: u32,
+    /// Image size in logical (DPI-independent) units
+    pub logical_width: u32,
+    pub logical_height: u32,
+    /// Time spent grabbing the pixels from the OS, in milliseconds
+    pub capture_ms: u64,
+    /// Time spent encoding the image, in milliseconds
+    pub encode_ms: u64,
+    /// Small JPEG preview, when the capture was asked for one (`with_thumbnail`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<String>,
+    /// Clockwise rotation (0, 90, 180, 270) the OS reports for the captured display
+    pub rotation: u16,
+    /// Area `capture_work_area` captured, in the display's coordinates as
+    /// reported by `list_monitors`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub work_area: Option<Rect>,
+}
+
+/// Milliseconds spent in each stage of a capture
+#[derive(Clone, Copy)]
+struct Timing {
+    capture_ms: u64,
+    encode_ms: u64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct MonitorInfo {
+    pub index: usize,
+    pub width: u32,
+    pub height: u32,
+    pub x: i32,
+    pub y: i32,
+    pub is_primary: bool,
+}
+
+/// Display geometry for positioning overlays and converting coordinates
+#[derive(Clone, Serialize)]
+pub struct ScreenInfo {
+    /// Same index as `list_monitors` / `capture_screen`
+    pub index: usize,
+    pub width: u32,
+    pub height: u32,
+    pub x: i32,
+    pub y: i32,
+    pub scale_factor: f64,
+    /// Degrees clockwise (0, 90, 180, 270)
+    pub rotation: f32,
+    /// Refresh rate in Hz, 0 when the OS doesn't report it
+    pub refresh_rate: f32,
+    pub is_primary: bool,
+}
+
+/// Axis-aligned rectangle — screen coordinates for captures, pixels for image edits
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub w: u32,
+    pub h: u32,
+}
+
+impl Rect {
+    /// Multiply by a DPI scale factor. Edges are rounded rather than the size,
+    /// so adjacent rectangles stay adjacent at fractional scales like 125%.
+    pub fn scaled(self, factor: f64) -> Rect {
+        let left = (self.x as f64 * factor).round();
+        let top = (self.y as f64 * factor).round();
+        let right = ((self.x as f64 + self.w as f64) * factor).round();
+        let bottom = ((self.y as f64 + self.h as f64) * factor).round();
+        Rect {
+            x: left as i32,
+            y: top as i32,
+            w: (right - left).max(0.0) as u32,
+            h: (bottom - top).max(0.0) as u32,
+        }
+    }
+
+    /// Round the edges to the nearest multiple of `grid`, counted from the
+    /// origin of `bounds` (the display) when given. Edges that would land
+    /// outside `bounds` move inward to the nearest grid line instead; if no
+    /// whole cell fits the rectangle is returned unchanged.
+    pub fn snapped(self, grid: u32, bounds: Option<Rect>) -> Rect {
+        if grid <= 1 {
+            return self;
+        }
+        let g = grid as i64;
+        let (ox, oy) = bounds.map_or((0, 0), |b| (b.x as i64, b.y as i64));
+        let nearest = |v: i64, o: i64| o + ((v - o) as f64 / g as f64).round() as i64 * g;
+        let (mut left, mut top) = (nearest(self.x as i64, ox), nearest(self.y as i64, oy));
+        let mut right = nearest(self.x as i64 + self.w as i64, ox).max(left + g);
+        let mut bottom = nearest(self.y as i64 + self.h as i64, oy).max(top + g);
+        if let Some(b) = bounds {
+            let (bx, by) = (b.x as i64, b.y as i64);
+            let (bright, bbottom) = (bx + b.w as i64, by + b.h as i64);
+            let floor = |v: i64, o: i64| o + (v - o).div_euclid(g) * g;
+            left = left.max(bx);
+            top = top.max(by);
+            right = right.min(floor(bright, bx));
+            bottom = bottom.min(floor(bbottom, by));
+            if right <= left || bottom <= top {
+                return self;
+            }
+        }
+        Rect { x: left as i32, y: top as i32, w: (right - left) as u32, h: (bottom - top) as u32 }
+    }
+
+    /// Set the height from the width so that width / height is `ratio`, with
+    /// the top-left corner fixed. If that runs past `bounds` (the display) both
+    /// sides shrink together until it fits.
+    pub fn with_aspect(self, ratio: f64, bounds: Option<Rect>) -> Rect {
+        let (mut w, mut h) = (self.w as f64, self.w as f64 / ratio);
+        if let Some(b) = bounds {
+            let room_w = (b.x as i64 + b.w as i64 - self.x as i64).max(1) as f64;
+            let room_h = (b.y as i64 + b.h as i64 - self.y as i64).max(1) as f64;
+            let fit = (room_w / w).min(room_h / h).min(1.0);
+            w *= fit;
+            h *= fit;
+        }
+        Rect { x: self.x, y: self.y, w: (w.round() as u32).max(1), h: (h.round() as u32).max(1) }
+    }
+}
+
+/// Post-processing applied before the image leaves the backend
+#[derive(Clone, Copy)]
+pub struct CaptureOptions {
+    pub format: OutputFormat,
+    /// Ignored on Wayland, where the compositor decides whether the portal
+    /// screenshot shows the pointer
+    pub include_cursor: bool,
+    pub color_mode: ColorMode,
+    /// Attach a `DEFAULT_THUMBNAIL_SIZE` preview to the payload
+    pub with_thumbnail: bool,
+    /// Refuse (or shrink) captures above this size; `None` = unlimited
+    pub limit: Option<PixelLimit>,
+    /// Only used when `format` is PNG
+    pub png_compression: PngCompression,
+    /// Only used when `format` is JPEG
+    pub jpeg_subsampling: JpegSubsampling,
+    /// Turn captures of rotated displays upright when the OS hands them over
+    /// sideways (`screencapture` on macOS always returns them as shown)
+    #[cfg_attr(target_os = "macos", allow(dead_code))]
+    pub auto_rotate: bool,
+}
+
+/// Size cap checked before a capture allocates its pixels (`Config::max_pixels`)
+#[derive(Clone, Copy)]
+pub struct PixelLimit {
+    pub max_pixels: u64,
+    /// Scale oversized captures down to fit instead of failing
+    pub downscale: bool,
+}
+
+impl PixelLimit {
+    /// Factor (at most 1) a `width`×`height` capture must be scaled by to fit
+    fn fit(self, width: u32, height: u32) -> Result<f64, AppError> {
+        let pixels = width as u64 * height as u64;
+        if pixels <= self.max_pixels {
+            return Ok(1.0);
+        }
+        if !self.downscale {
+            return Err(AppError::CaptureTooLarge { width, height, max_pixels: self.max_pixels });
+        }
+        Ok((self.max_pixels as f64 / pixels as f64).sqrt())
+    }
+}
+
+/// `PixelLimit::fit` for the options' limit, 1.0 when there is none
+fn fit_factor(options: CaptureOptions, width: u32, height: u32) -> Result<f64, AppError> {
+    options.limit.map_or(Ok(1.0), |limit| limit.fit(width, height))
+}
+
+/// Scale `image` by `factor` (< 1) to respect a `PixelLimit`
+fn shrink(image: image::RgbaImage, factor: f64) -> image::RgbaImage {
+    if factor >= 1.0 {
+        return image;
+    }
+    // Floor so rounding never lands just above the limit
+    let w = ((image.width() as f64 * factor).floor() as u32).max(1);
+    let h = ((image.height() as f64 * factor).floor() as u32).max(1);
+    image::imageops::resize(&image, w, h, image::imageops::FilterType::Triangle)
+}
+
+/// Pixel size of a display before grabbing it: display-info reports points
+/// on macOS and physical pixels elsewhere
+fn physical_size(di: &display_info::DisplayInfo) -> (u32, u32) {
+    let ppu = if cfg!(target_os = "macos") { di.scale_factor as f64 } else { 1.0 };
+    ((di.width as f64 * ppu).round() as u32, (di.height as f64 * ppu).round() as u32)
+}
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
+        CaptureOptions {
+            format: OutputFormat::Png,
+            include_cursor: false,
+            color_mode: ColorMode::Rgba,
+            with_thumbnail: false,
+            limit: None,
+            png_compression: PngCompression::Default,
+            jpeg_subsampling: JpegSubsampling::default(),
+            auto_rotate: true,
+        }
+    }
+}
+
+/// Apply the colour mode and encode
+fn encode(image: &image::RgbaImage, options: CaptureOptions) -> Result<Vec<u8>, AppError> {
+    imaging::encode_color(image, options.format, options.color_mode, options.png_compression, options.jpeg_subsampling)
+}
+
+/// Resolve a monitor index into `DisplayInfo::all()` order.
+/// `None` picks the primary display (or the first one if none is flagged primary).
+pub fn resolve_monitor_index(displays: &[display_info::DisplayInfo], monitor_index: Option<usize>) -> Result<usize, AppError> {
+    match monitor_index {
+        Some(i) if i < displays.len() => Ok(i),
+        Some(i) => Err(AppError::MonitorNotFound(i)),
+        None if displays.is_empty() => Err(AppError::NoScreen),
+        None => Ok(displays.iter().position(|d| d.is_primary).unwrap_or(0)),
+    }
+}
+
+/// False when the OS reports no displays at all, as in headless and some
+/// remote-desktop sessions. An error listing them counts as "maybe": the
+/// capture itself will say what went wrong.
+pub fn has_display() -> bool {
+    display_info::DisplayInfo::all().map_or(true, |displays| !displays.is_empty())
+}
+
+/// Bounds, in logical coordinates at `scale`, of the display under the
+/// centre of `rect` (also logical)
+pub fn display_bounds(rect: Rect, scale: f64) -> Option<Rect> {
+    let displays = display_info::DisplayInfo::all().ok()?;
+    // display-info reports points on macOS, physical pixels elsewhere
+    let to_logical = if cfg!(target_os = "macos") { 1.0 } else { 1.0 / scale.max(0.01) };
+    let (cx, cy) = (rect.x + rect.w as i32 / 2, rect.y + rect.h as i32 / 2);
+    displays
+        .iter()
+        .map(|d| Rect { x: d.x, y: d.y, w: d.width, h: d.height }.scaled(to_logical))
+        .find(|b| cx >= b.x && cy >= b.y && cx < b.x + b.w as i32 && cy < b.y + b.h as i32)
+}
+
+/// Bounds of display `index` as `monitors` reports them, `None` when no such
+/// display is connected (or the displays can't be listed)
+pub fn monitor_rect(index: usize) -> Option<Rect> {
+    let displays = display_info::DisplayInfo::all().ok()?;
+    displays.get(index).map(|d| Rect { x: d.x, y: d.y, w: d.width, h: d.height })
+}
+
+/// All connected displays in `DisplayInfo::all()` order
+pub fn monitors() -> Result<Vec<MonitorInfo>, AppError> {
+    let displays = display_info::DisplayInfo::all().map_err(|e| AppError::CaptureFailed(e.to_string()))?;
+    Ok(displays
+        .iter()
+        .enumerate()
+        .map(|(index, d)| MonitorInfo {
+            index,
+            width: d.width,
+            height: d.height,
+            x: d.x,
+            y: d.y,
+            is_primary: d.is_primary,
+        })
+        .collect())
+}
+
+/// Geometry of all connected displays in `DisplayInfo::all()` order
+pub fn screens() -> Result<Vec<ScreenInfo>, AppError> {
+    let displays = display_info::DisplayInfo::all().map_err(|e| AppError::CaptureFailed(e.to_string()))?;
+    Ok(displays
+        .iter()
+        .enumerate()
+        .map(|(index, d)| ScreenInfo {
+            index,
+            width: d.width,
+            height: d.height,
+            x: d.x,
+            y: d.y,
+            scale_factor: d.scale_factor as f64,
+            rotation: d.rotation,
+            refresh_rate: d.frequency,
+            is_primary: d.is_primary,
+        })
+        .collect())
+}
+
+/// `scale` is the physical-pixels-per-logical-unit ratio of the captured display
+fn payload(bytes: &[u8], options: CaptureOptions, mode: &str, monitor_index: Option<usize>, timing: Timing, scale: f64) -> CapturePayload {
+    let (width, height) = imaging::dimensions(bytes).unwrap_or((0, 0));
+    let logical = |px: u32| (px as f64 / scale.max(0.01)).round() as u32;
+    let thumbnail = if options.with_thumbnail {
+        image::load_from_memory(bytes)
+            .ok()
+            .and_then(|image| imaging::thumbnail(&image.to_rgba8(), imaging::DEFAULT_THUMBNAIL_SIZE).ok())
+    } else {
+        None
+    };
+    CapturePayload {
+        data_url: imaging::to_data_url(bytes, options.format),
+        mode: mode.to_string(),
+        monitor_index,
+        width,
+        height,
+        logical_width: logical(width),
+        logical_height: logical(height),
+        capture_ms: timing.capture_ms,
+        encode_ms: timing.encode_ms,
+        thumbnail,
+        rotation: monitor_index.map_or(0, display_rotation),
+        work_area: None,
+    }
+}
+
+/// Wrap an image that was produced elsewhere (e.g. cropped by the overlay)
+pub fn payload_from_data_url(data_url: String, mode: String) -> CapturePayload {
+    let (width, height) = imaging::data_url_bytes(&data_url)
+        .ok()
+        .and_then(|bytes| imaging::dimensions(&bytes))
+        .unwrap_or((0, 0));
+    CapturePayload {
+        data_url,
+        mode,
+        monitor_index: None,
+        width,
+        height,
+        logical_width: width,
+        logical_height: height,
+        capture_ms: 0,
+        encode_ms: 0,
+        thumbnail: None,
+        rotation: 0,
+        work_area: None,
+    }
+}
+
+/// Display rotation in clockwise degrees, normalized to 0, 90, 180 or 270
+fn rotation_degrees(di: &display_info::DisplayInfo) -> u16 {
+    ((di.rotation.round() as i32).rem_euclid(360) as u16 + 45) / 90 % 4 * 90
+}
+
+/// Rotation of display `index` in `DisplayInfo::all()` order, 0 if unknown
+fn display_rotation(index: usize) -> u16 {
+    display_info::DisplayInfo::all()
+        .ok()
+        .and_then(|displays| displays.get(index).map(rotation_degrees))
+        .unwrap_or(0)
+}
+
+/// Undo a sideways capture of a display rotated by 90° or 270°. Backends
+/// that already return the image as shown on screen are detected by its
+/// orientation matching the display's and left alone. 180° gives no such
+/// hint and every supported backend returns it upright, so it is not touched.
+#[cfg(not(target_os = "macos"))]
+fn upright(image: image::RgbaImage, di: &display_info::DisplayInfo) -> image::RgbaImage {
+    let image_portrait = image.height() > image.width();
+    let display_portrait = di.height > di.width;
+    if image.width() == image.height() || image_portrait == display_portrait {
+        return image;
+    }
+    match rotation_degrees(di) {
+        90 => {
+            log::debug!("Rotating capture of display {} by 90°", di.id);
+            image::imageops::rotate90(&image)
+        }
+        270 => {
+            log::debug!("Rotating capture of display {} by 270°", di.id);
+            image::imageops::rotate270(&image)
+        }
+        _ => image,
+    }
+}
+
+/// Scale factor of display `index`, or of the primary display
+#[cfg(target_os = "macos")]
+fn display_scale(index: Option<usize>) -> f64 {
+    let displays = display_info::DisplayInfo::all().unwrap_or_default();
+    resolve_monitor_index(&displays, index)
+        .map(|i| displays[i].scale_factor as f64)
+        .unwrap_or(1.0)
+}
+
+/// Run a capture or encode step and report how long it took in milliseconds
+fn timed<T>(f: impl FnOnce() -> Result<T, AppError>) -> Result<(T, u64), AppError> {
+    let start = std::time::Instant::now();
+    let value = f()?;
+    Ok((value, start.elapsed().as_millis() as u64))
+}
+
+/// Run `screencapture` with `args` and return the encoded result.
+/// -x = no sound, -C = capture cursor, -t png = format
+#[cfg(target_os = "macos")]
+fn screencapture(args: &[&str], options: CaptureOptions) -> Result<(Vec<u8>, Timing), AppError> {
+    use std::process::Command;
+    let start = std::time::Instant::now();
+    let tmp_path = std::env::temp_dir().join("screenai_capture.png");
+    let tmp_str = tmp_path
+        .to_str()
+        .ok_or_else(|| AppError::CaptureFailed("invalid temp path".to_string()))?;
+
+    let mut all_args = vec!["-x", "-t", "png"];
+    if options.include_cursor {
+        all_args.push("-C");
+    }
+    all_args.extend_from_slice(args);
+    all_args.push(tmp_str);
+
+    let output = Command::new("screencapture")
+        .args(&all_args)
+        .output()
+        .map_err(|e| AppError::CaptureFailed(format!("failed to run screencapture: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::CaptureFailed(format!("screencapture failed: {}", stderr)));
+    }
+
+    // Read the PNG file written by screencapture — missing means the user pressed Escape
+    if !tmp_path.exists() {
+        return Err(AppError::CaptureCancelled);
+    }
+
+    let png_bytes = std::fs::read(&tmp_path)
+        .map_err(|e| AppError::CaptureFailed(format!("failed to read capture: {}", e)))?;
+
+    // Clean up temp file
+    let _ = std::fs::remove_file(&tmp_path);
+
+    if png_bytes.is_empty() {
+        return Err(AppError::CaptureFailed("capture produced empty file".to_string()));
+    }
+
+    let capture_ms = start.elapsed().as_millis() as u64;
+    // screencapture's own PNG is kept unless asked for a smaller one, "fast" included:
+    // re-encoding could never be quicker than reusing it. Other formats are decoded anyway
+    let recompress = options.format != OutputFormat::Png || options.png_compression == PngCompression::Best;
+    let (bytes, encode_ms) = timed(|| match options.color_mode {
+        ColorMode::Rgba if !recompress => imaging::transcode(&png_bytes, options.format),
+        _ => {
+            let image = image::load_from_memory(&png_bytes).map_err(|e| AppError::DecodeFailed(e.to_string()))?;
+            encode(&image.to_rgba8(), options)
+        }
+    })?;
+    Ok((bytes, Timing { capture_ms, encode_ms }))
+}
+
+/// Capture a whole display with the `screenshots` crate, cursor included if requested.
+/// Returns the image plus the scale from display units to image pixels.
+#[cfg(not(target_os = "macos"))]
+fn grab_display(screen: &screenshots::Screen, options: CaptureOptions) -> Result<(image::RgbaImage, f64), AppError> {
+    let di = screen.display_info;
+    log::debug!("Screen {}: {}x{} (scale_factor: {})", di.id, di.width, di.height, di.scale_factor);
+    #[cfg(target_os = "linux")]
+    if crate::portal::is_wayland() {
+        return grab_display_wayland(di);
+    }
+    let mut image = capture_with_timeout(*screen)?;
+    if options.auto_rotate {
+        image = upright(image, &di);
+    }
+    log::debug!("Captured image: {}x{}", image.width(), image.height());
+
+    let pixels_per_unit = image.width() as f64 / di.width.max(1) as f64;
+    if options.include_cursor {
+        if let Some(sprite) = crate::cursor::current_cursor(di.scale_factor) {
+            crate::cursor::composite(&mut image, &sprite, (di.x, di.y), pixels_per_unit);
+        }
+    }
+    Ok((image, pixels_per_unit))
+}
+
+/// Longest `screen.capture()` may take, 0 = no limit; see `set_capture_timeout`
+static CAPTURE_TIMEOUT_MS: AtomicU64 = AtomicU64::new(5000);
+
+/// Apply `Config::capture_timeout_ms`. macOS grabs through `screencapture`,
+/// which isn't affected.
+pub fn set_capture_timeout(ms: u64) {
+    CAPTURE_TIMEOUT_MS.store(ms, Ordering::Relaxed);
+}
+
+/// Grab threads given up on while stuck in `screen.capture()`; at most
+/// `MAX_STUCK_WORKERS` are left behind before grabs are refused
+#[cfg(not(target_os = "macos"))]
+static STUCK_WORKERS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+#[cfg(not(target_os = "macos"))]
+const MAX_STUCK_WORKERS: usize = 2;
+
+#[cfg(not(target_os = "macos"))]
+type GrabJob = (screenshots::Screen, std::sync::mpsc::Sender<Result<image::RgbaImage, String>>);
+
+/// The thread every timed grab runs on, so recording, replay and the overlay
+/// preview don't start one per frame
+#[cfg(not(target_os = "macos"))]
+struct GrabWorker {
+    jobs: std::sync::mpsc::Sender<GrabJob>,
+    /// Set when a grab on this thread timed out and it was replaced
+    abandoned: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+#[cfg(not(target_os = "macos"))]
+static GRAB_WORKER: std::sync::Mutex<Option<GrabWorker>> = std::sync::Mutex::new(None);
+
+#[cfg(not(target_os = "macos"))]
+impl GrabWorker {
+    fn spawn() -> Result<GrabWorker, AppError> {
+        let (jobs, queue) = std::sync::mpsc::channel::<GrabJob>();
+        let abandoned = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let flag = abandoned.clone();
+        std::thread::Builder::new()
+            .name("screen-grab".to_string())
+            .spawn(move || {
+                for (screen, reply) in queue {
+                    let _ = reply.send(screen.capture().map_err(|e| e.to_string()));
+                    if flag.load(Ordering::SeqCst) {
+                        break;
+                    }
+                }
+                if flag.load(Ordering::SeqCst) {
+                    let left = STUCK_WORKERS.fetch_sub(1, Ordering::SeqCst) - 1;
+                    log::info!("A stuck screen grab returned after all; {} grab thread(s) still stuck", left);
+                }
+            })
+            .map_err(|e| AppError::CaptureFailed(format!("could not start the capture thread: {}", e)))?;
+        Ok(GrabWorker { jobs, abandoned })
+    }
+}
+
+/// `screen.capture()` on the grab worker, given up on after `CAPTURE_TIMEOUT_MS`.
+/// Some drivers never return from it, typically right after waking from sleep;
+/// the stuck thread is left behind and a fresh one takes the next grab, so the
+/// capture flow (and the hidden main window) can recover. Past
+/// `MAX_STUCK_WORKERS` stuck threads, grabs fail straight away.
+#[cfg(not(target_os = "macos"))]
+fn capture_with_timeout(screen: screenshots::Screen) -> Result<image::RgbaImage, AppError> {
+    let ms = CAPTURE_TIMEOUT_MS.load(Ordering::Relaxed);
+    if ms == 0 {
+        return screen.capture().map_err(|e| AppError::CaptureFailed(e.to_string()));
+    }
+    let id = screen.display_info.id;
+    let (reply, result) = std::sync::mpsc::channel();
+    let abandoned = {
+        let mut worker = GRAB_WORKER.lock().unwrap_or_else(|e| e.into_inner());
+        let mut job = (screen, reply);
+        loop {
+            if worker.is_none() {
+                let stuck = STUCK_WORKERS.load(Ordering::SeqCst);
+                if stuck >= MAX_STUCK_WORKERS {
+                    return Err(AppError::CaptureFailed(format!(
+                        "{} earlier screen grabs never returned; restart ScreenAI to capture again",
+                        stuck
+                    )));
+                }
+                *worker = Some(GrabWorker::spawn()?);
+            }
+            let current = worker.as_ref().expect("worker was just set");
+            match current.jobs.send(job) {
+                Ok(()) => break current.abandoned.clone(),
+                // The thread is gone (a panic in the driver); start another
+                Err(std::sync::mpsc::SendError(returned)) => {
+                    job = returned;
+                    *worker = None;
+                }
+            }
+        }
+    };
+    match result.recv_timeout(std::time::Duration::from_millis(ms)) {
+        Ok(result) => result.map_err(AppError::CaptureFailed),
+        Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+            Err(AppError::CaptureFailed("the capture thread stopped".to_string()))
+        }
+        Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+            let mut worker = GRAB_WORKER.lock().unwrap_or_else(|e| e.into_inner());
+            // Grabs queued behind the stuck one time out too; replace the thread once
+            if worker.as_ref().is_some_and(|w| std::sync::Arc::ptr_eq(&w.abandoned, &abandoned)) {
+                // Counted before flagging, so a thread returning right now can't count down first
+                let stuck = STUCK_WORKERS.fetch_add(1, Ordering::SeqCst) + 1;
+                abandoned.store(true, Ordering::SeqCst);
+                *worker = None;
+                log::error!("Screen {} did not return a capture within {} ms; {} grab thread(s) stuck", id, ms, stuck);
+            } else {
+                log::error!("Screen {} did not return a capture within {} ms", id, ms);
+            }
+            Err(AppError::CaptureTimeout(ms))
+        }
+    }
+}
+
+/// Wayland: the portal returns the whole desktop, so cut out display `di`.
+/// The compositor decides whether the cursor is drawn.
+#[cfg(target_os = "linux")]
+fn grab_display_wayland(di: display_info::DisplayInfo) -> Result<(image::RgbaImage, f64), AppError> {
+    let desktop = crate::portal::screenshot()?;
+    let displays = display_info::DisplayInfo::all().map_err(|e| AppError::CaptureFailed(e.to_string()))?;
+    cut_display(&desktop, &displays, di)
+}
+
+/// Cut display `di` out of a portal screenshot of the whole desktop
+#[cfg(target_os = "linux")]
+fn cut_display(
+    desktop: &image::RgbaImage,
+    displays: &[display_info::DisplayInfo],
+    di: display_info::DisplayInfo,
+) -> Result<(image::RgbaImage, f64), AppError> {
+    let min_x = displays.iter().map(|d| d.x).min().unwrap_or(di.x);
+    let min_y = displays.iter().map(|d| d.y).min().unwrap_or(di.y);
+    let max_x = displays.iter().map(|d| d.x + d.width as i32).max().unwrap_or(di.x + di.width as i32);
+
+    let pixels_per_unit = desktop.width() as f64 / (max_x - min_x).max(1) as f64;
+    let area = Rect { x: di.x - min_x, y: di.y - min_y, w: di.width, h: di.height }.scaled(pixels_per_unit);
+    let left = (area.x.max(0) as u32).min(desktop.width());
+    let top = (area.y.max(0) as u32).min(desktop.height());
+    let w = area.w.min(desktop.width() - left);
+    let h = area.h.min(desktop.height() - top);
+    if w == 0 || h == 0 {
+        return Err(AppError::CaptureFailed("display is outside the portal screenshot".to_string()));
+    }
+    let image = image::imageops::crop_imm(desktop, left, top, w, h).to_image();
+    Ok((image, pixels_per_unit))
+}
+
+/// Capture display `monitor_index` (default: primary) and encode it.
+/// Returns the bytes, the resolved index, timings and the display's scale factor.
+fn screen_bytes(monitor_index: Option<usize>, options: CaptureOptions) -> Result<(Vec<u8>, usize, Timing, f64), AppError> {
+    #[cfg(target_os = "macos")]
+    {
+        // -D = display number (1-based, same order as DisplayInfo::all())
+        let displays = display_info::DisplayInfo::all()
+            .map_err(|e| AppError::CaptureFailed(e.to_string()))?;
+        let index = resolve_monitor_index(&displays, monitor_index)?;
+        let (width, height) = physical_size(&displays[index]);
+        if fit_factor(options, width, height)? < 1.0 {
+            // Has to be decoded to shrink it anyway
+            let (image, index, capture_ms, scale) = grab_screen(Some(index), options)?;
+            let (bytes, encode_ms) = timed(|| encode(&image, options))?;
+            return Ok((bytes, index, Timing { capture_ms, encode_ms }, scale));
+        }
+        let display_arg = (index + 1).to_string();
+        let (bytes, timing) = screencapture(&["-D", &display_arg], options)?;
+        Ok((bytes, index, timing, displays[index].scale_factor as f64))
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let (image, index, capture_ms, scale) = grab_screen(monitor_index, options)?;
+        let (bytes, encode_ms) = timed(|| encode(&image, options))?;
+        Ok((bytes, index, Timing { capture_ms, encode_ms }, scale))
+    }
+}
+
+/// Capture display `monitor_index` (default: primary) as unencoded pixels.
+/// Returns the image, the resolved index, the capture time and the display's scale factor.
+pub fn grab_screen(monitor_index: Option<usize>, options: CaptureOptions) -> Result<(image::RgbaImage, usize, u64, f64), AppError> {
+    #[cfg(target_os = "macos")]
+    {
+        // screencapture only writes files, so this is a PNG round trip
+        let displays = display_info::DisplayInfo::all().map_err(|e| AppError::CaptureFailed(e.to_string()))?;
+        let index = resolve_monitor_index(&displays, monitor_index)?;
+        let (width, height) = physical_size(&displays[index]);
+        let factor = fit_factor(options, width, height)?;
+        let display_arg = (index + 1).to_string();
+        let png = CaptureOptions { format: OutputFormat::Png, ..options };
+        let ((bytes, _), capture_ms) = timed(|| screencapture(&["-D", &display_arg], png))?;
+        let image = image::load_from_memory(&bytes).map_err(|e| AppError::DecodeFailed(e.to_string()))?;
+        Ok((shrink(image.to_rgba8(), factor), index, capture_ms, displays[index].scale_factor as f64 * factor))
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        use screenshots::Screen;
+
+        let screens = Screen::all().map_err(|e| AppError::CaptureFailed(e.to_string()))?;
+        let displays: Vec<_> = screens.iter().map(|s| s.display_info).collect();
+        let index = resolve_monitor_index(&displays, monitor_index)?;
+        let (width, height) = physical_size(&displays[index]);
+        let factor = fit_factor(options, width, height)?;
+        let ((image, _), capture_ms) = timed(|| grab_display(&screens[index], options))?;
+        Ok((shrink(image, factor), index, capture_ms, displays[index].scale_factor as f64 * factor))
+    }
+}
+
+/// Above this size `capture_screen_raw` hands back a file instead of base64
+const RAW_INLINE_LIMIT: usize = 16 * 1024 * 1024;
+
+/// Result of `capture_screen_raw`: row-major RGBA, 4 bytes per pixel, either
+/// inline as base64 (`rgba`) or in a file in the app cache dir (`path`) for large displays
+#[derive(Clone, Serialize)]
+pub struct RawCapture {
+    pub width: u32,
+    pub height: u32,
+    pub monitor_index: usize,
+    pub capture_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rgba: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// Capture a display without encoding it. `as_file` forces the file transport
+/// (a `raw-<monitor>.rgba` in `cache_dir`); otherwise it is used only past
+/// `RAW_INLINE_LIMIT`.
+pub fn capture_screen_raw(
+    monitor_index: Option<usize>,
+    as_file: bool,
+    cache_dir: Option<&std::path::Path>,
+    options: CaptureOptions,
+) -> Result<RawCapture, AppError> {
+    use base64::Engine;
+
+    let (image, index, capture_ms, _) = grab_screen(monitor_index, options)?;
+    let (width, height) = image.dimensions();
+    let pixels = image.into_raw();
+    let mut raw = RawCapture { width, height, monitor_index: index, capture_ms, rgba: None, path: None };
+    if as_file || pixels.len() > RAW_INLINE_LIMIT {
+        let dir = cache_dir.ok_or_else(|| AppError::Io("No app cache directory".to_string()))?;
+        std::fs::create_dir_all(dir).map_err(|e| AppError::Io(format!("{}: {}", dir.display(), e)))?;
+        // Overwritten by the next raw capture, so preview loops don't fill the cache dir
+        let path = dir.join(format!("{}{}{}", RAW_FILE_PREFIX, index, RAW_FILE_SUFFIX));
+        std::fs::write(&path, &pixels).map_err(|e| AppError::Io(format!("{}: {}", path.display(), e)))?;
+        raw.path = Some(path.to_string_lossy().to_string());
+    } else {
+        raw.rgba = Some(base64::engine::general_purpose::STANDARD.encode(&pixels));
+    }
+    Ok(raw)
+}
+
+const RAW_FILE_PREFIX: &str = "raw-";
+const RAW_FILE_SUFFIX: &str = ".rgba";
+
+/// Delete `capture_screen_raw` files in `cache_dir` not rewritten for `max_age`.
+/// A running preview loop rewrites its file every frame, so it is never hit.
+pub fn remove_stale_raw_files(cache_dir: &std::path::Path, max_age: std::time::Duration) -> u32 {
+    let mut removed = 0;
+    for entry in std::fs::read_dir(cache_dir).into_iter().flatten().flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !(name.starts_with(RAW_FILE_PREFIX) && name.ends_with(RAW_FILE_SUFFIX)) {
+            continue;
+        }
+        let stale = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .is_ok_and(|modified| modified.elapsed().unwrap_or_default() > max_age);
+        if stale && std::fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+/// Capture a full display (`mode` = "fullscreen") or, on macOS, run the native
+/// interactive selection (`mode` = "region").
+pub fn native_capture(mode: &str, monitor_index: Option<usize>, options: CaptureOptions) -> Result<CapturePayload, AppError> {
+    // -i = interactive selection (for region mode)
+    #[cfg(target_os = "macos")]
+    if mode == "region" {
+        let (bytes, timing) = screencapture(&["-i"], options)?;
+        return Ok(payload(&bytes, options, mode, None, timing, display_scale(None)));
+    }
+
+    let (bytes, index, timing, scale) = screen_bytes(monitor_index, options)?;
+    Ok(payload(&bytes, options, mode, Some(index), timing, scale))
+}
+
+/// Capture display `monitor_index` with the given windows painted over with
+/// `fill`. Only the parts of each window that are actually visible are
+/// covered, so windows stacked in front of an excluded one stay intact.
+pub fn capture_screen_excluding(
+    monitor_index: Option<usize>,
+    exclude: &[u64],
+    fill: image::Rgba<u8>,
+    options: CaptureOptions,
+) -> Result<CapturePayload, AppError> {
+    let displays = display_info::DisplayInfo::all().map_err(|e| AppError::CaptureFailed(e.to_string()))?;
+    let index = resolve_monitor_index(&displays, monitor_index)?;
+    let di = displays[index];
+    let start = std::time::Instant::now();
+
+    #[cfg(target_os = "macos")]
+    let mut image = {
+        let display_arg = (index + 1).to_string();
+        let png = CaptureOptions { format: OutputFormat::Png, ..options };
+        let (bytes, _) = screencapture(&["-D", &display_arg], png)?;
+        image::load_from_memory(&bytes).map_err(|e| AppError::DecodeFailed(e.to_string()))?.to_rgba8()
+    };
+    #[cfg(not(target_os = "macos"))]
+    let mut image = {
+        let screens = screenshots::Screen::all().map_err(|e| AppError::CaptureFailed(e.to_string()))?;
+        let screen = screens
+            .iter()
+            .find(|s| s.display_info.id == di.id)
+            .ok_or(AppError::MonitorNotFound(index))?;
+        grab_display(screen, options)?.0
+    };
+    let capture_ms = start.elapsed().as_millis() as u64;
+
+    // Window bounds use the same units as display bounds
+    let pixels_per_unit = image.width() as f64 / di.width.max(1) as f64;
+    let to_image = |w: &crate::window_list::WindowInfo| {
+        Rect { x: w.x - di.x, y: w.y - di.y, w: w.w.max(0) as u32, h: w.h.max(0) as u32 }.scaled(pixels_per_unit)
+    };
+    let windows = crate::window_list::list();
+    for target in windows.iter().filter(|w| exclude.contains(&w.id)) {
+        let in_front: Vec<Rect> = windows
+            .iter()
+            .filter(|w| w.z_order < target.z_order && !exclude.contains(&w.id))
+            .map(to_image)
+            .collect();
+        let Some((x, y, w, h)) = crate::edit::clip(&image, to_image(target)) else { continue };
+        // Fill the whole window, then put back what the windows in front show
+        let original = image::imageops::crop_imm(&image, x, y, w, h).to_image();
+        for py in y..y + h {
+            for px in x..x + w {
+                image.put_pixel(px, py, fill);
+            }
+        }
+        for r in &in_front {
+            // Overlap with the filled area, empty ranges when there is none
+            let left = (r.x as i64).clamp(x as i64, (x + w) as i64) as u32;
+            let top = (r.y as i64).clamp(y as i64, (y + h) as i64) as u32;
+            let right = (r.x as i64 + r.w as i64).clamp(x as i64, (x + w) as i64) as u32;
+            let bottom = (r.y as i64 + r.h as i64).clamp(y as i64, (y + h) as i64) as u32;
+            for py in top..bottom {
+                for px in left..right {
+                    image.put_pixel(px, py, *original.get_pixel(px - x, py - y));
+                }
+            }
+        }
+    }
+
+    let factor = fit_factor(options, image.width(), image.height())?;
+    let image = shrink(image, factor);
+    let (bytes, encode_ms) = timed(|| encode(&image, options))?;
+    Ok(payload(&bytes, options, "fullscreen", Some(index), Timing { capture_ms, encode_ms }, di.scale_factor as f64 * factor))
+}
+
+/// Result of `capture_screen_to_file` — everything but the image itself
+#[derive(Clone, Serialize)]
+pub struct SavedCapture {
+    pub path: String,
+    pub monitor_index: usize,
+    pub width: u32,
+    pub height: u32,
+    pub capture_ms: u64,
+    pub encode_ms: u64,
+}
+
+/// Capture a display straight to `path`, skipping the base64 data URL
+pub fn capture_screen_to_file(path: &std::path::Path, monitor_index: Option<usize>, options: CaptureOptions) -> Result<SavedCapture, AppError> {
+    let (bytes, index, timing, _) = screen_bytes(monitor_index, options)?;
+    let (width, height) = imaging::dimensions(&bytes).unwrap_or((0, 0));
+    crate::disk::ensure_space(path, bytes.len() as u64)?;
+    std::fs::write(path, &bytes).map_err(|e| AppError::Io(format!("{}: {}", path.display(), e)))?;
+    Ok(SavedCapture {
+        path: path.to_string_lossy().to_string(),
+        monitor_index: index,
+        width,
+        height,
+        capture_ms: timing.capture_ms,
+        encode_ms: timing.encode_ms,
+    })
+}
+
+/// Capture every display and stitch them into one image laid out like the
+/// virtual desktop. Displays left of / above the primary have negative
+/// coordinates, so the canvas origin is the top-left-most display corner.
+pub fn capture_all_monitors(options: CaptureOptions) -> Result<CapturePayload, AppError> {
+    let mut grabs: Vec<(display_info::DisplayInfo, image::RgbaImage)> = Vec::new();
+    let start = std::time::Instant::now();
+
+    // Check the stitched size before grabbing anything
+    let displays = display_info::DisplayInfo::all().map_err(|e| AppError::CaptureFailed(e.to_string()))?;
+    let factor = {
+        let density = displays
+            .iter()
+            .map(|di| physical_size(di).0 as f64 / di.width.max(1) as f64)
+            .fold(1.0, f64::max);
+        let span = |lo: i32, hi: i32| ((hi - lo).max(0) as f64 * density).round() as u32;
+        let width = span(
+            displays.iter().map(|d| d.x).min().unwrap_or(0),
+            displays.iter().map(|d| d.x + d.width as i32).max().unwrap_or(0),
+        );
+        let height = span(
+            displays.iter().map(|d| d.y).min().unwrap_or(0),
+            displays.iter().map(|d| d.y + d.height as i32).max().unwrap_or(0),
+        );
+        fit_factor(options, width, height)?
+    };
+
+    #[cfg(target_os = "macos")]
+    {
+        let png = CaptureOptions { format: OutputFormat::Png, ..options };
+        for (index, di) in displays.into_iter().enumerate() {
+            let display_arg = (index + 1).to_string();
+            let (bytes, _) = screencapture(&["-D", &display_arg], png)?;
+            let image = image::load_from_memory(&bytes).map_err(|e| AppError::DecodeFailed(e.to_string()))?;
+            grabs.push((di, image.to_rgba8()));
+        }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    grabs.extend(grab_displays_parallel(options)?);
+
+    if grabs.is_empty() {
+        return Err(AppError::NoScreen);
+    }
+    let capture_ms = start.elapsed().as_millis() as u64;
+
+    // Lay out in display units at the sharpest display's pixel density,
+    // reduced to fit the pixel limit
+    let scale = grabs
+        .iter()
+        .map(|(di, image)| image.width() as f64 / di.width.max(1) as f64)
+        .fold(1.0, f64::max)
+        * factor;
+    let min_x = grabs.iter().map(|(di, _)| di.x).min().unwrap_or(0);
+    let min_y = grabs.iter().map(|(di, _)| di.y).min().unwrap_or(0);
+    let max_x = grabs.iter().map(|(di, _)| di.x + di.width as i32).max().unwrap_or(0);
+    let max_y = grabs.iter().map(|(di, _)| di.y + di.height as i32).max().unwrap_or(0);
+    let to_px = |units: i32| (units as f64 * scale).round() as u32;
+
+    let mut canvas = image::RgbaImage::new(to_px(max_x - min_x), to_px(max_y - min_y));
+    for (di, image) in grabs {
+        let (w, h) = (to_px(di.width as i32), to_px(di.height as i32));
+        let image = if image.dimensions() == (w, h) {
+            image
+        } else {
+            image::imageops::resize(&image, w, h, image::imageops::FilterType::Triangle)
+        };
+        image::imageops::replace(&mut canvas, &image, to_px(di.x - min_x) as i64, to_px(di.y - min_y) as i64);
+    }
+
+    let (bytes, encode_ms) = timed(|| encode(&canvas, options))?;
+    Ok(payload(&bytes, options, "all_monitors", None, Timing { capture_ms, encode_ms }, scale))
+}
+
+/// Capture every display as its own image, in `DisplayInfo::all()` order.
+/// All displays are grabbed before any is encoded, at the same time where the
+/// platform allows: one `screencapture` call on macOS, one thread per display
+/// elsewhere, one portal request on Wayland. The pixel limit applies per image.
+pub fn capture_each_monitor(mode: &str, options: CaptureOptions) -> Result<Vec<CapturePayload>, AppError> {
+    let start = std::time::Instant::now();
+    let displays = display_info::DisplayInfo::all().map_err(|e| AppError::CaptureFailed(e.to_string()))?;
+    if displays.is_empty() {
+        return Err(AppError::NoScreen);
+    }
+    for di in &displays {
+        let (width, height) = physical_size(di);
+        fit_factor(options, width, height)?;
+    }
+
+    #[cfg(target_os = "macos")]
+    let grabs: Vec<image::RgbaImage> = {
+        use std::process::Command;
+        // Given one file per display, screencapture fills them from a single grab
+        let paths: Vec<_> = (0..displays.len())
+            .map(|i| std::env::temp_dir().join(format!("screenai_capture_{}.png", i)))
+            .collect();
+        let mut command = Command::new("screencapture");
+        command.args(["-x", "-t", "png"]);
+        if options.include_cursor {
+            command.arg("-C");
+        }
+        let output = command
+            .args(&paths)
+            .output()
+            .map_err(|e| AppError::CaptureFailed(format!("failed to run screencapture: {}", e)))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(AppError::CaptureFailed(format!("screencapture failed: {}", stderr)));
+        }
+        let images = paths
+            .iter()
+            .map(|path| {
+                let bytes = std::fs::read(path).map_err(|e| AppError::CaptureFailed(format!("failed to read capture: {}", e)))?;
+                image::load_from_memory(&bytes).map(|i| i.to_rgba8()).map_err(|e| AppError::DecodeFailed(e.to_string()))
+            })
+            .collect::<Result<Vec<_>, AppError>>();
+        for path in &paths {
+            let _ = std::fs::remove_file(path);
+        }
+        images?
+    };
+
+    #[cfg(not(target_os = "macos"))]
+    let grabs: Vec<image::RgbaImage> = grab_displays_parallel(options)?.into_iter().map(|(_, image)| image).collect();
+    let capture_ms = start.elapsed().as_millis() as u64;
+
+    std::thread::scope(|scope| {
+        let encoders: Vec<_> = grabs
+            .into_iter()
+            .zip(&displays)
+            .enumerate()
+            .map(|(index, (image, di))| {
+                scope.spawn(move || {
+                    let factor = fit_factor(options, image.width(), image.height())?;
+                    let image = shrink(image, factor);
+                    let (bytes, encode_ms) = timed(|| encode(&image, options))?;
+                    let timing = Timing { capture_ms, encode_ms };
+                    Ok(payload(&bytes, options, mode, Some(index), timing, di.scale_factor as f64 * factor))
+                })
+            })
+            .collect();
+        encoders
+            .into_iter()
+            .map(|encoder| encoder.join().unwrap_or_else(|_| Err(AppError::EncodeFailed("encoder thread panicked".to_string()))))
+            .collect()
+    })
+}
+
+/// Grab every display at once, one thread each, in `Screen::all()` order.
+/// On Wayland one portal screenshot is taken and every display cut from it,
+/// rather than a round trip (and possibly a permission prompt) per display.
+#[cfg(not(target_os = "macos"))]
+fn grab_displays_parallel(options: CaptureOptions) -> Result<Vec<(display_info::DisplayInfo, image::RgbaImage)>, AppError> {
+    let screens = screenshots::Screen::all().map_err(|e| AppError::CaptureFailed(e.to_string()))?;
+    #[cfg(target_os = "linux")]
+    if crate::portal::is_wayland() {
+        let desktop = crate::portal::screenshot()?;
+        let displays: Vec<_> = screens.iter().map(|s| s.display_info).collect();
+        return displays.iter().map(|di| cut_display(&desktop, &displays, *di).map(|(image, _)| (*di, image))).collect();
+    }
+    std::thread::scope(|scope| {
+        let grabbers: Vec<_> = screens.iter().map(|screen| scope.spawn(move || grab_display(screen, options))).collect();
+        grabbers
+            .into_iter()
+            .zip(&screens)
+            .map(|(grabber, screen)| {
+                grabber
+                    .join()
+                    .unwrap_or_else(|_| Err(AppError::CaptureFailed("capture thread panicked".to_string())))
+                    .map(|(image, _)| (screen.display_info, image))
+            })
+            .collect()
+    })
+}
+
+/// Grab the pixels of a rectangle given in global logical coordinates without
+/// encoding. `scale` converts them to physical pixels (1.0 = already physical).
+/// Returns the image, the display it came from (if known) and that display's scale factor.
+pub fn grab_rect(rect: Rect, scale: f64, options: CaptureOptions) -> Result<(image::RgbaImage, Option<usize>, f64), AppError> {
+    if rect.w == 0 || rect.h == 0 {
+        return Err(AppError::InvalidArgument("Capture area is empty".to_string()));
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        // -R takes points; screencapture handles the Retina scaling itself
+        let _ = scale;
+        let area = format!("{},{},{},{}", rect.x, rect.y, rect.w, rect.h);
+        let png = CaptureOptions { format: OutputFormat::Png, ..options };
+        let (bytes, _) = screencapture(&["-R", &area], png)?;
+        let image = image::load_from_memory(&bytes).map_err(|e| AppError::DecodeFailed(e.to_string()))?;
+        Ok((image.to_rgba8(), None, display_scale(None)))
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let rect = rect.scaled(scale);
+        let screens = screenshots::Screen::all().map_err(|e| AppError::CaptureFailed(e.to_string()))?;
+        let displays: Vec<_> = screens.iter().map(|s| s.display_info).collect();
+        let (cx, cy) = (rect.x + rect.w as i32 / 2, rect.y + rect.h as i32 / 2);
+        let index = match displays.iter().position(|d| {
+            cx >= d.x && cy >= d.y && cx < d.x + d.width as i32 && cy < d.y + d.height as i32
+        }) {
+            Some(i) => i,
+            None => resolve_monitor_index(&displays, None)?,
+        };
+        let di = displays[index];
+        let (image, ppu) = grab_display(&screens[index], options)?;
+
+        // Convert to image pixels and clamp to what this display actually shows
+        let local = Rect { x: rect.x - di.x, y: rect.y - di.y, ..rect }.scaled(ppu);
+        let left = local.x.max(0) as u32;
+        let top = local.y.max(0) as u32;
+        let right = (local.x + local.w as i32).clamp(0, image.width() as i32) as u32;
+        let bottom = (local.y + local.h as i32).clamp(0, image.height() as i32) as u32;
+        if right <= left || bottom <= top {
+            return Err(AppError::InvalidArgument("Capture area is outside the screen".to_string()));
+        }
+
+        let cropped = image::imageops::crop_imm(&image, left, top, right - left, bottom - top).to_image();
+        Ok((cropped, Some(index), di.scale_factor as f64))
+    }
+}
+
+/// Capture a rectangle given in global logical coordinates. `scale` converts
+/// them to the physical pixels the OS captures in (1.0 = already physical).
+/// The display containing the rectangle's center is captured and cropped to it.
+pub fn capture_rect(mode: &str, rect: Rect, scale: f64, options: CaptureOptions) -> Result<CapturePayload, AppError> {
+    let ((image, index, display_scale), capture_ms) = timed(|| grab_rect(rect, scale, options))?;
+    let factor = fit_factor(options, image.width(), image.height())?;
+    let image = shrink(image, factor);
+    let (bytes, encode_ms) = timed(|| encode(&image, options))?;
+    Ok(payload(&bytes, options, mode, index, Timing { capture_ms, encode_ms }, display_scale * factor))
+}
+
+/// A rectangle as fractions (0.0-1.0) of a display's width and height
+#[derive(Clone, Copy, Debug)]
+pub struct FractionRect {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+}
+
+impl FractionRect {
+    /// Pixel rectangle on a `width`×`height` image, edges rounded like `Rect::scaled`
+    fn to_pixels(self, width: u32, height: u32) -> Result<(u32, u32, u32, u32), AppError> {
+        let values = [("x", self.x), ("y", self.y), ("width", self.w), ("height", self.h)];
+        if let Some((name, value)) = values.iter().find(|(_, v)| !(0.0..=1.0).contains(v)) {
+            return Err(AppError::InvalidArgument(format!("{} {} is outside 0-1", name, value)));
+        }
+        // Small tolerance so 0.75 + 0.25 style sums aren't rejected for float error
+        if self.x + self.w > 1.0 + 1e-9 || self.y + self.h > 1.0 + 1e-9 {
+            return Err(AppError::InvalidArgument("Region extends past the edge of the display".to_string()));
+        }
+        let edge = |fraction: f64, size: u32| ((fraction * size as f64).round() as u32).min(size);
+        let (left, top) = (edge(self.x, width), edge(self.y, height));
+        let (right, bottom) = (edge(self.x + self.w, width), edge(self.y + self.h, height));
+        if right <= left || bottom <= top {
+            return Err(AppError::InvalidArgument("Capture area is empty".to_string()));
+        }
+        Ok((left, top, right - left, bottom - top))
+    }
+}
+
+/// Capture part of display `monitor_index` (default: primary) given as fractions
+/// of its resolution, so the same call works on any display size
+pub fn capture_fraction(mode: &str, monitor_index: Option<usize>, area: FractionRect, options: CaptureOptions) -> Result<CapturePayload, AppError> {
+    // The pixel limit applies to the cropped area, not the whole display
+    let unlimited = CaptureOptions { limit: None, ..options };
+    let ((image, index, display_scale), capture_ms) = timed(|| {
+        let (image, index, _, scale) = grab_screen(monitor_index, unlimited)?;
+        Ok((image, index, scale))
+    })?;
+    let (x, y, w, h) = area.to_pixels(image.width(), image.height())?;
+    let factor = fit_factor(options, w, h)?;
+    let cropped = shrink(image::imageops::crop_imm(&image, x, y, w, h).to_image(), factor);
+    let (bytes, encode_ms) = timed(|| encode(&cropped, options))?;
+    Ok(payload(&bytes, options, mode, Some(index), Timing { capture_ms, encode_ms }, display_scale * factor))
+}
+
+/// Capture display `monitor_index` (default: primary) without the taskbar,
+/// dock or menu bar. The whole display is captured when the OS can't report
+/// its work area; `work_area` in the payload says which bounds were used.
+pub fn capture_work_area(monitor_index: Option<usize>, options: CaptureOptions) -> Result<CapturePayload, AppError> {
+    let displays = display_info::DisplayInfo::all().map_err(|e| AppError::CaptureFailed(e.to_string()))?;
+    let index = resolve_monitor_index(&displays, monitor_index)?;
+    let di = &displays[index];
+    let bounds = Rect { x: di.x, y: di.y, w: di.width, h: di.height };
+    let area = match crate::work_area::query(di) {
+        Some(area) if area.w > 0 && area.h > 0 => area,
+        _ => {
+            log::info!("No work area for display {}, capturing all of it", index);
+            bounds
+        }
+    };
+    // As fractions, so the crop follows the image whatever its pixel density
+    let fraction = FractionRect {
+        x: (area.x - bounds.x) as f64 / bounds.w.max(1) as f64,
+        y: (area.y - bounds.y) as f64 / bounds.h.max(1) as f64,
+        w: area.w as f64 / bounds.w.max(1) as f64,
+        h: area.h as f64 / bounds.h.max(1) as f64,
+    };
+    let mut payload = capture_fraction("work_area", Some(index), fraction, options)?;
+    payload.work_area = Some(area);
+    Ok(payload)
+}
+
+/// Capture the window the user is currently working in (never ScreenAI itself).
+/// Not supported on Wayland, which doesn't tell apps which window is active.
+pub fn capture_active_window(options: CaptureOptions) -> Result<CapturePayload, AppError> {
+    #[cfg(target_os = "linux")]
+    if crate::portal::is_wayland() {
+        return Err(AppError::CaptureFailed("Capturing the active window is not supported on Wayland".to_string()));
+    }
+    let window = crate::window_list::active()
+        .ok_or_else(|| AppError::CaptureFailed("No active window to capture".to_string()))?;
+    #[cfg(target_os = "macos")]
+    {
+        capture_window(window.id, options)
+    }
+    // Linux can't look windows up by id, see `window_list::list`
+    #[cfg(not(target_os = "macos"))]
+    {
+        capture_window_bounds(&window, options)
+    }
+}
+
+/// Capture a single top-level window by the id returned from `list_windows`
+pub fn capture_window(window_id: u64, options: CaptureOptions) -> Result<CapturePayload, AppError> {
+    #[cfg(target_os = "macos")]
+    {
+        // -l = window id (CGWindowID), -o = no drop shadow
+        let id = window_id.to_string();
+        let (bytes, timing) = screencapture(&["-o", "-l", &id], options)?;
+        Ok(payload(&bytes, options, "window", None, timing, display_scale(None)))
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let window = crate::window_list::list()
+            .into_iter()
+            .find(|w| w.id == window_id)
+            .ok_or_else(|| AppError::InvalidArgument(format!("Window {} not found", window_id)))?;
+        capture_window_bounds(&window, options)
+    }
+}
+
+/// Capture the screen area `window` covers
+#[cfg(not(target_os = "macos"))]
+fn capture_window_bounds(window: &crate::window_list::WindowInfo, options: CaptureOptions) -> Result<CapturePayload, AppError> {
+    let rect = Rect {
+        x: window.x,
+        y: window.y,
+        w: window.w.max(0) as u32,
+        h: window.h.max(0) as u32,
+    };
+    // Window bounds are already physical pixels
+    capture_rect("window", rect, 1.0, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rect;
+
+    #[test]
+    fn scaled_rounds_edges_so_neighbours_stay_adjacent() {
+        let left = Rect { x: 0, y: 0, w: 1, h: 1 }.scaled(1.25);
+        let right = Rect { x: 1, y: 0, w: 1, h: 1 }.scaled(1.25);
+        assert_eq!(left.x + left.w as i32, right.x);
+    }
+
+    #[test]
+    fn snapped_rounds_to_the_grid() {
+        let rect = Rect { x: 3, y: 5, w: 30, h: 13 };
+        assert_eq!(rect.snapped(8, None), Rect { x: 0, y: 8, w: 32, h: 8 });
+        assert_eq!(rect.snapped(1, None), rect);
+    }
+
+    #[test]
+    fn snapped_counts_from_the_display_and_stays_on_it() {
+        let display = Rect { x: 100, y: 0, w: 50, h: 50 };
+        let snapped = Rect { x: 103, y: 2, w: 52, h: 45 }.snapped(8, Some(display));
+        assert_eq!(snapped, Rect { x: 100, y: 0, w: 48, h: 48 });
+    }
+
+    #[test]
+    fn with_aspect_keeps_the_width() {
+        let rect = Rect { x: 0, y: 0, w: 160, h: 10 }.with_aspect(16.0 / 9.0, None);
+        assert_eq!(rect, Rect { x: 0, y: 0, w: 160, h: 90 });
+    }
+
+    #[test]
+    fn with_aspect_shrinks_to_fit_the_display() {
+        let display = Rect { x: 0, y: 0, w: 100, h: 100 };
+        let rect = Rect { x: 20, y: 50, w: 160, h: 1 }.with_aspect(2.0, Some(display));
+        assert_eq!(rect, Rect { x: 20, y: 50, w: 80, h: 40 });
+    }
+}
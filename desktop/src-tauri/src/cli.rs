@@ -0,0 +1,191 @@
+// ============================================
+// ScreenAI Desktop — Headless command-line captures
+// ============================================
+//
+// `screenai --capture fullscreen --out shot.png` takes the screenshot, writes
+// it and exits before any tray or webview is created, so it can be scripted.
+// Without `--capture` the arguments are ignored and the app starts normally.
+// Exit codes: 0 = written, 1 = the capture or write failed, 2 = bad arguments.
+
+use crate::capture::{self, CaptureOptions, Rect};
+use crate::error::AppError;
+use crate::imaging::{self, OutputFormat};
+use std::path::PathBuf;
+
+const USAGE: &str = "\
+Usage: screenai --capture <fullscreen|region> --out <file> [options]
+
+  --capture <mode>    fullscreen (default display or --monitor) or region
+  --out <file>        where to write; the extension picks the format
+  --region x,y,w,h    area for region mode, in screen pixels (points on macOS)
+  --monitor <n>       fullscreen display index, as listed by the app (default: primary)
+  --format <fmt>      png, jpeg, webp, webp-lossless or avif instead of the extension
+  --quality <1-100>   for lossy formats
+  --cursor            include the mouse pointer";
+
+struct Args {
+    mode: String,
+    out: PathBuf,
+    region: Option<Rect>,
+    monitor: Option<usize>,
+    format: OutputFormat,
+    cursor: bool,
+}
+
+fn parse_region(value: &str) -> Result<Rect, String> {
+    let parts: Vec<&str> = value.split(',').map(str::trim).collect();
+    let [x, y, w, h] = parts.as_slice() else {
+        return Err(format!("--region expects x,y,w,h, got \"{}\"", value));
+    };
+    let bad = |name: &str, v: &str| format!("--region {} \"{}\" is not a number", name, v);
+    Ok(Rect {
+        x: x.parse().map_err(|_| bad("x", x))?,
+        y: y.parse().map_err(|_| bad("y", y))?,
+        w: w.parse().map_err(|_| bad("width", w))?,
+        h: h.parse().map_err(|_| bad("height", h))?,
+    })
+}
+
+fn parse(args: &[String]) -> Result<Args, String> {
+    let (mut mode, mut out, mut format, mut quality) = (None, None, None, None);
+    let mut parsed = Args {
+        mode: String::new(),
+        out: PathBuf::new(),
+        region: None,
+        monitor: None,
+        format: OutputFormat::Png,
+        cursor: false,
+    };
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let mut value = || iter.next().cloned().ok_or_else(|| format!("{} needs a value", flag));
+        match flag.as_str() {
+            "--capture" => mode = Some(value()?),
+            "--out" => out = Some(PathBuf::from(value()?)),
+            "--region" => parsed.region = Some(parse_region(&value()?)?),
+            "--monitor" => {
+                let v = value()?;
+                parsed.monitor = Some(v.parse().map_err(|_| format!("--monitor \"{}\" is not an index", v))?);
+            }
+            "--format" => format = Some(value()?),
+            "--quality" => {
+                let v = value()?;
+                quality = Some(v.parse().map_err(|_| format!("--quality \"{}\" is not 1-100", v))?);
+            }
+            "--cursor" => parsed.cursor = true,
+            other => return Err(format!("Unknown argument \"{}\"", other)),
+        }
+    }
+    parsed.mode = mode.ok_or("--capture is required")?;
+    parsed.out = out.ok_or("--out is required")?;
+    match parsed.mode.as_str() {
+        "fullscreen" if parsed.region.is_some() => return Err("--region only applies to --capture region".to_string()),
+        "region" if parsed.region.is_none() => return Err("--capture region needs --region x,y,w,h".to_string()),
+        // The region's coordinates already say which display it is on
+        "region" if parsed.monitor.is_some() => return Err("--monitor only applies to --capture fullscreen".to_string()),
+        "fullscreen" | "region" => {}
+        other => return Err(format!("Unknown capture mode \"{}\"", other)),
+    }
+    let format = match format {
+        Some(format) => format,
+        None => OutputFormat::from_extension(&parsed.out).map_err(|e| e.to_string())?.extension().to_string(),
+    };
+    parsed.format = OutputFormat::parse(Some(&format), quality).map_err(|e| e.to_string())?;
+    Ok(parsed)
+}
+
+fn run(args: Args) -> Result<PathBuf, AppError> {
+    let options = CaptureOptions { format: args.format, include_cursor: args.cursor, ..CaptureOptions::default() };
+    match args.region {
+        Some(rect) => {
+            let (image, _, _) = capture::grab_rect(rect, 1.0, options)?;
+            let bytes = imaging::encode(&image, args.format)?;
+            crate::disk::ensure_space(&args.out, bytes.len() as u64)?;
+            std::fs::write(&args.out, bytes).map_err(|e| AppError::Io(format!("{}: {}", args.out.display(), e)))?;
+        }
+        None => {
+            capture::capture_screen_to_file(&args.out, args.monitor, options)?;
+        }
+    }
+    Ok(args.out)
+}
+
+/// Handle a `--capture` invocation and return the process exit code, or
+/// `None` when the app should start normally. Messages go to stderr, which
+/// release builds on Windows don't attach to a console; the exit code does.
+pub fn run_from_args() -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{}", USAGE);
+        return Some(0);
+    }
+    if !args.iter().any(|a| a == "--capture") {
+        return None;
+    }
+    let args = match parse(&args) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("screenai: {}\n\n{}", message, USAGE);
+            return Some(2);
+        }
+    };
+    match run(args) {
+        Ok(path) => {
+            println!("{}", path.display());
+            Some(0)
+        }
+        Err(e) => {
+            eprintln!("screenai: {}", e);
+            Some(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use crate::capture::Rect;
+    use crate::imaging::OutputFormat;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_a_fullscreen_capture() {
+        let parsed = parse(&args(&["--capture", "fullscreen", "--out", "a.png", "--monitor", "1", "--cursor"])).unwrap();
+        assert_eq!(parsed.mode, "fullscreen");
+        assert_eq!(parsed.out, std::path::PathBuf::from("a.png"));
+        assert_eq!(parsed.monitor, Some(1));
+        assert!(parsed.cursor);
+        assert_eq!(parsed.region, None);
+    }
+
+    #[test]
+    fn parses_a_region_capture() {
+        let parsed = parse(&args(&["--capture", "region", "--region", "10, -20,300,200", "--out", "a.jpg", "--quality", "80"])).unwrap();
+        assert_eq!(parsed.region, Some(Rect { x: 10, y: -20, w: 300, h: 200 }));
+        assert_eq!(parsed.format, OutputFormat::Jpeg(80));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        for bad in [
+            &["--out", "a.png"][..],
+            &["--capture", "fullscreen"],
+            &["--capture", "region", "--out", "a.png"],
+            &["--capture", "fullscreen", "--region", "0,0,10,10", "--out", "a.png"],
+            &["--capture", "window", "--out", "a.png"],
+            &["--capture", "region", "--region", "0,0,10", "--out", "a.png"],
+            &["--capture", "fullscreen", "--out", "a.png", "--quality", "high"],
+            &["--capture", "fullscreen", "--out", "a.png", "--monitor"],
+            &["--capture", "fullscreen", "--out", "a.png", "--verbose"],
+            &["--capture", "region", "--region", "0,0,10,10", "--monitor", "1", "--out", "a.png"],
+            &["--capture", "fullscreen", "--out", "a.txt"],
+            &["--capture", "fullscreen", "--out", "shot"],
+            &["--capture", "fullscreen", "--out", "a.png", "--format", "gif"],
+        ] {
+            assert!(parse(&args(bad)).is_err(), "{:?} should be rejected", bad);
+        }
+    }
+}
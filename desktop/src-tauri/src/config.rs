@@ -0,0 +1,241 @@
+// ============================================
+// ScreenAI Desktop — Persisted user settings
+// ============================================
+//
+// Stored as `config.json` in the app config dir. Missing or unreadable files
+// fall back to defaults so a bad edit never keeps the app from starting.
+
+use crate::capture::PixelLimit;
+use crate::error::AppError;
+use crate::imaging::OutputFormat;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::AppHandle;
+
+/// Actions a global shortcut can trigger
+pub const SHORTCUT_ACTIONS: [&str; 5] = ["captureFullscreen", "captureRegion", "captureWindow", "captureText", "captureLastRegion"];
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Config {
+    /// Action name → accelerator (e.g. "captureRegion" → "Alt+Shift+A")
+    pub shortcuts: BTreeMap<String, String>,
+    /// Format captures are encoded in when a command doesn't ask for one: "png", "jpeg", "webp", "webp-lossless" or "avif"
+    pub output_format: String,
+    /// Quality (1-100) for lossy output formats; `None` = the format's default
+    pub output_quality: Option<u8>,
+    /// PNG speed/size trade-off when a command doesn't ask: "fast", "default" or "best"
+    pub png_compression: String,
+    /// JPEG chroma subsampling when a command doesn't ask: "444", "422" or "420"
+    pub jpeg_subsampling: String,
+    /// Largest capture allowed, in pixels (0 = unlimited). Guards against
+    /// multi-gigabyte allocations when spanning several 4K/8K displays.
+    pub max_pixels: u64,
+    /// Scale captures above `max_pixels` down to fit instead of failing
+    pub downscale_oversized: bool,
+    /// Turn captures of portrait (90°/270°) displays upright when the OS returns them sideways
+    pub auto_rotate: bool,
+    /// Captures kept in the history before the oldest are evicted (0 = disabled)
+    pub history_max: usize,
+    /// Write every capture to `auto_save_dir` without asking
+    pub auto_save_enabled: bool,
+    /// `None` = ~/Documents/ScreenAI/Captures
+    pub auto_save_dir: Option<String>,
+    /// File name with strftime-style fields, e.g. "ScreenAI_%Y%m%d_%H%M%S.png",
+    /// plus `%n` (counter), `%i` (monitor) and `%t` (mode) — see `autosave::format_name`
+    pub auto_save_pattern: String,
+    /// Last number used for `%n` in `auto_save_pattern` by a saved file; only
+    /// moves forward (see `Config::save`)
+    pub capture_counter: u64,
+    /// Start ScreenAI when the user logs in (see `autostart`)
+    pub launch_at_login: bool,
+    /// Left click on the tray icon: "capture", "open", "region" or "none"
+    pub tray_click_action: String,
+    /// Play a shutter sound after each capture (see `feedback`)
+    pub play_shutter_sound: bool,
+    /// Show a native notification after each capture
+    pub show_notification: bool,
+    /// Steps every capture runs through afterwards, in order (see `pipeline`)
+    pub post_capture_actions: Vec<crate::pipeline::ActionSpec>,
+    /// "stable" or "beta"
+    pub update_channel: String,
+    /// Check for updates at launch and every `update_check_interval_hours`
+    pub auto_update_check: bool,
+    pub update_check_interval_hours: u32,
+    /// Longest wait for the main window to report hidden before capturing anyway
+    pub hide_timeout_ms: u64,
+    /// Extra delay after the window is hidden, for compositor fade-outs
+    pub hide_settle_ms: u64,
+    /// Give up on a screen grab that hangs this long (0 = wait forever)
+    pub capture_timeout_ms: u64,
+    /// Frames per second of the region overlay's live backdrop (see `preview`)
+    pub overlay_preview_fps: u32,
+    /// Minimum level written to the log: "error", "warn", "info", "debug" or "trace"
+    pub log_level: String,
+    /// Vision model used by `analyze_capture`: "anthropic", "openai" or "ollama"
+    pub ai_provider: String,
+    /// Only used when there is no OS keychain; see `secrets`. Same for the
+    /// Imgur client ID, the S3 secret key, the WebDAV password and the custom
+    /// endpoint's headers.
+    pub ai_api_key: Option<String>,
+    /// `None` = the provider's public endpoint
+    pub ai_base_url: Option<String>,
+    /// `None` = a vision-capable default for the provider
+    pub ai_model: Option<String>,
+    /// Client ID of an Imgur app, for anonymous uploads
+    pub upload_imgur_client_id: Option<String>,
+    pub upload_s3: Option<crate::upload::S3Settings>,
+    pub upload_webdav: Option<crate::upload::WebDavSettings>,
+    pub upload_http: Option<crate::upload::HttpSettings>,
+    /// Largest capture `upload_capture` sends, in MB (0 = no limit of our own)
+    pub upload_max_mb: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let mut shortcuts = BTreeMap::new();
+        shortcuts.insert("captureFullscreen".to_string(), "Alt+Shift+S".to_string());
+        shortcuts.insert("captureRegion".to_string(), "Alt+Shift+A".to_string());
+        shortcuts.insert("captureText".to_string(), "Alt+Shift+T".to_string());
+        Config {
+            shortcuts,
+            output_format: "png".to_string(),
+            output_quality: None,
+            png_compression: "default".to_string(),
+            jpeg_subsampling: "420".to_string(),
+            max_pixels: 50_000_000,
+            downscale_oversized: false,
+            auto_rotate: true,
+            history_max: 50,
+            auto_save_enabled: false,
+            auto_save_dir: None,
+            auto_save_pattern: crate::autosave::DEFAULT_PATTERN.to_string(),
+            capture_counter: 0,
+            launch_at_login: false,
+            tray_click_action: "open".to_string(),
+            play_shutter_sound: false,
+            show_notification: false,
+            post_capture_actions: Vec::new(),
+            update_channel: "stable".to_string(),
+            auto_update_check: true,
+            update_check_interval_hours: 6,
+            hide_timeout_ms: 2000,
+            hide_settle_ms: 250,
+            capture_timeout_ms: 5000,
+            overlay_preview_fps: crate::preview::DEFAULT_FPS,
+            log_level: crate::logging::DEFAULT_LEVEL.to_string(),
+            ai_provider: "anthropic".to_string(),
+            ai_api_key: None,
+            ai_base_url: None,
+            ai_model: None,
+            upload_imgur_client_id: None,
+            upload_s3: None,
+            upload_webdav: None,
+            upload_http: None,
+            upload_max_mb: 25,
+        }
+    }
+}
+
+fn config_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path_resolver().app_config_dir().map(|dir| dir.join("config.json"))
+}
+
+impl Config {
+    /// Read the config from disk, falling back to defaults
+    pub fn load(app: &AppHandle) -> Config {
+        let Some(path) = config_path(app) else { return Config::default() };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                log::warn!("Ignoring invalid config {}: {}", path.display(), e);
+                Config::default()
+            }),
+            Err(_) => Config::default(),
+        }
+    }
+
+    /// Output format for a capture: the command's own `format` / `quality`
+    /// if given, otherwise the saved defaults
+    pub fn capture_format(&self, format: Option<&str>, quality: Option<u8>) -> Result<OutputFormat, AppError> {
+        OutputFormat::parse(Some(format.unwrap_or(&self.output_format)), quality.or(self.output_quality))
+    }
+
+    /// The `max_pixels` cap for capture options, `None` when unlimited
+    pub fn pixel_limit(&self) -> Option<PixelLimit> {
+        (self.max_pixels > 0).then_some(PixelLimit { max_pixels: self.max_pixels, downscale: self.downscale_oversized })
+    }
+
+    /// Destinations and limits for `upload_capture`
+    pub fn upload_settings(&self) -> crate::upload::UploadSettings {
+        crate::upload::UploadSettings {
+            imgur_client_id: self.upload_imgur_client_id.clone(),
+            s3: self.upload_s3.clone(),
+            webdav: self.upload_webdav.clone(),
+            http: self.upload_http.clone(),
+            max_bytes: self.upload_max_mb as u64 * 1024 * 1024,
+        }
+    }
+
+    /// Apply the fields present in `partial` (same camelCase keys as the file)
+    /// on top of this config. Unknown keys and invalid values are rejected.
+    pub fn merged(&self, partial: &serde_json::Value) -> Result<Config, AppError> {
+        let changes = partial
+            .as_object()
+            .ok_or_else(|| AppError::InvalidArgument("Config update must be an object".to_string()))?;
+        let mut value = serde_json::to_value(self).map_err(|e| AppError::Io(e.to_string()))?;
+        let fields = value.as_object_mut().expect("Config serializes to an object");
+        for (key, field) in changes {
+            if !fields.contains_key(key) {
+                return Err(AppError::InvalidArgument(format!("Unknown config field \"{}\"", key)));
+            }
+            fields.insert(key.clone(), field.clone());
+        }
+        let config: Config =
+            serde_json::from_value(value).map_err(|e| AppError::InvalidArgument(format!("Invalid config: {}", e)))?;
+
+        // Catch bad values now rather than at the next capture or launch
+        config.capture_format(None, None)?;
+        crate::imaging::PngCompression::parse(Some(&config.png_compression))?;
+        crate::imaging::JpegSubsampling::parse(Some(&config.jpeg_subsampling))?;
+        crate::updater::Channel::parse(&config.update_channel)?;
+        crate::tray::ClickAction::parse(&config.tray_click_action)?;
+        for action in &config.post_capture_actions {
+            action.validate()?;
+        }
+        if config.update_check_interval_hours == 0 {
+            return Err(AppError::InvalidArgument("updateCheckIntervalHours must be at least 1".to_string()));
+        }
+        if !(1..=crate::preview::MAX_FPS).contains(&config.overlay_preview_fps) {
+            return Err(AppError::InvalidArgument(format!(
+                "overlayPreviewFps must be between 1 and {}",
+                crate::preview::MAX_FPS
+            )));
+        }
+        crate::logging::parse_level(&config.log_level)?;
+        crate::ai::Provider::parse(&config.ai_provider)?;
+        Ok(config)
+    }
+
+    /// Write the config to disk. Keeps the saved `capture_counter` when it is
+    /// ahead of this one, so a config loaded before an auto-save numbered its
+    /// file can't hand that number out again.
+    pub fn save(&self, app: &AppHandle) -> Result<(), AppError> {
+        let _lock = crate::autosave::COUNTER_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let capture_counter = Config::load(app).capture_counter.max(self.capture_counter);
+        Config { capture_counter, ..self.clone() }.write(app)
+    }
+
+    /// `save` as is, for callers holding `autosave::COUNTER_LOCK`
+    pub fn write(&self, app: &AppHandle) -> Result<(), AppError> {
+        let path = config_path(app).ok_or_else(|| AppError::Io("No app config directory".to_string()))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| AppError::Io(e.to_string()))?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(|e| AppError::Io(e.to_string()))?;
+        // Write then rename, so quitting mid-save never leaves a truncated file
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, text).map_err(|e| AppError::Io(e.to_string()))?;
+        std::fs::rename(&tmp, &path).map_err(|e| AppError::Io(e.to_string()))
+    }
+}
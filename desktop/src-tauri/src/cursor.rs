@@ -0,0 +1,311 @@
+// ============================================
+// ScreenAI Desktop — Mouse cursor compositing
+// ============================================
+//
+// The `screenshots` crate never includes the pointer, so when the user asks for
+// it we query the cursor ourselves and paint it onto the captured image.
+// macOS does not need this: `screencapture -C` draws the real cursor natively.
+
+use image::RgbaImage;
+
+/// Cursor bitmap plus where it sits on the virtual desktop
+pub struct CursorSprite {
+    pub image: RgbaImage,
+    /// Hotspot offset inside `image` (the pixel that "points")
+    pub hotspot: (i32, i32),
+    /// Hotspot position in global screen coordinates
+    pub position: (i32, i32),
+}
+
+/// Standard arrow used when the OS cursor bitmap is not available.
+/// `X` = outline, `.` = fill, space = transparent.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+const ARROW: [&str; 19] = [
+    "X           ",
+    "XX          ",
+    "X.X         ",
+    "X..X        ",
+    "X...X       ",
+    "X....X      ",
+    "X.....X     ",
+    "X......X    ",
+    "X.......X   ",
+    "X........X  ",
+    "X.........X ",
+    "X......XXXXX",
+    "X...X..X    ",
+    "X..XX..X    ",
+    "X.X  X..X   ",
+    "XX   X..X   ",
+    "X     X..X  ",
+    "      X..X  ",
+    "       XX   ",
+];
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn arrow_sprite(scale: u32) -> RgbaImage {
+    let scale = scale.max(1);
+    let width = ARROW[0].len() as u32;
+    let height = ARROW.len() as u32;
+    RgbaImage::from_fn(width * scale, height * scale, |x, y| {
+        match ARROW[(y / scale) as usize].as_bytes()[(x / scale) as usize] {
+            b'X' => image::Rgba([0, 0, 0, 255]),
+            b'.' => image::Rgba([255, 255, 255, 255]),
+            _ => image::Rgba([0, 0, 0, 0]),
+        }
+    })
+}
+
+/// Cursor size (pixels) themes are drawn at on a 1× display
+#[cfg(target_os = "linux")]
+const BASE_CURSOR_SIZE: f32 = 24.0;
+
+/// `image` and `hotspot` enlarged by `factor`
+#[cfg(target_os = "linux")]
+fn enlarge(image: RgbaImage, hotspot: (i32, i32), factor: f32) -> (RgbaImage, (i32, i32)) {
+    let size = |v: u32| ((v as f32 * factor).round() as u32).max(1);
+    let image = image::imageops::resize(&image, size(image.width()), size(image.height()), image::imageops::FilterType::Triangle);
+    let hotspot = ((hotspot.0 as f32 * factor).round() as i32, (hotspot.1 as f32 * factor).round() as i32);
+    (image, hotspot)
+}
+
+/// Query the current cursor. `scale` is the display scale factor: cursors the
+/// OS hands back at their 1× size are enlarged by it (and the fallback arrow
+/// drawn at it), so the pointer matches what the user sees on HiDPI displays.
+#[cfg(target_os = "linux")]
+pub fn current_cursor(scale: f32) -> Option<CursorSprite> {
+    use xcb::xfixes;
+
+    // XFixes gives us the real cursor image (ARGB, premultiplied) and its position
+    let (conn, _) = xcb::Connection::connect_with_extensions(None, &[xcb::Extension::XFixes], &[]).ok()?;
+    let version = conn.send_request(&xfixes::QueryVersion {
+        client_major_version: 4,
+        client_minor_version: 0,
+    });
+    conn.wait_for_reply(version).ok()?;
+
+    let reply = conn.wait_for_reply(conn.send_request(&xfixes::GetCursorImage {})).ok()?;
+    let (width, height) = (reply.width() as u32, reply.height() as u32);
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let mut image = RgbaImage::new(width, height);
+    for (pixel, argb) in image.pixels_mut().zip(reply.cursor_image()) {
+        let a = (argb >> 24) & 0xff;
+        let unpremultiply = |c: u32| (c * 255).checked_div(a).unwrap_or(0).min(255) as u8;
+        *pixel = image::Rgba([
+            unpremultiply((argb >> 16) & 0xff),
+            unpremultiply((argb >> 8) & 0xff),
+            unpremultiply(argb & 0xff),
+            a as u8,
+        ]);
+    }
+
+    let mut hotspot = (reply.xhot() as i32, reply.yhot() as i32);
+    // Xcursor themes are often left at their 1× size on HiDPI X11 setups; a
+    // theme already sized for the display is left alone
+    if scale > 1.0 && (width.max(height) as f32) < BASE_CURSOR_SIZE * scale * 0.75 {
+        (image, hotspot) = enlarge(image, hotspot, scale);
+    }
+
+    Some(CursorSprite {
+        image,
+        hotspot,
+        position: (reply.x() as i32, reply.y() as i32),
+    })
+}
+
+#[cfg(target_os = "windows")]
+mod win {
+    use std::ffi::c_void;
+
+    pub const CURSOR_SHOWING: u32 = 0x1;
+    pub const DIB_RGB_COLORS: u32 = 0;
+
+    #[repr(C)]
+    pub struct CursorInfo {
+        pub size: u32,
+        pub flags: u32,
+        pub cursor: isize,
+        pub position: [i32; 2],
+    }
+
+    #[repr(C)]
+    pub struct IconInfo {
+        pub is_icon: i32,
+        pub x_hotspot: u32,
+        pub y_hotspot: u32,
+        pub mask: isize,
+        pub color: isize,
+    }
+
+    #[repr(C)]
+    pub struct Bitmap {
+        pub kind: i32,
+        pub width: i32,
+        pub height: i32,
+        pub width_bytes: i32,
+        pub planes: u16,
+        pub bits_per_pixel: u16,
+        pub bits: *mut c_void,
+    }
+
+    /// BITMAPINFO with room for the largest colour table GetDIBits may write
+    #[repr(C)]
+    pub struct BitmapInfo {
+        pub size: u32,
+        pub width: i32,
+        pub height: i32,
+        pub planes: u16,
+        pub bit_count: u16,
+        pub compression: u32,
+        pub size_image: u32,
+        pub x_pels_per_meter: i32,
+        pub y_pels_per_meter: i32,
+        pub colors_used: u32,
+        pub colors_important: u32,
+        pub colors: [u32; 256],
+    }
+
+    #[link(name = "user32")]
+    extern "system" {
+        pub fn GetCursorInfo(info: *mut CursorInfo) -> i32;
+        pub fn GetCursorPos(point: *mut [i32; 2]) -> i32;
+        pub fn GetIconInfo(icon: isize, info: *mut IconInfo) -> i32;
+        pub fn GetDC(hwnd: isize) -> isize;
+        pub fn ReleaseDC(hwnd: isize, hdc: isize) -> i32;
+    }
+
+    #[link(name = "gdi32")]
+    extern "system" {
+        pub fn GetObjectW(object: isize, size: i32, out: *mut c_void) -> i32;
+        pub fn GetDIBits(hdc: isize, bitmap: isize, start: u32, lines: u32, bits: *mut c_void, info: *mut BitmapInfo, usage: u32) -> i32;
+        pub fn DeleteObject(object: isize) -> i32;
+    }
+
+    /// Size of a bitmap handle
+    pub fn bitmap_size(bitmap: isize) -> Option<(u32, u32)> {
+        let mut info: Bitmap = unsafe { std::mem::zeroed() };
+        let read = unsafe { GetObjectW(bitmap, std::mem::size_of::<Bitmap>() as i32, &mut info as *mut Bitmap as *mut c_void) };
+        (read != 0 && info.width > 0 && info.height > 0).then_some((info.width as u32, info.height as u32))
+    }
+
+    /// A bitmap's pixels as top-down 32-bit BGRA, whatever its own depth
+    pub fn bitmap_pixels(bitmap: isize, width: u32, height: u32) -> Option<Vec<u8>> {
+        let mut info: BitmapInfo = unsafe { std::mem::zeroed() };
+        info.size = (std::mem::size_of::<BitmapInfo>() - std::mem::size_of::<[u32; 256]>()) as u32;
+        info.width = width as i32;
+        // Negative: rows top to bottom
+        info.height = -(height as i32);
+        info.planes = 1;
+        info.bit_count = 32;
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        let hdc = unsafe { GetDC(0) };
+        if hdc == 0 {
+            return None;
+        }
+        let lines = unsafe { GetDIBits(hdc, bitmap, 0, height, pixels.as_mut_ptr() as *mut c_void, &mut info, DIB_RGB_COLORS) };
+        unsafe { ReleaseDC(0, hdc) };
+        (lines == height as i32).then_some(pixels)
+    }
+}
+
+/// The cursor's own bitmap from GetIconInfo: colour cursors with their alpha
+/// (or AND mask, for old ones without alpha), monochrome ones from their
+/// AND/XOR mask pair. Inverting pixels (the I-beam) come out black.
+#[cfg(target_os = "windows")]
+fn cursor_image(cursor: isize) -> Option<(RgbaImage, (i32, i32))> {
+    let mut icon: win::IconInfo = unsafe { std::mem::zeroed() };
+    if unsafe { win::GetIconInfo(cursor, &mut icon) } == 0 {
+        return None;
+    }
+    let image = (|| {
+        let (width, mask_height) = win::bitmap_size(icon.mask)?;
+        let mask = win::bitmap_pixels(icon.mask, width, mask_height)?;
+        // A set AND-mask bit (white after conversion) is transparent
+        let and_bit = |i: usize| mask[i * 4] != 0;
+        if icon.color != 0 {
+            let (width, height) = win::bitmap_size(icon.color)?;
+            let color = win::bitmap_pixels(icon.color, width, height)?;
+            let has_alpha = color.chunks_exact(4).any(|p| p[3] != 0);
+            Some(RgbaImage::from_fn(width, height, |x, y| {
+                let i = (y * width + x) as usize;
+                let p = &color[i * 4..i * 4 + 4];
+                let alpha = if has_alpha {
+                    p[3]
+                } else if i * 4 < mask.len() && and_bit(i) {
+                    0
+                } else {
+                    255
+                };
+                image::Rgba([p[2], p[1], p[0], alpha])
+            }))
+        } else {
+            // Monochrome: the AND mask on top, the XOR mask below it
+            let height = mask_height / 2;
+            Some(RgbaImage::from_fn(width, height, |x, y| {
+                let i = (y * width + x) as usize;
+                let xor = mask[(i + (width * height) as usize) * 4] != 0;
+                match (and_bit(i), xor) {
+                    (false, false) => image::Rgba([0, 0, 0, 255]),
+                    (false, true) => image::Rgba([255, 255, 255, 255]),
+                    (true, false) => image::Rgba([0, 0, 0, 0]),
+                    (true, true) => image::Rgba([0, 0, 0, 255]),
+                }
+            }))
+        }
+    })();
+    // GetIconInfo hands us copies of both bitmaps
+    unsafe {
+        win::DeleteObject(icon.mask);
+        if icon.color != 0 {
+            win::DeleteObject(icon.color);
+        }
+    }
+    Some((image?, (icon.x_hotspot as i32, icon.y_hotspot as i32)))
+}
+
+/// The real cursor at the pointer, already at the size Windows draws it for
+/// the display. `scale` only sizes the fallback arrow, used when the cursor
+/// bitmap can't be read.
+#[cfg(target_os = "windows")]
+pub fn current_cursor(scale: f32) -> Option<CursorSprite> {
+    let mut info = win::CursorInfo { size: std::mem::size_of::<win::CursorInfo>() as u32, flags: 0, cursor: 0, position: [0; 2] };
+    if unsafe { win::GetCursorInfo(&mut info) } != 0 {
+        // Hidden, e.g. while typing or in a full-screen video
+        if info.flags & win::CURSOR_SHOWING == 0 || info.cursor == 0 {
+            return None;
+        }
+        if let Some((image, hotspot)) = cursor_image(info.cursor) {
+            return Some(CursorSprite { image, hotspot, position: (info.position[0], info.position[1]) });
+        }
+    }
+
+    let mut point = [0i32; 2];
+    if unsafe { win::GetCursorPos(&mut point) } == 0 {
+        return None;
+    }
+    Some(CursorSprite {
+        image: arrow_sprite(scale.round() as u32),
+        hotspot: (0, 0),
+        position: (point[0], point[1]),
+    })
+}
+
+/// Paint `cursor` onto `target`, a capture whose top-left corner is at `origin`
+/// in screen coordinates. `scale` converts screen coordinates to image pixels.
+/// Does nothing when the hotspot falls outside the captured area.
+pub fn composite(target: &mut RgbaImage, cursor: &CursorSprite, origin: (i32, i32), scale: f64) {
+    let local_x = ((cursor.position.0 - origin.0) as f64 * scale).round() as i64;
+    let local_y = ((cursor.position.1 - origin.1) as f64 * scale).round() as i64;
+    if local_x < 0 || local_y < 0 || local_x >= target.width() as i64 || local_y >= target.height() as i64 {
+        return;
+    }
+    image::imageops::overlay(
+        target,
+        &cursor.image,
+        local_x - cursor.hotspot.0 as i64,
+        local_y - cursor.hotspot.1 as i64,
+    );
+}
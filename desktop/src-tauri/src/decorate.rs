@@ -0,0 +1,184 @@
+// ============================================
+// ScreenAI Desktop — Rounded corners and drop shadow
+// ============================================
+//
+// Frames a capture the way macOS window shots look: the canvas grows by a
+// padding on every side, a soft shadow is laid under the image, and the
+// image's corners are cut with an anti-aliased alpha mask before everything
+// is composited over the background color. The shadow blur is three box
+// blurs, so its cost doesn't grow with the blur radius.
+
+use crate::annotate::parse_color;
+use crate::error::AppError;
+use image::{Rgba, RgbaImage};
+use serde::Deserialize;
+
+/// Widest padding accepted, per side
+const MAX_PADDING: u32 = 1024;
+/// Softest shadow accepted (gaussian sigma in pixels)
+const MAX_SHADOW_BLUR: f32 = 200.0;
+
+/// Drop shadow under a decorated capture:
+/// `{ "blur": 24, "offset_x": 0, "offset_y": 12, "color": "#00000066" }`.
+/// Missing fields keep these defaults.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ShadowSpec {
+    /// Softness in pixels (gaussian sigma), 0 = a hard edge
+    pub blur: f32,
+    pub offset_x: i32,
+    pub offset_y: i32,
+    /// "#rrggbbaa"; a fully transparent color leaves the shadow out
+    pub color: String,
+}
+
+impl Default for ShadowSpec {
+    fn default() -> Self {
+        Self { blur: 24.0, offset_x: 0, offset_y: 12, color: "#00000066".to_string() }
+    }
+}
+
+/// `image` on a canvas `2 × padding` larger in both directions, filled with
+/// `background` ("#rrggbb" or "#rrggbbaa"), with `shadow` under it and its
+/// corners rounded by `corner_radius` pixels (capped at half the shorter side)
+pub fn decorate(image: &RgbaImage, corner_radius: u32, shadow: &ShadowSpec, padding: u32, background: &str) -> Result<RgbaImage, AppError> {
+    if padding > MAX_PADDING {
+        return Err(AppError::InvalidArgument(format!("padding must be at most {} pixels", MAX_PADDING)));
+    }
+    if !(0.0..=MAX_SHADOW_BLUR).contains(&shadow.blur) {
+        return Err(AppError::InvalidArgument(format!("Shadow blur must be between 0 and {}", MAX_SHADOW_BLUR)));
+    }
+    let background = parse_color(background)?;
+    let shadow_color = parse_color(&shadow.color)?;
+
+    let (w, h) = image.dimensions();
+    let (canvas_w, canvas_h) = (w + 2 * padding, h + 2 * padding);
+    let mask = rounded_mask(w, h, corner_radius.min(w.min(h) / 2) as f32);
+    let mut canvas = RgbaImage::from_pixel(canvas_w, canvas_h, background);
+
+    if shadow_color[3] > 0 {
+        // The image's silhouette, moved by the offset, blurred, then tinted
+        let mut alpha = vec![0f32; canvas_w as usize * canvas_h as usize];
+        let left = padding as i64 + shadow.offset_x as i64;
+        let top = padding as i64 + shadow.offset_y as i64;
+        for y in 0..h as i64 {
+            let cy = top + y;
+            if !(0..canvas_h as i64).contains(&cy) {
+                continue;
+            }
+            for x in 0..w as i64 {
+                let cx = left + x;
+                if (0..canvas_w as i64).contains(&cx) {
+                    alpha[(cy * canvas_w as i64 + cx) as usize] = mask[(y * w as i64 + x) as usize];
+                }
+            }
+        }
+        blur(&mut alpha, canvas_w as usize, canvas_h as usize, shadow.blur);
+        let strength = shadow_color[3] as f32 / 255.0;
+        for (pixel, a) in canvas.pixels_mut().zip(&alpha) {
+            blend(pixel, shadow_color, a * strength);
+        }
+    }
+
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let alpha = pixel[3] as f32 / 255.0 * mask[(y * w + x) as usize];
+        blend(canvas.get_pixel_mut(x + padding, y + padding), *pixel, alpha);
+    }
+    Ok(canvas)
+}
+
+/// Coverage (0-1) of each pixel by a `w`×`h` rectangle with rounded corners,
+/// sampled at pixel centers with a one-pixel soft edge
+fn rounded_mask(w: u32, h: u32, radius: f32) -> Vec<f32> {
+    let mut mask = vec![1f32; w as usize * h as usize];
+    if radius <= 0.0 {
+        return mask;
+    }
+    // How far a pixel center lies inside a corner square, per axis
+    let into_corner = |p: f32, size: f32| if p < radius { radius - p } else { (p - (size - radius)).max(0.0) };
+    for y in 0..h {
+        let dy = into_corner(y as f32 + 0.5, h as f32);
+        if dy == 0.0 {
+            continue;
+        }
+        for x in 0..w {
+            let dx = into_corner(x as f32 + 0.5, w as f32);
+            if dx > 0.0 {
+                mask[(y * w + x) as usize] = (radius - dx.hypot(dy) + 0.5).clamp(0.0, 1.0);
+            }
+        }
+    }
+    mask
+}
+
+/// Gaussian blur with this sigma, approximated by three box blurs per axis
+fn blur(values: &mut [f32], width: usize, height: usize, sigma: f32) {
+    if sigma < 0.5 {
+        return;
+    }
+    // Three boxes of width 2r + 1 have variance 3 ((2r + 1)² - 1) / 12 = sigma²
+    let radius = (((4.0 * sigma * sigma + 1.0).sqrt() - 1.0) / 2.0).round().max(1.0) as usize;
+    let mut scratch = vec![0f32; values.len()];
+    for _ in 0..3 {
+        box_pass(values, &mut scratch, width, height, radius, true);
+        box_pass(&scratch, values, width, height, radius, false);
+    }
+}
+
+/// Mean over `radius` values either side along rows or columns; beyond the
+/// edges counts as 0, so the shadow fades out at the canvas border
+fn box_pass(src: &[f32], dst: &mut [f32], width: usize, height: usize, radius: usize, horizontal: bool) {
+    let (lines, len, line_step, step) = if horizontal { (height, width, width, 1) } else { (width, height, 1, width) };
+    let norm = 1.0 / (2 * radius + 1) as f32;
+    for line in 0..lines {
+        let at = |i: usize| line * line_step + i * step;
+        let mut sum: f32 = (0..=radius.min(len - 1)).map(|i| src[at(i)]).sum();
+        for i in 0..len {
+            dst[at(i)] = sum * norm;
+            if i + radius + 1 < len {
+                sum += src[at(i + radius + 1)];
+            }
+            if i >= radius {
+                sum -= src[at(i - radius)];
+            }
+        }
+    }
+}
+
+/// Paint `src` over `dst` at `alpha` (0-1), both with straight alpha
+fn blend(dst: &mut Rgba<u8>, src: Rgba<u8>, alpha: f32) {
+    if alpha <= 0.0 {
+        return;
+    }
+    let below = dst[3] as f32 / 255.0 * (1.0 - alpha);
+    let out = alpha + below;
+    for c in 0..3 {
+        dst[c] = ((src[c] as f32 * alpha + dst[c] as f32 * below) / out).round() as u8;
+    }
+    dst[3] = (out * 255.0).round() as u8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rounded_mask;
+
+    #[test]
+    fn no_radius_covers_everything() {
+        assert!(rounded_mask(8, 4, 0.0).iter().all(|&m| m == 1.0));
+    }
+
+    #[test]
+    fn corners_are_cut_and_the_rest_kept() {
+        let (w, h) = (30u32, 20u32);
+        let mask = rounded_mask(w, h, 5.0);
+        let at = |x: u32, y: u32| mask[(y * w + x) as usize];
+        for (x, y) in [(0, 0), (w - 1, 0), (0, h - 1), (w - 1, h - 1)] {
+            assert_eq!(at(x, y), 0.0);
+        }
+        assert_eq!(at(w / 2, h / 2), 1.0);
+        assert_eq!(at(0, h / 2), 1.0);
+        assert_eq!(at(w / 2, 0), 1.0);
+        // Soft edge in between
+        assert!(at(1, 1) > 0.0 && at(1, 1) < 1.0);
+    }
+}
@@ -0,0 +1,140 @@
+// ============================================
+// ScreenAI Desktop — Diagnostics for bug reports
+// ============================================
+//
+// What support always asks for, in one struct the UI can copy into an issue:
+// versions, OS, displays, session type and which shortcuts the OS accepted.
+// Nothing that identifies the user goes in — no host or user name, no paths,
+// no config values beyond the shortcut bindings.
+
+use crate::capture::{self, MonitorInfo};
+use crate::config::Config;
+use serde::Serialize;
+use tauri::{AppHandle, GlobalShortcutManager};
+
+/// Returned by `get_diagnostics`
+#[derive(Clone, Serialize)]
+pub struct Diagnostics {
+    pub app_version: String,
+    pub tauri_version: &'static str,
+    /// "windows", "macos" or "linux"
+    pub os: &'static str,
+    /// e.g. "10.0.22631", "14.5" or "Ubuntu 24.04 LTS, kernel 6.8.0-31-generic"
+    pub os_version: String,
+    pub arch: &'static str,
+    /// "wayland" or "x11" on Linux
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_type: Option<&'static str>,
+    pub monitors: Vec<MonitorInfo>,
+    pub shortcuts: Vec<ShortcutStatus>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ShortcutStatus {
+    pub action: String,
+    pub accelerator: String,
+    /// False when another app holds the combination or the OS refused it
+    pub registered: bool,
+}
+
+pub fn collect(app: &AppHandle) -> Diagnostics {
+    let config = Config::load(app);
+    let manager = app.global_shortcut_manager();
+    let shortcuts = config
+        .shortcuts
+        .iter()
+        .filter(|(_, accelerator)| !accelerator.is_empty())
+        .map(|(action, accelerator)| ShortcutStatus {
+            action: action.clone(),
+            accelerator: accelerator.clone(),
+            registered: manager.is_registered(accelerator).unwrap_or(false),
+        })
+        .collect();
+    Diagnostics {
+        app_version: app.package_info().version.to_string(),
+        tauri_version: tauri::VERSION,
+        os: std::env::consts::OS,
+        os_version: os_version().unwrap_or_else(|| "unknown".to_string()),
+        arch: std::env::consts::ARCH,
+        session_type: session_type(),
+        monitors: capture::monitors().unwrap_or_default(),
+        shortcuts,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn session_type() -> Option<&'static str> {
+    Some(if crate::portal::is_wayland() { "wayland" } else { "x11" })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn session_type() -> Option<&'static str> {
+    None
+}
+
+#[cfg(target_os = "windows")]
+fn os_version() -> Option<String> {
+    #[repr(C)]
+    struct OsVersionInfo {
+        size: u32,
+        major: u32,
+        minor: u32,
+        build: u32,
+        platform_id: u32,
+        csd_version: [u16; 128],
+    }
+    #[link(name = "ntdll")]
+    extern "system" {
+        fn RtlGetVersion(info: *mut OsVersionInfo) -> i32;
+    }
+    // GetVersionEx reports 6.2 to apps without a compatibility manifest; this doesn't
+    let mut info = OsVersionInfo {
+        size: std::mem::size_of::<OsVersionInfo>() as u32,
+        major: 0,
+        minor: 0,
+        build: 0,
+        platform_id: 0,
+        csd_version: [0; 128],
+    };
+    if unsafe { RtlGetVersion(&mut info) } != 0 {
+        return None;
+    }
+    Some(format!("{}.{}.{}", info.major, info.minor, info.build))
+}
+
+#[cfg(target_os = "macos")]
+fn os_version() -> Option<String> {
+    let mut buf = [0u8; 64];
+    let mut len = buf.len();
+    let rc = unsafe {
+        libc::sysctlbyname(
+            c"kern.osproductversion".as_ptr(),
+            buf.as_mut_ptr() as *mut libc::c_void,
+            &mut len,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if rc != 0 {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&buf[..len]).trim_end_matches('\0').to_string())
+}
+
+/// Distribution from os-release and the kernel release (not `uname`'s host name)
+#[cfg(target_os = "linux")]
+fn os_version() -> Option<String> {
+    let distro = std::fs::read_to_string("/etc/os-release").ok().and_then(|release| {
+        release
+            .lines()
+            .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+            .map(|name| name.trim_matches('"').to_string())
+    });
+    let mut uts: libc::utsname = unsafe { std::mem::zeroed() };
+    let kernel = (unsafe { libc::uname(&mut uts) } == 0)
+        .then(|| unsafe { std::ffi::CStr::from_ptr(uts.release.as_ptr()) }.to_string_lossy().to_string());
+    match (distro, kernel) {
+        (Some(distro), Some(kernel)) => Some(format!("{}, kernel {}", distro, kernel)),
+        (distro, kernel) => distro.or(kernel),
+    }
+}
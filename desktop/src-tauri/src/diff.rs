@@ -0,0 +1,66 @@
+// ============================================
+// ScreenAI Desktop — Visual diff of two captures
+// ============================================
+//
+// A pixel counts as changed when its largest channel difference (alpha
+// included) exceeds the threshold. The diff image is the second capture faded
+// to a light grayscale, so unchanged areas still give context, with every
+// changed pixel painted solid red.
+
+use crate::capture::Rect;
+use crate::error::AppError;
+use image::{Rgba, RgbaImage};
+use serde::Serialize;
+
+/// Fraction of the full channel range (0-1); ignores lossy-compression noise
+pub const DEFAULT_THRESHOLD: f64 = 0.1;
+const CHANGED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+/// Returned by `diff_captures`
+#[derive(Clone, Serialize)]
+pub struct DiffResult {
+    pub data_url: String,
+    pub changed_pixels: u64,
+    pub total_pixels: u64,
+    /// 0-100
+    pub changed_percent: f64,
+    /// Smallest rectangle holding every change, `None` when nothing changed
+    pub bounds: Option<Rect>,
+}
+
+/// Compare `a` and `b` (same size) and build the highlighted image.
+/// Returns the image, the number of changed pixels and their bounding box.
+pub fn diff(a: &RgbaImage, b: &RgbaImage, threshold: f64) -> Result<(RgbaImage, u64, Option<Rect>), AppError> {
+    if a.dimensions() != b.dimensions() {
+        return Err(AppError::InvalidArgument(format!(
+            "Captures must be the same size to compare: {}x{} vs {}x{}",
+            a.width(),
+            a.height(),
+            b.width(),
+            b.height()
+        )));
+    }
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(AppError::InvalidArgument(format!("Threshold {} is outside 0-1", threshold)));
+    }
+    let limit = (threshold * 255.0).round() as u8;
+
+    let mut out = RgbaImage::new(a.width(), a.height());
+    let mut changed = 0u64;
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (u32::MAX, u32::MAX, 0, 0);
+    for (x, y, pixel) in out.enumerate_pixels_mut() {
+        let (pa, pb) = (a.get_pixel(x, y).0, b.get_pixel(x, y).0);
+        let delta = (0..4).map(|i| pa[i].abs_diff(pb[i])).max().unwrap_or(0);
+        if delta > limit {
+            *pixel = CHANGED;
+            changed += 1;
+            (min_x, min_y, max_x, max_y) = (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y));
+        } else {
+            let luma = (pb[0] as u32 * 77 + pb[1] as u32 * 150 + pb[2] as u32 * 29) >> 8;
+            let faded = (255 - (255 - luma) / 4) as u8;
+            *pixel = Rgba([faded, faded, faded, 255]);
+        }
+    }
+    let bounds = (changed > 0).then(|| Rect { x: min_x as i32, y: min_y as i32, w: max_x - min_x + 1, h: max_y - min_y + 1 });
+    Ok((out, changed, bounds))
+}
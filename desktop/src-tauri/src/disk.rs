@@ -0,0 +1,81 @@
+// ============================================
+// ScreenAI Desktop — Free disk space
+// ============================================
+//
+// Saves check the target volume before writing, so a full disk fails with
+// `insufficient_space` up front instead of leaving a truncated file behind.
+// The figure is the space available to the current user (quotas and the
+// root-reserved blocks on ext4 excluded), not the raw free space.
+
+use crate::error::AppError;
+use std::path::Path;
+
+/// Left free beyond the file itself, so a save never takes the last bytes
+/// the OS and other apps need
+const RESERVE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Bytes available to the current user on the volume holding `path`. The
+/// path doesn't have to exist yet; its nearest existing ancestor is asked.
+pub fn free_space(path: &Path) -> Result<u64, AppError> {
+    let existing = path
+        .ancestors()
+        .find(|p| p.exists())
+        .ok_or_else(|| AppError::Io(format!("No existing folder above {}", path.display())))?;
+    query(existing).map_err(|e| AppError::Io(format!("Could not read free space of {}: {}", existing.display(), e)))
+}
+
+/// Fail with `insufficient_space` unless `bytes` fit on the volume holding
+/// `path` with `RESERVE_BYTES` to spare. A volume we can't ask doesn't block the write.
+pub fn ensure_space(path: &Path, bytes: u64) -> Result<(), AppError> {
+    let available = match free_space(path) {
+        Ok(available) => available,
+        Err(e) => {
+            log::debug!("{}", e);
+            return Ok(());
+        }
+    };
+    let required = bytes.saturating_add(RESERVE_BYTES);
+    if available < required {
+        return Err(AppError::InsufficientSpace { available, required });
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn query(path: &Path) -> std::io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDiskFreeSpaceExW(
+            lpDirectoryName: *const u16,
+            lpFreeBytesAvailableToCaller: *mut u64,
+            lpTotalNumberOfBytes: *mut u64,
+            lpTotalNumberOfFreeBytes: *mut u64,
+        ) -> i32;
+    }
+
+    // Any path on the volume works; a file's own path does too
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let mut available = 0u64;
+    let ok = unsafe { GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, std::ptr::null_mut(), std::ptr::null_mut()) };
+    if ok == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(available)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn query(path: &Path) -> std::io::Result<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // f_bavail excludes the blocks reserved for root
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
@@ -0,0 +1,241 @@
+// ============================================
+// ScreenAI Desktop — Pixel edits on captures
+// ============================================
+//
+// Edits are burned into the pixels: the output carries no layers, so the
+// recipient of a shared capture cannot undo them.
+
+use crate::capture::Rect;
+use crate::error::AppError;
+use image::imageops::FilterType;
+use image::{Rgba, RgbaImage};
+
+pub const DEFAULT_BLUR_RADIUS: f32 = 12.0;
+pub const DEFAULT_BLOCK_SIZE: u32 = 16;
+
+#[derive(Clone, Copy, Debug)]
+pub enum RedactStyle {
+    /// Gaussian blur with this sigma
+    Blur(f32),
+    /// Mosaic of square blocks this many pixels wide
+    Pixelate(u32),
+    BlackBox,
+}
+
+impl RedactStyle {
+    pub fn parse(style: &str, blur_radius: Option<f32>, block_size: Option<u32>) -> Result<Self, AppError> {
+        match style.trim().to_ascii_lowercase().as_str() {
+            "blur" => Ok(RedactStyle::Blur(blur_radius.unwrap_or(DEFAULT_BLUR_RADIUS).clamp(1.0, 100.0))),
+            "pixelate" => Ok(RedactStyle::Pixelate(block_size.unwrap_or(DEFAULT_BLOCK_SIZE).clamp(2, 256))),
+            "blackbox" => Ok(RedactStyle::BlackBox),
+            other => Err(AppError::InvalidArgument(format!(
+                "Unknown redaction style \"{}\" (expected blur, pixelate or blackbox)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Lossless rotation or mirroring of a whole capture
+#[derive(Clone, Copy, Debug)]
+pub enum Transform {
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipH,
+    FlipV,
+}
+
+impl Transform {
+    pub fn parse(op: &str) -> Result<Self, AppError> {
+        match op.trim().to_ascii_lowercase().as_str() {
+            "rotate90" => Ok(Transform::Rotate90),
+            "rotate180" => Ok(Transform::Rotate180),
+            "rotate270" => Ok(Transform::Rotate270),
+            "flip_h" => Ok(Transform::FlipH),
+            "flip_v" => Ok(Transform::FlipV),
+            other => Err(AppError::InvalidArgument(format!(
+                "Unknown transform \"{}\" (expected rotate90, rotate180, rotate270, flip_h or flip_v)",
+                other
+            ))),
+        }
+    }
+
+    /// Rotations are clockwise; 90 and 270 swap width and height
+    pub fn apply(self, image: &RgbaImage) -> RgbaImage {
+        match self {
+            Transform::Rotate90 => image::imageops::rotate90(image),
+            Transform::Rotate180 => image::imageops::rotate180(image),
+            Transform::Rotate270 => image::imageops::rotate270(image),
+            Transform::FlipH => image::imageops::flip_horizontal(image),
+            Transform::FlipV => image::imageops::flip_vertical(image),
+        }
+    }
+}
+
+/// Clip `rect` (image pixels) to the image. `None` when nothing is left.
+pub fn clip(image: &RgbaImage, rect: Rect) -> Option<(u32, u32, u32, u32)> {
+    let left = rect.x.max(0) as u32;
+    let top = rect.y.max(0) as u32;
+    let right = (rect.x as i64 + rect.w as i64).clamp(0, image.width() as i64) as u32;
+    let bottom = (rect.y as i64 + rect.h as i64).clamp(0, image.height() as i64) as u32;
+    (right > left && bottom > top).then(|| (left, top, right - left, bottom - top))
+}
+
+/// Cut out `w`×`h` pixels at (`x`, `y`). The rectangle must lie inside the image.
+pub fn crop(image: &RgbaImage, x: u32, y: u32, w: u32, h: u32) -> Result<RgbaImage, AppError> {
+    if w == 0 || h == 0 {
+        return Err(AppError::InvalidArgument("Crop area is empty".to_string()));
+    }
+    let fits = |start: u32, len: u32, max: u32| start.checked_add(len).is_some_and(|end| end <= max);
+    if !fits(x, w, image.width()) || !fits(y, h, image.height()) {
+        return Err(AppError::InvalidArgument(format!(
+            "Crop area {}x{} at ({}, {}) exceeds the {}x{} image",
+            w,
+            h,
+            x,
+            y,
+            image.width(),
+            image.height()
+        )));
+    }
+    Ok(image::imageops::crop_imm(image, x, y, w, h).to_image())
+}
+
+/// Parse a resampling filter name: "nearest", "triangle" (default) or "lanczos3"
+pub fn parse_filter(filter: Option<&str>) -> Result<FilterType, AppError> {
+    match filter.map(|f| f.trim().to_ascii_lowercase()).as_deref() {
+        None | Some("") | Some("triangle") => Ok(FilterType::Triangle),
+        Some("nearest") => Ok(FilterType::Nearest),
+        Some("lanczos3") => Ok(FilterType::Lanczos3),
+        Some(other) => Err(AppError::InvalidArgument(format!(
+            "Unknown resize filter \"{}\" (expected nearest, triangle or lanczos3)",
+            other
+        ))),
+    }
+}
+
+/// Shrink `image` to fit within `max_width`×`max_height`, keeping its aspect
+/// ratio. Images that already fit are returned unchanged — never upscaled.
+pub fn downscale(image: RgbaImage, max_width: u32, max_height: u32, filter: FilterType) -> Result<RgbaImage, AppError> {
+    if max_width == 0 || max_height == 0 {
+        return Err(AppError::InvalidArgument("Maximum size must be at least 1x1".to_string()));
+    }
+    let (w, h) = image.dimensions();
+    let fit = (max_width as f64 / w as f64).min(max_height as f64 / h as f64);
+    if fit >= 1.0 {
+        return Ok(image);
+    }
+    let new_w = ((w as f64 * fit).round() as u32).clamp(1, max_width);
+    let new_h = ((h as f64 * fit).round() as u32).clamp(1, max_height);
+    Ok(image::imageops::resize(&image, new_w, new_h, filter))
+}
+
+/// Apply `style` to each region in place. Regions outside the image are skipped.
+pub fn redact(image: &mut RgbaImage, regions: &[Rect], style: RedactStyle) {
+    for &rect in regions {
+        let Some((x, y, w, h)) = clip(image, rect) else { continue };
+        match style {
+            RedactStyle::Blur(sigma) => {
+                let area = image::imageops::crop_imm(image, x, y, w, h).to_image();
+                let blurred = image::imageops::blur(&area, sigma);
+                image::imageops::replace(image, &blurred, x as i64, y as i64);
+            }
+            RedactStyle::Pixelate(block) => pixelate(image, (x, y, w, h), block),
+            RedactStyle::BlackBox => {
+                for py in y..y + h {
+                    for px in x..x + w {
+                        image.put_pixel(px, py, Rgba([0, 0, 0, 255]));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Blur along a freehand stroke through `points` (image pixels, clamped to the
+/// image). The stroke is `brush_size` pixels wide with round ends; `radius` is
+/// the blur sigma.
+pub fn blur_path(image: &mut RgbaImage, points: &[(u32, u32)], radius: f32, brush_size: u32) {
+    let (width, height) = image.dimensions();
+    if points.is_empty() || width == 0 || height == 0 {
+        return;
+    }
+    let points: Vec<(f32, f32)> = points
+        .iter()
+        .map(|&(x, y)| (x.min(width - 1) as f32, y.min(height - 1) as f32))
+        .collect();
+    let half = (brush_size.max(1) as f32) / 2.0;
+
+    // Work on the stroke's bounding box, padded so the blur can pull in
+    // surrounding pixels instead of fading to the crop edge
+    let pad = half + radius * 3.0;
+    let min = |f: fn(&(f32, f32)) -> f32| points.iter().map(f).fold(f32::MAX, f32::min);
+    let max = |f: fn(&(f32, f32)) -> f32| points.iter().map(f).fold(f32::MIN, f32::max);
+    let left = (min(|p| p.0) - pad).max(0.0) as u32;
+    let top = (min(|p| p.1) - pad).max(0.0) as u32;
+    let right = ((max(|p| p.0) + pad).ceil() as u32).min(width);
+    let bottom = ((max(|p| p.1) + pad).ceil() as u32).min(height);
+    let (w, h) = (right - left, bottom - top);
+
+    // Stamp discs along each segment, closer together than their radius
+    let mut mask = vec![false; (w * h) as usize];
+    let mut stamp = |cx: f32, cy: f32| {
+        let x0 = (cx - half).floor().max(left as f32) as u32;
+        let y0 = (cy - half).floor().max(top as f32) as u32;
+        let x1 = ((cx + half).ceil() as u32).min(right - 1);
+        let y1 = ((cy + half).ceil() as u32).min(bottom - 1);
+        for py in y0..=y1 {
+            for px in x0..=x1 {
+                let (dx, dy) = (px as f32 - cx, py as f32 - cy);
+                if dx * dx + dy * dy <= half * half {
+                    mask[((py - top) * w + (px - left)) as usize] = true;
+                }
+            }
+        }
+    };
+    stamp(points[0].0, points[0].1);
+    for pair in points.windows(2) {
+        let ((ax, ay), (bx, by)) = (pair[0], pair[1]);
+        let len = ((bx - ax).powi(2) + (by - ay).powi(2)).sqrt();
+        let steps = (len / (half / 2.0).max(0.5)).ceil().max(1.0) as u32;
+        for i in 1..=steps {
+            let t = i as f32 / steps as f32;
+            stamp(ax + (bx - ax) * t, ay + (by - ay) * t);
+        }
+    }
+
+    let area = image::imageops::crop_imm(image, left, top, w, h).to_image();
+    let blurred = image::imageops::blur(&area, radius);
+    for py in 0..h {
+        for px in 0..w {
+            if mask[(py * w + px) as usize] {
+                image.put_pixel(left + px, top + py, *blurred.get_pixel(px, py));
+            }
+        }
+    }
+}
+
+/// Replace each `block`×`block` cell of the area with its average colour
+fn pixelate(image: &mut RgbaImage, (x, y, w, h): (u32, u32, u32, u32), block: u32) {
+    for by in (y..y + h).step_by(block as usize) {
+        for bx in (x..x + w).step_by(block as usize) {
+            let (bw, bh) = (block.min(x + w - bx), block.min(y + h - by));
+            let mut sum = [0u64; 4];
+            for py in by..by + bh {
+                for px in bx..bx + bw {
+                    for (s, c) in sum.iter_mut().zip(image.get_pixel(px, py).0) {
+                        *s += c as u64;
+                    }
+                }
+            }
+            let n = (bw * bh) as u64;
+            let avg = Rgba(sum.map(|s| (s / n) as u8));
+            for py in by..by + bh {
+                for px in bx..bx + bw {
+                    image.put_pixel(px, py, avg);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+// ============================================
+// ScreenAI Desktop — Typed command errors
+// ============================================
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+
+/// Error returned by capture, image and updater commands.
+/// Crosses the Tauri boundary as `{ code, message }` so the frontend can
+/// branch on `code` instead of parsing Rust error text.
+#[derive(Debug, Clone)]
+pub enum AppError {
+    NoScreen,
+    MonitorNotFound(usize),
+    /// `capture_last_region` was called before any region was captured
+    NoPreviousRegion,
+    /// The user dismissed the native interactive selection (macOS `screencapture -i`)
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    CaptureCancelled,
+    CaptureFailed(String),
+    /// Width × height would exceed `Config::max_pixels`
+    CaptureTooLarge { width: u32, height: u32, max_pixels: u64 },
+    /// The screen grab didn't return within `Config::capture_timeout_ms`
+    #[cfg_attr(target_os = "macos", allow(dead_code))]
+    CaptureTimeout(u64),
+    /// The OS or the user refused screen access (e.g. the Wayland portal dialog)
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    PermissionDenied(String),
+    EncodeFailed(String),
+    DecodeFailed(String),
+    ClipboardUnavailable(String),
+    /// The clipboard holds text, files or nothing, but no image
+    ClipboardNoImage,
+    InvalidArgument(String),
+    Io(String),
+    /// The target volume has less free space than the file needs
+    InsufficientSpace { available: u64, required: u64 },
+    /// The OS refused a global shortcut (usually already taken by another app)
+    ShortcutUnavailable(String),
+    UpdaterUnavailable,
+    UpdateFailed(String),
+    /// The update server couldn't be reached, even after retrying
+    UpdateNetwork(String),
+    /// `cancel_update` stopped the download
+    UpdateCancelled,
+    /// Another update check (e.g. the periodic one) hasn't finished yet
+    UpdateCheckInProgress,
+    /// Registering or removing the launch-at-login entry failed
+    AutostartFailed(String),
+    /// The vision model request failed or returned an error
+    AnalysisFailed(String),
+    /// The tesseract CLI is not installed; the frontend falls back to tesseract.js
+    OcrUnavailable,
+    OcrFailed(String),
+    UploadFailed(String),
+    /// The upload service rejected the configured credentials
+    UploadUnauthorized(String),
+    /// Above our own or the service's size limit (`None` = only the service knows it)
+    UploadTooLarge { bytes: u64, max_bytes: Option<u64> },
+}
+
+impl AppError {
+    /// Stable machine-readable identifier sent to the frontend
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NoScreen => "no_screen",
+            AppError::MonitorNotFound(_) => "monitor_not_found",
+            AppError::NoPreviousRegion => "no_previous_region",
+            AppError::CaptureCancelled => "capture_cancelled",
+            AppError::CaptureFailed(_) => "capture_failed",
+            AppError::CaptureTooLarge { .. } => "capture_too_large",
+            AppError::CaptureTimeout(_) => "capture_timeout",
+            AppError::PermissionDenied(_) => "permission_denied",
+            AppError::EncodeFailed(_) => "encode_failed",
+            AppError::DecodeFailed(_) => "decode_failed",
+            AppError::ClipboardUnavailable(_) => "clipboard_unavailable",
+            AppError::ClipboardNoImage => "clipboard_no_image",
+            AppError::InvalidArgument(_) => "invalid_argument",
+            AppError::Io(_) => "io",
+            AppError::InsufficientSpace { .. } => "insufficient_space",
+            AppError::ShortcutUnavailable(_) => "shortcut_unavailable",
+            AppError::UpdaterUnavailable => "updater_unavailable",
+            AppError::UpdateFailed(_) => "update_failed",
+            AppError::UpdateNetwork(_) => "update_network",
+            AppError::UpdateCancelled => "update_cancelled",
+            AppError::UpdateCheckInProgress => "update_check_in_progress",
+            AppError::AutostartFailed(_) => "autostart_failed",
+            AppError::AnalysisFailed(_) => "analysis_failed",
+            AppError::OcrUnavailable => "tesseract_not_found",
+            AppError::OcrFailed(_) => "ocr_failed",
+            AppError::UploadFailed(_) => "upload_failed",
+            AppError::UploadUnauthorized(_) => "upload_unauthorized",
+            AppError::UploadTooLarge { .. } => "upload_too_large",
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NoScreen => write!(
+                f,
+                "No display to capture. Remote desktop and headless sessions may not have one; \
+                 reconnect with a display attached and try again"
+            ),
+            AppError::MonitorNotFound(i) => write!(f, "Monitor {} not found", i),
+            AppError::NoPreviousRegion => write!(f, "No region has been captured yet"),
+            AppError::CaptureCancelled => write!(f, "Capture was cancelled"),
+            AppError::CaptureFailed(e) => write!(f, "Capture failed: {}", e),
+            AppError::CaptureTooLarge { width, height, max_pixels } => write!(
+                f,
+                "Capture would be {}×{} ({:.1} MP), above the {:.1} MP limit",
+                width,
+                height,
+                (*width as u64 * *height as u64) as f64 / 1e6,
+                *max_pixels as f64 / 1e6
+            ),
+            AppError::CaptureTimeout(ms) => write!(f, "The screen grab did not finish within {} ms; try again", ms),
+            AppError::PermissionDenied(e) => write!(f, "Permission denied: {}", e),
+            AppError::EncodeFailed(e) => write!(f, "Failed to encode image: {}", e),
+            AppError::DecodeFailed(e) => write!(f, "Failed to decode image: {}", e),
+            AppError::ClipboardUnavailable(e) => write!(f, "Clipboard unavailable: {}", e),
+            AppError::ClipboardNoImage => write!(f, "The clipboard holds no image"),
+            AppError::InvalidArgument(e) => write!(f, "{}", e),
+            AppError::Io(e) => write!(f, "{}", e),
+            AppError::InsufficientSpace { available, required } => write!(
+                f,
+                "Not enough disk space: {:.1} MB free, {:.1} MB needed",
+                *available as f64 / 1e6,
+                *required as f64 / 1e6
+            ),
+            AppError::ShortcutUnavailable(e) => write!(f, "Could not register shortcut {}", e),
+            AppError::UpdaterUnavailable => write!(f, "Updater is not configured yet"),
+            AppError::UpdateFailed(e) => write!(f, "Update failed: {}", e),
+            AppError::UpdateNetwork(e) => write!(f, "Could not reach the update server: {}", e),
+            AppError::UpdateCancelled => write!(f, "Update was cancelled"),
+            AppError::UpdateCheckInProgress => write!(f, "An update check is already running"),
+            AppError::AutostartFailed(e) => write!(f, "Could not change launch at login: {}", e),
+            AppError::AnalysisFailed(e) => write!(f, "AI analysis failed: {}", e),
+            AppError::OcrUnavailable => write!(f, "Tesseract is not installed"),
+            AppError::OcrFailed(e) => write!(f, "Text recognition failed: {}", e),
+            AppError::UploadFailed(e) => write!(f, "Upload failed: {}", e),
+            AppError::UploadUnauthorized(e) => write!(f, "Upload credentials were rejected: {}", e),
+            AppError::UploadTooLarge { bytes, max_bytes: Some(max) } => write!(
+                f,
+                "Capture is {:.1} MB, above the {:.1} MB upload limit",
+                *bytes as f64 / 1e6,
+                *max as f64 / 1e6
+            ),
+            AppError::UploadTooLarge { bytes, max_bytes: None } => {
+                write!(f, "Capture is {:.1} MB, more than the upload service accepts", *bytes as f64 / 1e6)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
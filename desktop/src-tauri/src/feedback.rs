@@ -0,0 +1,147 @@
+// ============================================
+// ScreenAI Desktop — Capture sound and notification
+// ============================================
+//
+// Optional feedback after a successful capture: a short shutter sound
+// (`playShutterSound`) and a native notification (`showNotification`). The
+// sound goes through what the OS already ships — PlaySound on Windows,
+// afplay on macOS, PulseAudio/ALSA on Linux — and the notification through
+// Tauri's notification API under ScreenAI's own app ID, so it honours Focus /
+// Focus Assist / Do Not Disturb the same way the user's other apps do. Where
+// we can read the do-not-disturb state ourselves, the sound is skipped too.
+
+use crate::capture::CapturePayload;
+use crate::config::Config;
+use tauri::api::notification::Notification;
+use tauri::AppHandle;
+
+/// 16-bit mono WAV, about 120 ms
+static SHUTTER_WAV: &[u8] = include_bytes!("../sounds/shutter.wav");
+
+const NOTIFICATION_TITLE: &str = "Screenshot taken";
+
+/// Play the sound and/or show the notification the config asks for, off the
+/// capture's thread
+pub fn after_capture(app: &AppHandle, payload: &CapturePayload) {
+    let config = Config::load(app);
+    if !config.play_shutter_sound && !config.show_notification {
+        return;
+    }
+    let (app, payload) = (app.clone(), payload.clone());
+    tauri::async_runtime::spawn_blocking(move || signal(&app, &payload, config.play_shutter_sound, config.show_notification));
+}
+
+/// Sound and/or notification for `payload`, on the calling thread. Nothing
+/// happens while do not disturb is on.
+pub fn signal(app: &AppHandle, payload: &CapturePayload, sound: bool, notification: bool) {
+    if do_not_disturb() {
+        log::debug!("Do not disturb is on, capture feedback skipped");
+        return;
+    }
+    if sound {
+        play_shutter(app);
+    }
+    if notification {
+        notify(app, NOTIFICATION_TITLE, &format!("{} × {}", payload.width, payload.height));
+    }
+}
+
+fn notify(app: &AppHandle, title: &str, body: &str) {
+    let shown = Notification::new(&app.config().tauri.bundle.identifier).title(title).body(body).show();
+    if let Err(e) = shown {
+        log::warn!("Could not show the capture notification: {}", e);
+    }
+}
+
+/// Whether the user asked not to be interrupted: presentation mode, a
+/// full-screen app or quiet hours
+#[cfg(target_os = "windows")]
+fn do_not_disturb() -> bool {
+    const QUNS_ACCEPTS_NOTIFICATIONS: i32 = 5;
+    #[link(name = "shell32")]
+    extern "system" {
+        fn SHQueryUserNotificationState(pquns: *mut i32) -> i32;
+    }
+    let mut state = QUNS_ACCEPTS_NOTIFICATIONS;
+    let hr = unsafe { SHQueryUserNotificationState(&mut state) };
+    hr == 0 && state != QUNS_ACCEPTS_NOTIFICATIONS
+}
+
+/// Focus state isn't readable without private API; Notification Center still
+/// holds back the notification itself
+#[cfg(target_os = "macos")]
+fn do_not_disturb() -> bool {
+    false
+}
+
+/// GNOME's "Do Not Disturb" switch. Other desktops leave it to their
+/// notification daemon. Asking means running `gsettings`, so the answer is
+/// reused for `DND_CACHE_TTL` rather than spawning it on every capture.
+#[cfg(target_os = "linux")]
+fn do_not_disturb() -> bool {
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+    const DND_CACHE_TTL: Duration = Duration::from_secs(30);
+    static CACHED: Mutex<Option<(Instant, bool)>> = Mutex::new(None);
+
+    let mut cached = CACHED.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((at, on)) = *cached {
+        if at.elapsed() < DND_CACHE_TTL {
+            return on;
+        }
+    }
+    let on = std::process::Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.notifications", "show-banners"])
+        .output()
+        .map(|out| out.status.success() && String::from_utf8_lossy(&out.stdout).trim() == "false")
+        .unwrap_or(false);
+    *cached = Some((Instant::now(), on));
+    on
+}
+
+#[cfg(target_os = "windows")]
+fn play_shutter(_app: &AppHandle) {
+    const SND_ASYNC: u32 = 0x0001;
+    const SND_NODEFAULT: u32 = 0x0002;
+    const SND_MEMORY: u32 = 0x0004;
+    #[link(name = "winmm")]
+    extern "system" {
+        fn PlaySoundW(pszSound: *const u8, hmod: isize, fdwSound: u32) -> i32;
+    }
+    // SND_MEMORY reads the WAV straight from the embedded bytes, which live forever
+    if unsafe { PlaySoundW(SHUTTER_WAV.as_ptr(), 0, SND_MEMORY | SND_ASYNC | SND_NODEFAULT) } == 0 {
+        log::warn!("Could not play the shutter sound");
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn play_shutter(app: &AppHandle) {
+    // The players want a file; written once into our own cache dir, where
+    // other users can't plant a file of their own in its place
+    let Some(dir) = app.path_resolver().app_cache_dir() else {
+        log::warn!("Could not play the shutter sound: no app cache directory");
+        return;
+    };
+    let path = dir.join("shutter.wav");
+    if !path.exists() {
+        if let Err(e) = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, SHUTTER_WAV)) {
+            log::warn!("Could not write the shutter sound: {}", e);
+            return;
+        }
+    }
+    #[cfg(target_os = "macos")]
+    let players: &[&str] = &["afplay"];
+    #[cfg(not(target_os = "macos"))]
+    let players: &[&str] = &["paplay", "pw-play", "aplay"];
+    for player in players {
+        let mut command = std::process::Command::new(player);
+        if *player == "aplay" {
+            command.arg("-q");
+        }
+        match command.arg(&path).stdout(std::process::Stdio::null()).stderr(std::process::Stdio::null()).status() {
+            Ok(status) if status.success() => return,
+            _ => continue,
+        }
+    }
+    log::warn!("Could not play the shutter sound: no working audio player");
+}
@@ -0,0 +1,179 @@
+// ============================================
+// ScreenAI Desktop — Capture history
+// ============================================
+//
+// Every capture is kept in `<app data>/history/` as the original file plus a
+// small JPEG thumbnail. `index.json` lists entries newest first and is capped at
+// `Config::history_max`; the oldest entries are evicted with their files.
+
+use crate::capture::CapturePayload;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::imaging::{self, OutputFormat};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+const THUMBNAIL_SIZE: u32 = 256;
+
+/// Serializes index read-modify-write between concurrent captures
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+/// Disambiguates captures taken within the same millisecond
+static ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: String,
+    /// Unix time in milliseconds
+    pub timestamp: u64,
+    pub mode: String,
+    pub width: u32,
+    pub height: u32,
+    /// Full image file name inside the history dir
+    file: String,
+}
+
+/// Entry as returned to the UI, with its thumbnail inlined
+#[derive(Clone, Serialize)]
+pub struct HistoryItem {
+    #[serde(flatten)]
+    pub entry: HistoryEntry,
+    pub thumbnail: String,
+}
+
+fn history_dir(app: &AppHandle) -> Result<PathBuf, AppError> {
+    app.path_resolver()
+        .app_data_dir()
+        .map(|dir| dir.join("history"))
+        .ok_or_else(|| AppError::Io("No app data directory".to_string()))
+}
+
+fn thumbnail_file(id: &str) -> String {
+    format!("{}_thumb.jpg", id)
+}
+
+fn load_index(dir: &Path) -> Vec<HistoryEntry> {
+    std::fs::read_to_string(dir.join("index.json"))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn save_index(dir: &Path, entries: &[HistoryEntry]) -> Result<(), AppError> {
+    let text = serde_json::to_string_pretty(entries).map_err(|e| AppError::Io(e.to_string()))?;
+    let tmp = dir.join("index.json.tmp");
+    std::fs::write(&tmp, text).map_err(|e| AppError::Io(e.to_string()))?;
+    std::fs::rename(&tmp, dir.join("index.json")).map_err(|e| AppError::Io(e.to_string()))
+}
+
+/// Wait for a history write in progress to finish (used before quitting)
+pub fn flush() {
+    drop(INDEX_LOCK.lock());
+}
+
+fn remove_files(dir: &Path, entry: &HistoryEntry) {
+    let _ = std::fs::remove_file(dir.join(&entry.file));
+    let _ = std::fs::remove_file(dir.join(thumbnail_file(&entry.id)));
+}
+
+fn read_data_url(path: &Path) -> Result<String, AppError> {
+    let format = OutputFormat::from_extension(path)?;
+    let bytes = std::fs::read(path).map_err(|e| AppError::Io(e.to_string()))?;
+    Ok(imaging::to_data_url(&bytes, format))
+}
+
+fn add(app: &AppHandle, payload: &CapturePayload) -> Result<(), AppError> {
+    let max = Config::load(app).history_max;
+    if max == 0 {
+        return Ok(());
+    }
+
+    let bytes = imaging::data_url_bytes(&payload.data_url)?;
+    let image = image::load_from_memory(&bytes).map_err(|e| AppError::DecodeFailed(e.to_string()))?;
+    let ext = match image::guess_format(&bytes) {
+        Ok(image::ImageFormat::Jpeg) => "jpg",
+        Ok(image::ImageFormat::WebP) => "webp",
+        Ok(image::ImageFormat::Avif) => "avif",
+        _ => "png",
+    };
+    let thumbnail = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgba8();
+    let thumbnail = imaging::encode(&thumbnail, OutputFormat::Jpeg(75))?;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let id = format!("{}-{}", timestamp, ID_COUNTER.fetch_add(1, Ordering::Relaxed));
+    let entry = HistoryEntry {
+        file: format!("{}.{}", id, ext),
+        id,
+        timestamp,
+        mode: payload.mode.clone(),
+        width: image.width(),
+        height: image.height(),
+    };
+
+    let dir = history_dir(app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| AppError::Io(e.to_string()))?;
+    std::fs::write(dir.join(&entry.file), &bytes).map_err(|e| AppError::Io(e.to_string()))?;
+    std::fs::write(dir.join(thumbnail_file(&entry.id)), &thumbnail).map_err(|e| AppError::Io(e.to_string()))?;
+
+    let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut entries = load_index(&dir);
+    entries.insert(0, entry);
+    if entries.len() > max {
+        for old in entries.drain(max..) {
+            remove_files(&dir, &old);
+        }
+    }
+    save_index(&dir, &entries)
+}
+
+/// Store a capture in the history without blocking the caller.
+/// Failures are logged — history must never break a capture.
+pub fn record(app: &AppHandle, payload: &CapturePayload) {
+    let app = app.clone();
+    let payload = payload.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = add(&app, &payload) {
+            log::warn!("Could not save capture to history: {}", e);
+        }
+    });
+}
+
+/// All entries, newest first, with thumbnails as data URLs
+pub fn list(app: &AppHandle) -> Result<Vec<HistoryItem>, AppError> {
+    let dir = history_dir(app)?;
+    let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(load_index(&dir)
+        .into_iter()
+        .filter_map(|entry| {
+            let thumbnail = read_data_url(&dir.join(thumbnail_file(&entry.id))).ok()?;
+            Some(HistoryItem { entry, thumbnail })
+        })
+        .collect())
+}
+
+/// Full-size image of one entry as a data URL
+pub fn get(app: &AppHandle, id: &str) -> Result<String, AppError> {
+    let dir = history_dir(app)?;
+    let entry = {
+        let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        load_index(&dir).into_iter().find(|e| e.id == id)
+    }
+    .ok_or_else(|| AppError::InvalidArgument(format!("History item {} not found", id)))?;
+    read_data_url(&dir.join(&entry.file))
+}
+
+/// Delete every stored capture. Returns how many entries were removed.
+pub fn clear(app: &AppHandle) -> Result<usize, AppError> {
+    let dir = history_dir(app)?;
+    let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let entries = load_index(&dir);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).map_err(|e| AppError::Io(e.to_string()))?;
+    }
+    Ok(entries.len())
+}
@@ -0,0 +1,373 @@
+// ============================================
+// ScreenAI Desktop — Image encoding helpers
+// ============================================
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use crate::error::AppError;
+use image::buffer::ConvertBuffer;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::codecs::webp::{WebPEncoder, WebPQuality};
+use image::{ColorType, ImageEncoder, ImageOutputFormat, RgbImage, RgbaImage};
+use std::io::Cursor;
+
+const DEFAULT_JPEG_QUALITY: u8 = 85;
+const DEFAULT_WEBP_QUALITY: u8 = 80;
+#[cfg(feature = "avif")]
+const DEFAULT_AVIF_QUALITY: u8 = 70;
+
+/// Output encoding for captured images
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Png,
+    Jpeg(u8),
+    WebP(u8),
+    /// Much smaller than PNG for flat UI colours, still pixel-exact
+    WebPLossless,
+    /// Smallest output but slow to encode; needs the `avif` cargo feature
+    #[cfg_attr(not(feature = "avif"), allow(dead_code))]
+    Avif(u8),
+}
+
+impl OutputFormat {
+    /// Parse the `format` / `quality` pair passed from JS.
+    /// `None` keeps PNG; quality only applies to lossy formats and is clamped to 1–100.
+    pub fn parse(format: Option<&str>, quality: Option<u8>) -> Result<Self, AppError> {
+        let lossy = |default: u8| quality.unwrap_or(default).clamp(1, 100);
+        match format.map(|f| f.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("png") => Ok(OutputFormat::Png),
+            Some("jpeg") | Some("jpg") => Ok(OutputFormat::Jpeg(lossy(DEFAULT_JPEG_QUALITY))),
+            Some("webp") => Ok(OutputFormat::WebP(lossy(DEFAULT_WEBP_QUALITY))),
+            Some("webp-lossless") => Ok(OutputFormat::WebPLossless),
+            #[cfg(feature = "avif")]
+            Some("avif") => Ok(OutputFormat::Avif(lossy(DEFAULT_AVIF_QUALITY))),
+            #[cfg(not(feature = "avif"))]
+            Some("avif") => Err(AppError::EncodeFailed(
+                "AVIF support is not included in this build".to_string(),
+            )),
+            Some(other) => Err(AppError::InvalidArgument(format!(
+                "Unsupported image format \"{}\" (expected png, jpeg, webp, webp-lossless or avif)",
+                other
+            ))),
+        }
+    }
+
+    /// Pick the format matching a file extension (`shot.jpg` → JPEG)
+    pub fn from_extension(path: &std::path::Path) -> Result<Self, AppError> {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .ok_or_else(|| AppError::InvalidArgument(format!("Missing file extension in {}", path.display())))?;
+        Self::parse(Some(ext), None)
+    }
+
+    /// Usual file extension, without the dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Jpeg(_) => "jpg",
+            OutputFormat::WebP(_) | OutputFormat::WebPLossless => "webp",
+            OutputFormat::Avif(_) => "avif",
+        }
+    }
+
+    pub fn mime(self) -> &'static str {
+        match self {
+            OutputFormat::Png => "image/png",
+            OutputFormat::Jpeg(_) => "image/jpeg",
+            OutputFormat::WebP(_) | OutputFormat::WebPLossless => "image/webp",
+            OutputFormat::Avif(_) => "image/avif",
+        }
+    }
+}
+
+/// Speed/size trade-off for PNG output. Typical 4K desktop capture:
+/// "fast" encodes several times quicker than "default" but the file is
+/// 20-50% larger; "best" saves another few percent for roughly twice the time.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PngCompression {
+    Fast,
+    #[default]
+    Default,
+    Best,
+}
+
+impl PngCompression {
+    pub fn parse(level: Option<&str>) -> Result<Self, AppError> {
+        match level.map(|l| l.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("default") => Ok(PngCompression::Default),
+            Some("fast") => Ok(PngCompression::Fast),
+            Some("best") => Ok(PngCompression::Best),
+            Some(other) => Err(AppError::InvalidArgument(format!(
+                "Unknown PNG compression \"{}\" (expected fast, default or best)",
+                other
+            ))),
+        }
+    }
+
+    fn settings(self) -> (CompressionType, FilterType) {
+        match self {
+            // The Sub filter is nearly free and keeps the fast deflate path effective
+            PngCompression::Fast => (CompressionType::Fast, FilterType::Sub),
+            PngCompression::Default => (CompressionType::Default, FilterType::Adaptive),
+            PngCompression::Best => (CompressionType::Best, FilterType::Adaptive),
+        }
+    }
+}
+
+/// Encode raw 8-bit `RGBA` or `L` pixels as PNG at `compression`
+pub fn encode_png(raw: &[u8], width: u32, height: u32, color: ColorType, compression: PngCompression) -> Result<Vec<u8>, AppError> {
+    let mut buf = Vec::new();
+    let (compression, filter) = compression.settings();
+    PngEncoder::new_with_quality(&mut buf, compression, filter)
+        .write_image(raw, width, height, color)
+        .map_err(|e| AppError::EncodeFailed(e.to_string()))?;
+    Ok(buf)
+}
+
+/// Chroma subsampling for JPEG output. "444" keeps full colour detail, which
+/// matters for gradients, coloured text and video frames; "422" halves the
+/// colour resolution horizontally and "420" in both directions, the usual
+/// choice for photos (around 10-25% smaller than "444" at the same quality,
+/// with colour fringes on sharp coloured edges). Written as the file's
+/// sampling factors; no EXIF or other metadata is written.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum JpegSubsampling {
+    S444,
+    S422,
+    #[default]
+    S420,
+}
+
+impl JpegSubsampling {
+    pub fn parse(value: Option<&str>) -> Result<Self, AppError> {
+        match value.map(|v| v.trim().replace(':', "")).as_deref() {
+            None | Some("420") => Ok(JpegSubsampling::S420),
+            Some("422") => Ok(JpegSubsampling::S422),
+            Some("444") => Ok(JpegSubsampling::S444),
+            Some(_) => Err(AppError::InvalidArgument(format!(
+                "Unknown JPEG subsampling \"{}\" (expected 444, 422 or 420)",
+                value.unwrap_or_default()
+            ))),
+        }
+    }
+
+    fn sampling_factor(self) -> jpeg_encoder::SamplingFactor {
+        match self {
+            JpegSubsampling::S444 => jpeg_encoder::SamplingFactor::R_4_4_4,
+            JpegSubsampling::S422 => jpeg_encoder::SamplingFactor::R_4_2_2,
+            JpegSubsampling::S420 => jpeg_encoder::SamplingFactor::R_4_2_0,
+        }
+    }
+}
+
+/// Encode as JPEG at `quality` with `subsampling`; alpha is dropped. Used for
+/// captures, where the subsampling is a setting; everything else goes through
+/// `encode`, which keeps full-resolution colour.
+pub fn encode_jpeg(image: &RgbaImage, quality: u8, subsampling: JpegSubsampling) -> Result<Vec<u8>, AppError> {
+    let too_large = || AppError::EncodeFailed("JPEG images can be at most 65535 pixels on a side".to_string());
+    let width = u16::try_from(image.width()).map_err(|_| too_large())?;
+    let height = u16::try_from(image.height()).map_err(|_| too_large())?;
+    let mut buf = Vec::new();
+    let mut encoder = jpeg_encoder::Encoder::new(&mut buf, quality);
+    encoder.set_sampling_factor(subsampling.sampling_factor());
+    encoder
+        .encode(image.as_raw(), width, height, jpeg_encoder::ColorType::Rgba)
+        .map_err(|e| AppError::EncodeFailed(e.to_string()))?;
+    Ok(buf)
+}
+
+pub const DEFAULT_MONO_THRESHOLD: u8 = 128;
+
+/// Colour reduction applied to a capture before encoding.
+/// PNG and JPEG store grayscale and mono as 8-bit gray (about a third of the
+/// RGBA size); WebP and AVIF have no gray mode and keep three identical channels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorMode {
+    Rgba,
+    Grayscale,
+    /// Black and white: luma at or above the threshold becomes white
+    Mono(u8),
+}
+
+impl ColorMode {
+    /// Parse "rgba" (default), "grayscale" or "mono"; `threshold` only applies to mono
+    pub fn parse(mode: Option<&str>, threshold: Option<u8>) -> Result<Self, AppError> {
+        match mode.map(|m| m.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("rgba") | Some("color") => Ok(ColorMode::Rgba),
+            Some("grayscale") | Some("gray") => Ok(ColorMode::Grayscale),
+            Some("mono") => Ok(ColorMode::Mono(threshold.unwrap_or(DEFAULT_MONO_THRESHOLD))),
+            Some(other) => Err(AppError::InvalidArgument(format!(
+                "Unknown color mode \"{}\" (expected rgba, grayscale or mono)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Encode a capture after reducing it to `mode`
+pub fn encode_color(
+    image: &RgbaImage,
+    format: OutputFormat,
+    mode: ColorMode,
+    png: PngCompression,
+    jpeg: JpegSubsampling,
+) -> Result<Vec<u8>, AppError> {
+    let mut gray = match (mode, format) {
+        (ColorMode::Rgba, OutputFormat::Png) => {
+            return encode_png(image.as_raw(), image.width(), image.height(), ColorType::Rgba8, png)
+        }
+        (ColorMode::Rgba, OutputFormat::Jpeg(quality)) => return encode_jpeg(image, quality, jpeg),
+        (ColorMode::Rgba, _) => return encode(image, format),
+        (ColorMode::Grayscale | ColorMode::Mono(_), _) => image::imageops::grayscale(image),
+    };
+    if let ColorMode::Mono(threshold) = mode {
+        for pixel in gray.pixels_mut() {
+            pixel.0[0] = if pixel.0[0] >= threshold { 255 } else { 0 };
+        }
+    }
+    let mut buf = Cursor::new(Vec::new());
+    match format {
+        OutputFormat::Png => return encode_png(gray.as_raw(), gray.width(), gray.height(), ColorType::L8, png),
+        OutputFormat::Jpeg(quality) => gray.write_to(&mut buf, ImageOutputFormat::Jpeg(quality)),
+        _ => return encode(&gray.convert(), format),
+    }
+    .map_err(|e| AppError::EncodeFailed(e.to_string()))?;
+    Ok(buf.into_inner())
+}
+
+/// Longest side of the previews returned with captures
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 256;
+
+/// Shrink to fit within `max_dim`×`max_dim` (never enlarging) and return a JPEG
+/// data URL. Uses a triangle filter — good enough for previews and fast.
+pub fn thumbnail(image: &RgbaImage, max_dim: u32) -> Result<String, AppError> {
+    if max_dim == 0 {
+        return Err(AppError::InvalidArgument("Thumbnail size must be at least 1 pixel".to_string()));
+    }
+    let (width, height) = image.dimensions();
+    let ratio = (max_dim as f64 / width.max(height).max(1) as f64).min(1.0);
+    let (w, h) = (
+        ((width as f64 * ratio).round() as u32).max(1),
+        ((height as f64 * ratio).round() as u32).max(1),
+    );
+    let small = image::imageops::resize(image, w, h, image::imageops::FilterType::Triangle);
+    let format = OutputFormat::Jpeg(75);
+    Ok(to_data_url(&encode(&small, format)?, format))
+}
+
+/// Encode an RGBA capture into the requested format
+pub fn encode(image: &RgbaImage, format: OutputFormat) -> Result<Vec<u8>, AppError> {
+    let mut buf = Cursor::new(Vec::new());
+    match format {
+        OutputFormat::Png => image.write_to(&mut buf, ImageOutputFormat::Png),
+        OutputFormat::Jpeg(quality) => {
+            // JPEG has no alpha channel — drop it before encoding
+            let rgb: RgbImage = image.convert();
+            rgb.write_to(&mut buf, ImageOutputFormat::Jpeg(quality))
+        }
+        OutputFormat::WebP(quality) => {
+            #[allow(deprecated)]
+            let encoder = WebPEncoder::new_with_quality(&mut buf, WebPQuality::lossy(quality));
+            encoder.encode(image.as_raw(), image.width(), image.height(), ColorType::Rgba8)
+        }
+        OutputFormat::WebPLossless => {
+            let encoder = WebPEncoder::new_lossless(&mut buf);
+            encoder.encode(image.as_raw(), image.width(), image.height(), ColorType::Rgba8)
+        }
+        #[cfg(feature = "avif")]
+        OutputFormat::Avif(quality) => {
+            use image::ImageEncoder;
+            // Speed 8 of 10: a few times slower than WebP instead of minutes for 4K
+            let encoder = image::codecs::avif::AvifEncoder::new_with_speed_quality(&mut buf, 8, quality);
+            encoder.write_image(image.as_raw(), image.width(), image.height(), ColorType::Rgba8)
+        }
+        #[cfg(not(feature = "avif"))]
+        OutputFormat::Avif(_) => {
+            return Err(AppError::EncodeFailed("AVIF support is not included in this build".to_string()));
+        }
+    }
+    .map_err(|e| AppError::EncodeFailed(e.to_string()))?;
+    Ok(buf.into_inner())
+}
+
+/// Re-encode an already-encoded image (e.g. the PNG written by `screencapture`)
+pub fn transcode(bytes: &[u8], format: OutputFormat) -> Result<Vec<u8>, AppError> {
+    if format == OutputFormat::Png && image::guess_format(bytes).ok() == Some(image::ImageFormat::Png) {
+        return Ok(bytes.to_vec());
+    }
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| AppError::DecodeFailed(e.to_string()))?
+        .to_rgba8();
+    encode(&image, format)
+}
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Drop every ancillary PNG chunk (tEXt/iTXt/zTXt, tIME, iCCP, eXIf, pHYs, ...)
+/// without re-encoding the pixels. `tRNS` is ancillary too but carries
+/// transparency, so it stays. `None` if `bytes` isn't a well-formed PNG.
+pub fn strip_png_metadata(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut rest = bytes.strip_prefix(PNG_SIGNATURE)?;
+    let mut out = PNG_SIGNATURE.to_vec();
+    while !rest.is_empty() {
+        let length = u32::from_be_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+        // length + type + data + CRC
+        let chunk = rest.get(..12 + length)?;
+        let kind = &chunk[4..8];
+        // Critical chunks have an upper-case first letter
+        if kind[0].is_ascii_uppercase() || kind == b"tRNS" {
+            out.extend_from_slice(chunk);
+        }
+        rest = &rest[12 + length..];
+        if kind == b"IEND" {
+            break;
+        }
+    }
+    Some(out)
+}
+
+/// MIME type of already-encoded image bytes, if recognised
+pub fn sniff_mime(bytes: &[u8]) -> Option<&'static str> {
+    match image::guess_format(bytes).ok()? {
+        image::ImageFormat::Png => Some("image/png"),
+        image::ImageFormat::Jpeg => Some("image/jpeg"),
+        image::ImageFormat::WebP => Some("image/webp"),
+        image::ImageFormat::Avif => Some("image/avif"),
+        _ => None,
+    }
+}
+
+/// Extract the raw bytes from a capture passed back from JS.
+/// Accepts a full `data:image/...;base64,` URL or bare base64.
+pub fn data_url_bytes(data_url: &str) -> Result<Vec<u8>, AppError> {
+    let b64 = data_url
+        .find(',')
+        .map(|i| &data_url[i + 1..])
+        .unwrap_or(data_url);
+    BASE64
+        .decode(b64.trim())
+        .map_err(|e| AppError::DecodeFailed(format!("invalid base64: {}", e)))
+}
+
+/// Decode a data URL (or bare base64) into an RGBA image
+pub fn decode_data_url(data_url: &str) -> Result<RgbaImage, AppError> {
+    let bytes = data_url_bytes(data_url)?;
+    let image = image::load_from_memory(&bytes)
+        .map_err(|e| AppError::DecodeFailed(e.to_string()))?;
+    Ok(image.to_rgba8())
+}
+
+/// Width and height of an encoded image, read from its header
+pub fn dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    image::io::Reader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+/// Build a `data:` URL from encoded image bytes
+pub fn to_data_url(bytes: &[u8], format: OutputFormat) -> String {
+    format!("data:{};base64,{}", format.mime(), BASE64.encode(bytes))
+}
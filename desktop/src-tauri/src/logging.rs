@@ -0,0 +1,129 @@
+// ============================================
+// ScreenAI Desktop — Log file and level
+// ============================================
+//
+// A small `log` backend: every record goes to stderr and to `screenai.log` in
+// the app log dir. The file is rotated at MAX_FILE_BYTES, keeping KEPT_FILES
+// older copies (`screenai.log.1` is the most recent).
+
+use crate::error::AppError;
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+pub const DEFAULT_LEVEL: &str = "info";
+const FILE_NAME: &str = "screenai.log";
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+const KEPT_FILES: u32 = 3;
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+}
+
+struct Logger {
+    file: Mutex<Option<LogFile>>,
+}
+
+static LOGGER: Logger = Logger { file: Mutex::new(None) };
+
+/// Parse "off", "error", "warn", "info", "debug" or "trace"
+pub fn parse_level(level: &str) -> Result<LevelFilter, AppError> {
+    LevelFilter::from_str(level.trim()).map_err(|_| {
+        AppError::InvalidArgument(format!(
+            "Unknown log level \"{}\" (expected off, error, warn, info, debug or trace)",
+            level
+        ))
+    })
+}
+
+/// Path of the current log file, if the app has a log dir
+pub fn log_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path_resolver().app_log_dir().map(|dir| dir.join(FILE_NAME))
+}
+
+fn open(path: &Path) -> std::io::Result<LogFile> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let written = file.metadata().map(|m| m.len()).unwrap_or(0);
+    Ok(LogFile { path: path.to_path_buf(), file, written })
+}
+
+/// Shift `screenai.log.N` up by one and move the live file to `.1`
+fn rotate(path: &Path) {
+    let numbered = |n: u32| PathBuf::from(format!("{}.{}", path.display(), n));
+    let _ = std::fs::remove_file(numbered(KEPT_FILES));
+    for n in (1..KEPT_FILES).rev() {
+        let _ = std::fs::rename(numbered(n), numbered(n + 1));
+    }
+    let _ = std::fs::rename(path, numbered(1));
+}
+
+/// Install the logger at `DEFAULT_LEVEL`. Call once, before anything logs —
+/// loading the config included, so its warnings reach the file — then apply
+/// the configured level with `set_level`.
+pub fn init(app: &AppHandle) {
+    let level = parse_level(DEFAULT_LEVEL).unwrap_or(LevelFilter::Info);
+    if let Some(path) = log_path(app) {
+        match open(&path) {
+            Ok(file) => *LOGGER.file.lock().unwrap_or_else(|e| e.into_inner()) = Some(file),
+            Err(e) => eprintln!("[ScreenAI] Could not open log file {}: {}", path.display(), e),
+        }
+    }
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+/// Change the level for the rest of the session
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
+
+/// "YYYY-MM-DD HH:MM:SS.mmm" in local time
+fn timestamp() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string()
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!("{} {:<5} [{}] {}\n", timestamp(), record.level(), record.target(), record.args());
+        eprint!("{}", line);
+
+        let mut guard = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(log) = guard.as_mut() else { return };
+        if log.written + line.len() as u64 > MAX_FILE_BYTES {
+            rotate(&log.path);
+            match open(&log.path) {
+                Ok(fresh) => *log = fresh,
+                Err(_) => {
+                    *guard = None;
+                    return;
+                }
+            }
+        }
+        if log.file.write_all(line.as_bytes()).is_ok() {
+            log.written += line.len() as u64;
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(log) = self.file.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            let _ = log.file.flush();
+        }
+    }
+}
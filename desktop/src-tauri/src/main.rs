@@ -7,122 +7,1022 @@
     windows_subsystem = "windows"
 )]
 
-use reqwest;
+mod ai;
+mod annotate;
+mod autostart;
+mod autosave;
+mod capture;
+mod cli;
+mod config;
+#[cfg(not(target_os = "macos"))]
+mod cursor;
+mod decorate;
+mod diagnostics;
+mod diff;
+mod disk;
+mod edit;
+mod error;
+mod feedback;
+mod history;
+mod imaging;
+mod logging;
+mod ocr;
+mod palette;
+mod pin;
+mod pipeline;
+mod recording;
+mod replay;
+#[cfg(target_os = "linux")]
+mod portal;
+mod preview;
+mod scroll;
+mod secrets;
+mod tray;
+mod updater;
+mod upload;
+mod watermark;
+mod window_list;
+mod work_area;
+
 use base64::Engine;
 use base64::engine::general_purpose::STANDARD as BASE64;
+use capture::{native_capture, CaptureOptions, CapturePayload, MonitorInfo};
+use config::Config;
+use error::AppError;
+use imaging::OutputFormat;
 use serde::Serialize;
 use tauri::{
     AppHandle, GlobalShortcutManager, Manager, SystemTray, SystemTrayEvent,
-    SystemTrayMenu, SystemTrayMenuItem, CustomMenuItem,
 };
 
+/// Sent as "shortcut-registration-failed" when a configured hotkey can't be bound
 #[derive(Clone, Serialize)]
-struct CapturePayload {
-    data_url: String,
+struct ShortcutFailure {
+    action: String,
+    accelerator: String,
+    message: String,
+}
+
+/// Sent as "capture-encoded" once a capture command has its image ready
+#[derive(Clone, Serialize)]
+struct CaptureEncoded {
     mode: String,
+    monitor_index: Option<usize>,
+    /// Time spent grabbing the pixels
+    capture_ms: u64,
+    /// Time spent encoding the image
+    encode_ms: u64,
+    /// Whole capture including the hide delay
+    elapsed_ms: u64,
+}
+
+/// Shared flow for the capture commands: hide the main window, emit
+/// "capture-started", run `capture` off the async executor, show the window
+/// again and emit "capture-encoded" with timings.
+async fn run_capture(
+    app: &AppHandle,
+    mode: &str,
+    capture: impl FnOnce() -> Result<CapturePayload, AppError> + Send + 'static,
+) -> Result<CapturePayload, AppError> {
+    let mut payloads = run_captures(app, mode, move || capture().map(|payload| vec![payload])).await?;
+    Ok(payloads.remove(0))
+}
+
+/// `run_capture` for commands that produce several images at once: each one
+/// gets its own "capture-encoded" event, history entry and auto-save
+async fn run_captures(
+    app: &AppHandle,
+    mode: &str,
+    capture: impl FnOnce() -> Result<Vec<CapturePayload>, AppError> + Send + 'static,
+) -> Result<Vec<CapturePayload>, AppError> {
+    ensure_display(app)?;
+    let start = std::time::Instant::now();
+    let _busy = tray::busy(app);
+    let _ = app.emit_all("capture-started", mode);
+    hide_main_window(app).await;
+
+    let result = tauri::async_runtime::spawn_blocking(capture)
+        .await
+        .unwrap_or_else(|e| Err(AppError::CaptureFailed(e.to_string())));
+
+    if let Some(window) = app.get_window("main") { let _ = window.show(); let _ = window.set_focus(); }
+    match &result {
+        Ok(payloads) => {
+            for payload in payloads {
+                log::info!(
+                    "Captured {} {}x{} in {} ms",
+                    payload.mode,
+                    payload.width,
+                    payload.height,
+                    start.elapsed().as_millis()
+                );
+            }
+        }
+        Err(e) => log::warn!("{} capture failed: {}", mode, e),
+    }
+    if let Ok(payloads) = &result {
+        for payload in payloads {
+            let _ = app.emit_all("capture-encoded", CaptureEncoded {
+                mode: payload.mode.clone(),
+                monitor_index: payload.monitor_index,
+                capture_ms: payload.capture_ms,
+                encode_ms: payload.encode_ms,
+                elapsed_ms: start.elapsed().as_millis() as u64,
+            });
+            after_capture(app, payload);
+        }
+    }
+    result
+}
+
+/// Check for a display before hiding anything. Without one (headless or
+/// remote-desktop sessions) emit "no-display" with the error message, make
+/// sure the main window is showing and fail with `no_screen`.
+fn ensure_display(app: &AppHandle) -> Result<(), AppError> {
+    if capture::has_display() {
+        return Ok(());
+    }
+    log::warn!("No display detected, capture skipped");
+    let _ = app.emit_all("no-display", AppError::NoScreen.to_string());
+    if let Some(window) = app.get_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    Err(AppError::NoScreen)
+}
+
+/// Side effects every finished capture goes through: history, auto-save,
+/// the optional sound/notification and the post-capture actions
+fn after_capture(app: &AppHandle, payload: &CapturePayload) {
+    history::record(app, payload);
+    autosave::save_in_background(app, payload);
+    feedback::after_capture(app, payload);
+    pipeline::run_in_background(app, payload);
+}
+
+// Capture fullscreen — hides app window first so it does not appear in screenshot.
+// `monitor_index` selects a display from `list_monitors`; defaults to the one picked in the
+// tray's "Capture display" menu, else the primary screen. Its bounds are noted when the
+// command starts and checked again right before the grab: a display that is not
+// connected, or was swapped for another at that index meanwhile, falls back to the
+// primary screen with "monitor-changed".
+// `format` is "png", "jpeg", "webp", "webp-lossless" or "avif" (default: the saved output format);
+// `quality` (1-100) applies to lossy formats. AVIF is slow to encode: wait for "capture-encoded".
+// `include_cursor` paints the mouse pointer onto the capture (off by default). Not
+// supported on Wayland: the compositor decides whether its screenshot shows it.
+// `color_mode` is "rgba" (default), "grayscale" or "mono" (black and white at
+// `mono_threshold`, 0-255, default 128). PNG and JPEG store the reduced modes as
+// 8-bit gray and come out much smaller; WebP and AVIF keep RGB channels.
+// `with_thumbnail` adds a small JPEG preview as `thumbnail` to the payload.
+// `png_compression` is "fast", "default" or "best" (default: the saved setting):
+// "fast" encodes a 4K PNG several times quicker for a 20-50% larger file, "best"
+// is a few percent smaller but about twice as slow. Ignored for other formats.
+// `subsampling` is the JPEG chroma subsampling, "444", "422" or "420" (default: the
+// saved setting, 420): 444 keeps colour gradients and coloured text clean for a
+// larger file, 420 is smallest but smears colour on sharp edges. JPEG only.
+// All other capture commands take the same `color_mode`/`mono_threshold`/`with_thumbnail`/
+// `png_compression`/`subsampling`.
+// Emits "capture-started" and "capture-encoded" so the UI can show progress.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn capture_screen(
+    app: AppHandle,
+    monitor_index: Option<usize>,
+    format: Option<String>,
+    quality: Option<u8>,
+    include_cursor: Option<bool>,
+    color_mode: Option<String>,
+    mono_threshold: Option<u8>,
+    with_thumbnail: Option<bool>,
+    png_compression: Option<String>,
+    subsampling: Option<String>,
+) -> Result<CapturePayload, AppError> {
+    let options = capture_options(&app, format, quality, include_cursor, color_mode, mono_threshold, with_thumbnail, png_compression, subsampling)?;
+    let requested = monitor_index.or_else(tray::selected_monitor);
+    // Noted before the main window is hidden, which takes a moment
+    let expected = requested.and_then(capture::monitor_rect);
+    let handle = app.clone();
+    run_capture(&app, "fullscreen", move || {
+        let monitor_index = revalidate_monitor(&handle, requested, expected);
+        native_capture("fullscreen", monitor_index, options)
+    })
+    .await
 }
 
+// Capture region — hides app window first.
+// Without coordinates this is the native interactive selection (macOS).
+// `x`/`y`/`width`/`height` select an area in logical (CSS) pixels; `scale_factor`
+// converts them to physical pixels and defaults to the overlay/main window's DPI scale.
+// `grid` (e.g. 8 or 16) snaps the edges to multiples of that many logical pixels,
+// counted from the display's corner, without leaving the display.
+// `aspect_ratio` (width / height, e.g. 16/9 = 1.778) locks the proportions: the
+// height is recomputed from the width with the top-left corner kept, shrinking
+// both to stay on the display. Applied after `grid`, so the far edges may leave it.
+// An area on a display that is no longer connected captures the primary screen
+// instead and emits "monitor-changed".
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn capture_region(
+    app: AppHandle,
+    x: Option<i32>,
+    y: Option<i32>,
+    width: Option<u32>,
+    height: Option<u32>,
+    scale_factor: Option<f64>,
+    format: Option<String>,
+    quality: Option<u8>,
+    include_cursor: Option<bool>,
+    color_mode: Option<String>,
+    mono_threshold: Option<u8>,
+    with_thumbnail: Option<bool>,
+    png_compression: Option<String>,
+    subsampling: Option<String>,
+    grid: Option<u32>,
+    aspect_ratio: Option<f64>,
+) -> Result<CapturePayload, AppError> {
+    if aspect_ratio.is_some_and(|r| !r.is_finite() || r <= 0.0) {
+        return Err(AppError::InvalidArgument("aspect_ratio must be a positive number".to_string()));
+    }
+    let options = capture_options(&app, format, quality, include_cursor, color_mode, mono_threshold, with_thumbnail, png_compression, subsampling)?;
+    let rect = match (x, y, width, height) {
+        (Some(x), Some(y), Some(w), Some(h)) => Some(capture::Rect { x, y, w, h }),
+        (None, None, None, None) => None,
+        _ => return Err(AppError::InvalidArgument("x, y, width and height must be given together".to_string())),
+    };
+    let Some(rect) = rect else {
+        return run_capture(&app, "region", move || native_capture("region", None, options)).await;
+    };
+
+    let scale = selection_scale(&app, scale_factor)?;
+    let rect = match grid {
+        Some(grid) => rect.snapped(grid, capture::display_bounds(rect, scale)),
+        None => rect,
+    };
+    let rect = match aspect_ratio {
+        Some(ratio) => rect.with_aspect(ratio, capture::display_bounds(rect, scale)),
+        None => rect,
+    };
+    capture_region_rect(&app, rect, scale, options).await
+}
+
+/// Payload of "monitor-changed": the display a capture was aimed at is not
+/// connected, typically because it went away between choosing it and grabbing
+/// it (a laptop undocked during a countdown or a selection), so the primary
+/// display was captured instead
 #[derive(Clone, Serialize)]
-struct UpdateInfo {
-    available: bool,
-    version: String,
-    body: String,
-    date: String,
+struct MonitorChanged {
+    /// Display the capture asked for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    monitor_index: Option<usize>,
+    /// Area the capture asked for, global logical coordinates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<capture::Rect>,
 }
 
-/// Capture the screen using the native OS tool.
-/// macOS: uses `screencapture` CLI (reliable, handles permissions natively)
-/// Windows/Linux: uses the `screenshots` crate as fallback
-fn native_capture(mode: &str) -> Result<CapturePayload, String> {
-    let tmp_path = std::env::temp_dir().join("screenai_capture.png");
+/// Re-check `monitor_index` right before grabbing: the display must still be
+/// connected and, when the flow noted its bounds at the start (`expected`),
+/// still be the same one — indices shift when a display is unplugged.
+/// Otherwise emits "monitor-changed" and returns `None`, the primary display.
+fn revalidate_monitor(app: &AppHandle, monitor_index: Option<usize>, expected: Option<capture::Rect>) -> Option<usize> {
+    let index = monitor_index?;
+    let current = capture::monitor_rect(index);
+    if current.is_some() && (expected.is_none() || current == expected) {
+        return Some(index);
+    }
+    log::warn!("Display {} was disconnected or changed, capturing the primary display", index);
+    let _ = app.emit_all("monitor-changed", MonitorChanged { monitor_index: Some(index), region: None });
+    None
+}
 
-    #[cfg(target_os = "macos")]
-    {
-        use std::process::Command;
-        let tmp_str = tmp_path.to_str().ok_or("Invalid temp path")?;
-        // -x = no sound, -C = capture cursor, -t png = format
-        // -i = interactive selection (for region mode)
-        let args = if mode == "region" {
-            vec!["-x", "-i", "-t", "png", tmp_str]
-        } else {
-            vec!["-x", "-t", "png", tmp_str]
-        };
+/// Capture `rect` (global logical coordinates) and remember it for
+/// `capture_last_region`. When no connected display holds it any more, emits
+/// "monitor-changed" and captures the primary display instead.
+async fn capture_region_rect(app: &AppHandle, rect: capture::Rect, scale: f64, options: CaptureOptions) -> Result<CapturePayload, AppError> {
+    if capture::display_bounds(rect, scale).is_none() {
+        log::warn!("The display under {:?} was disconnected, capturing the primary display", rect);
+        let _ = app.emit_all("monitor-changed", MonitorChanged { monitor_index: None, region: Some(rect) });
+        return run_capture(app, "fullscreen", move || native_capture("fullscreen", None, options)).await;
+    }
+    let result = run_capture(app, "region", move || capture::capture_rect("region", rect, scale, options)).await;
+    if result.is_ok() {
+        remember_region(rect, scale);
+    }
+    result
+}
 
-        let output = Command::new("screencapture")
-            .args(&args)
-            .output()
-            .map_err(|e| format!("Failed to run screencapture: {}", e))?;
+/// Last area captured by `capture_region` or `commit_region`, in global
+/// logical coordinates with its DPI scale, for `capture_last_region`
+static LAST_REGION: std::sync::Mutex<Option<(capture::Rect, f64)>> = std::sync::Mutex::new(None);
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(format!("screencapture failed: {}", stderr));
-        }
+fn remember_region(rect: capture::Rect, scale: f64) {
+    *LAST_REGION.lock().unwrap_or_else(|e| e.into_inner()) = Some((rect, scale));
+}
+
+/// Capture the same area as the last region capture again, without a new
+/// selection, and emit the payload to the main window as "shortcut-capture".
+/// Fails with `no_previous_region` until a region has been captured.
+/// Bound to the optional `captureLastRegion` shortcut.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn capture_last_region(
+    app: AppHandle,
+    format: Option<String>,
+    quality: Option<u8>,
+    include_cursor: Option<bool>,
+    color_mode: Option<String>,
+    mono_threshold: Option<u8>,
+    with_thumbnail: Option<bool>,
+    png_compression: Option<String>,
+    subsampling: Option<String>,
+) -> Result<CapturePayload, AppError> {
+    let (rect, scale) = LAST_REGION.lock().unwrap_or_else(|e| e.into_inner()).ok_or(AppError::NoPreviousRegion)?;
+    let options = capture_options(&app, format, quality, include_cursor, color_mode, mono_threshold, with_thumbnail, png_compression, subsampling)?;
+    let result = capture_region_rect(&app, rect, scale, options).await;
+    if let (Ok(payload), Some(window)) = (&result, app.get_window("main")) {
+        let _ = window.emit("shortcut-capture", payload);
     }
+    result
+}
 
-    #[cfg(not(target_os = "macos"))]
-    {
-        // Fallback for Windows/Linux using screenshots crate
-        use screenshots::Screen;
-        use screenshots::image::ImageOutputFormat;
-        use std::io::Cursor;
+/// Capture part of a display given as fractions (0.0-1.0) of its resolution,
+/// e.g. the top-right quarter is x 0.5, y 0, width 0.5, height 0.5.
+/// Unlike `capture_region` this doesn't depend on the display's resolution.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn capture_region_pct(
+    app: AppHandle,
+    x_pct: f64,
+    y_pct: f64,
+    w_pct: f64,
+    h_pct: f64,
+    monitor_index: Option<usize>,
+    format: Option<String>,
+    quality: Option<u8>,
+    include_cursor: Option<bool>,
+    color_mode: Option<String>,
+    mono_threshold: Option<u8>,
+    with_thumbnail: Option<bool>,
+    png_compression: Option<String>,
+    subsampling: Option<String>,
+) -> Result<CapturePayload, AppError> {
+    let options = capture_options(&app, format, quality, include_cursor, color_mode, mono_threshold, with_thumbnail, png_compression, subsampling)?;
+    let area = capture::FractionRect { x: x_pct, y: y_pct, w: w_pct, h: h_pct };
+    run_capture(&app, "region", move || capture::capture_fraction("region", monitor_index, area, options)).await
+}
 
-        let screens = Screen::all().map_err(|e| e.to_string())?;
-        let screen = screens.first().ok_or("No screen found")?;
-        let di = screen.display_info;
-        eprintln!("[ScreenAI] Screen: {}x{} (scale_factor: {})", di.width, di.height, di.scale_factor);
-        let image = screen.capture().map_err(|e| e.to_string())?;
-        eprintln!("[ScreenAI] Captured image: {}x{}", image.width(), image.height());
+/// Capture a display without the taskbar, dock or menu bar (default: the
+/// primary display). Falls back to the whole display when the OS doesn't
+/// report a work area; the payload's `work_area` has the bounds used.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn capture_work_area(
+    app: AppHandle,
+    monitor_index: Option<usize>,
+    format: Option<String>,
+    quality: Option<u8>,
+    include_cursor: Option<bool>,
+    color_mode: Option<String>,
+    mono_threshold: Option<u8>,
+    with_thumbnail: Option<bool>,
+    png_compression: Option<String>,
+    subsampling: Option<String>,
+) -> Result<CapturePayload, AppError> {
+    let options = capture_options(&app, format, quality, include_cursor, color_mode, mono_threshold, with_thumbnail, png_compression, subsampling)?;
+    run_capture(&app, "work_area", move || capture::capture_work_area(monitor_index, options)).await
+}
 
-        let mut buf = Cursor::new(Vec::new());
-        image.write_to(&mut buf, ImageOutputFormat::Png)
-            .map_err(|e| e.to_string())?;
+/// Options shared by the capture commands: format (falling back to the saved
+/// output format), cursor, colour mode, thumbnail and PNG compression
+#[allow(clippy::too_many_arguments)]
+fn capture_options(
+    app: &AppHandle,
+    format: Option<String>,
+    quality: Option<u8>,
+    include_cursor: Option<bool>,
+    color_mode: Option<String>,
+    mono_threshold: Option<u8>,
+    with_thumbnail: Option<bool>,
+    png_compression: Option<String>,
+    subsampling: Option<String>,
+) -> Result<CaptureOptions, AppError> {
+    let config = Config::load(app);
+    Ok(CaptureOptions {
+        format: config.capture_format(format.as_deref(), quality)?,
+        include_cursor: include_cursor.unwrap_or(false),
+        color_mode: imaging::ColorMode::parse(color_mode.as_deref(), mono_threshold)?,
+        with_thumbnail: with_thumbnail.unwrap_or(false),
+        limit: config.pixel_limit(),
+        png_compression: imaging::PngCompression::parse(Some(png_compression.as_deref().unwrap_or(&config.png_compression)))?,
+        jpeg_subsampling: imaging::JpegSubsampling::parse(Some(subsampling.as_deref().unwrap_or(&config.jpeg_subsampling)))?,
+        auto_rotate: config.auto_rotate,
+    })
+}
 
-        std::fs::write(&tmp_path, buf.into_inner())
-            .map_err(|e| format!("Failed to write temp file: {}", e))?;
+/// DPI scale for coordinates coming from the webview: the caller's
+/// `scale_factor`, else that of the capture overlay or main window
+fn selection_scale(app: &AppHandle, scale_factor: Option<f64>) -> Result<f64, AppError> {
+    match scale_factor {
+        Some(s) if s.is_finite() && s > 0.0 => Ok(s),
+        Some(s) => Err(AppError::InvalidArgument(format!("Invalid scale factor {}", s))),
+        None => Ok(capture_overlays(app)
+            .into_iter()
+            .next()
+            .or_else(|| app.get_window("main"))
+            .and_then(|w| w.scale_factor().ok())
+            .unwrap_or(1.0)),
     }
+}
+
+/// Start a scrolling capture of a screen area (logical coordinates, like
+/// `capture_region`). Grabs the first frame and returns its height in pixels.
+/// The frontend then scrolls the target and calls `capture_scroll_frame`
+/// after each step, and `capture_scroll_finish` for the stitched image.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+async fn capture_scroll_start(
+    app: AppHandle,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    scale_factor: Option<f64>,
+    format: Option<String>,
+    quality: Option<u8>,
+    color_mode: Option<String>,
+    mono_threshold: Option<u8>,
+    with_thumbnail: Option<bool>,
+    png_compression: Option<String>,
+    subsampling: Option<String>,
+) -> Result<u32, AppError> {
+    let options = capture_options(&app, format, quality, Some(false), color_mode, mono_threshold, with_thumbnail, png_compression, subsampling)?;
+    let rect = capture::Rect { x, y, w: width, h: height };
+    let scale = selection_scale(&app, scale_factor)?;
+    tauri::async_runtime::spawn_blocking(move || scroll::start(rect, scale, options))
+        .await
+        .unwrap_or_else(|e| Err(AppError::CaptureFailed(e.to_string())))
+}
+
+/// Grab the next scrolling frame and append the rows that scrolled into view
+#[tauri::command]
+async fn capture_scroll_frame() -> Result<scroll::ScrollProgress, AppError> {
+    tauri::async_runtime::spawn_blocking(scroll::frame)
+        .await
+        .unwrap_or_else(|e| Err(AppError::CaptureFailed(e.to_string())))
+}
 
-    // Read the PNG file and convert to base64 data URL
-    if !tmp_path.exists() {
-        return Err("Capture was cancelled or failed".to_string());
+/// End the scrolling capture and return the assembled tall image
+#[tauri::command]
+async fn capture_scroll_finish(app: AppHandle) -> Result<CapturePayload, AppError> {
+    let payload = tauri::async_runtime::spawn_blocking(|| {
+        let (image, options) = scroll::finish()?;
+        let bytes = imaging::encode_color(&image, options.format, options.color_mode, options.png_compression, options.jpeg_subsampling)?;
+        let mut payload = capture::payload_from_data_url(imaging::to_data_url(&bytes, options.format), "scrolling".to_string());
+        if options.with_thumbnail {
+            payload.thumbnail = imaging::thumbnail(&image, imaging::DEFAULT_THUMBNAIL_SIZE).ok();
+        }
+        Ok(payload)
+    })
+    .await
+    .unwrap_or_else(|e| Err(AppError::CaptureFailed(e.to_string())))?;
+    after_capture(&app, &payload);
+    Ok(payload)
+}
+
+// Capture every monitor stitched into one image of the whole virtual desktop
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn capture_all_monitors(
+    app: AppHandle,
+    format: Option<String>,
+    quality: Option<u8>,
+    include_cursor: Option<bool>,
+    color_mode: Option<String>,
+    mono_threshold: Option<u8>,
+    with_thumbnail: Option<bool>,
+    png_compression: Option<String>,
+    subsampling: Option<String>,
+) -> Result<CapturePayload, AppError> {
+    let options = capture_options(&app, format, quality, include_cursor, color_mode, mono_threshold, with_thumbnail, png_compression, subsampling)?;
+    run_capture(&app, "all_monitors", move || capture::capture_all_monitors(options)).await
+}
+
+// Capture every monitor as a separate image, one payload per display with its
+// `monitor_index`, in `list_monitors` order. The displays are grabbed together to
+// keep them in sync; each payload is stored and auto-saved on its own.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn capture_each_monitor(
+    app: AppHandle,
+    format: Option<String>,
+    quality: Option<u8>,
+    include_cursor: Option<bool>,
+    color_mode: Option<String>,
+    mono_threshold: Option<u8>,
+    with_thumbnail: Option<bool>,
+    png_compression: Option<String>,
+    subsampling: Option<String>,
+) -> Result<Vec<CapturePayload>, AppError> {
+    let options = capture_options(&app, format, quality, include_cursor, color_mode, mono_threshold, with_thumbnail, png_compression, subsampling)?;
+    run_captures(&app, "each_monitor", move || capture::capture_each_monitor("each_monitor", options)).await
+}
+
+/// Bumped on every new delayed capture or cancel — a countdown whose generation no longer matches stops
+static DELAYED_CAPTURE_GEN: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// Timed capture: emits "countdown" (seconds remaining) every second to every window — the
+/// main one is hidden meanwhile, so an overlay or the tray UI shows it — then captures
+/// fullscreen and emits "capture" with the payload. Returns once the countdown has started.
+/// `monitor_index` defaults like `capture_screen`'s; if that display is unplugged or
+/// replaced during the countdown, the primary display is captured and "monitor-changed" emitted.
+/// Uses the saved format, compression and subsampling, like `capture_screen` without options.
+#[tauri::command]
+fn capture_screen_delayed(app: AppHandle, seconds: u32, monitor_index: Option<usize>) -> Result<(), AppError> {
+    use std::sync::atomic::Ordering;
+    ensure_display(&app)?;
+    // Built now so a bad saved setting fails the call rather than the capture
+    let options = capture_options(&app, None, None, None, None, None, None, None, None)?;
+    let monitor_index = monitor_index.or_else(tray::selected_monitor);
+    let expected = monitor_index.and_then(capture::monitor_rect);
+    let generation = DELAYED_CAPTURE_GEN.fetch_add(1, Ordering::SeqCst) + 1;
+    tauri::async_runtime::spawn(async move {
+        let is_current = || DELAYED_CAPTURE_GEN.load(Ordering::SeqCst) == generation;
+        let _busy = tray::busy(&app);
+        hide_main_window(&app).await;
+
+        for remaining in (1..=seconds).rev() {
+            if !is_current() { return; }
+            let _ = app.emit_all("countdown", remaining);
+            tray::set_tooltip(&app, Some(&format!("capturing in {} s", remaining)));
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        }
+        if !is_current() { return; }
+        tray::set_tooltip(&app, Some("capturing"));
+
+        let monitor_index = revalidate_monitor(&app, monitor_index, expected);
+        let result = native_capture("fullscreen", monitor_index, options);
+        if let Some(window) = app.get_window("main") {
+            let _ = window.show();
+            let _ = window.set_focus();
+            match result {
+                Ok(payload) => {
+                    after_capture(&app, &payload);
+                    let _ = window.emit("capture", payload);
+                }
+                Err(e) => { let _ = window.emit("capture-error", e.to_string()); }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Abort a pending `capture_screen_delayed` countdown and bring the main window back
+#[tauri::command]
+fn cancel_delayed_capture(app: AppHandle) {
+    DELAYED_CAPTURE_GEN.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    if let Some(window) = app.get_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+        let _ = window.emit("capture-cancelled", ());
     }
+}
+
+/// Color of one screen pixel, returned by `pick_color`
+#[derive(Clone, Serialize)]
+struct ColorSample {
+    /// "#rrggbb"
+    hex: String,
+    r: u8,
+    g: u8,
+    b: u8,
+    a: u8,
+}
+
+/// Eyedropper: sample the pixel at a global screen coordinate (any monitor)
+#[tauri::command]
+async fn pick_color(x: i32, y: i32) -> Result<ColorSample, AppError> {
+    let rect = capture::Rect { x, y, w: 1, h: 1 };
+    let (image, _, _) = tauri::async_runtime::spawn_blocking(move || capture::grab_rect(rect, 1.0, CaptureOptions::default()))
+        .await
+        .map_err(|e| AppError::CaptureFailed(e.to_string()))??;
+    // HiDPI grabs return several pixels for one logical point — take the middle one
+    let [r, g, b, a] = image.get_pixel(image.width() / 2, image.height() / 2).0;
+    Ok(ColorSample { hex: format!("#{:02x}{:02x}{:02x}", r, g, b), r, g, b, a })
+}
 
-    let png_bytes = std::fs::read(&tmp_path)
-        .map_err(|e| format!("Failed to read capture: {}", e))?;
+/// Largest loupe `get_magnifier` renders, per side
+const MAGNIFIER_MAX_SIZE: u32 = 2048;
 
-    // Clean up temp file
-    let _ = std::fs::remove_file(&tmp_path);
+/// Zoomed view around a screen point, returned by `get_magnifier`
+#[derive(Clone, Serialize)]
+struct Magnifier {
+    /// PNG
+    data_url: String,
+    width: u32,
+    height: u32,
+    /// Middle of the zoomed pixel under (x, y), in loupe pixels. Off-center
+    /// when the source square was clamped at a screen edge.
+    center_x: u32,
+    center_y: u32,
+    /// Color of the pixel under (x, y), "#rrggbb"
+    hex: String,
+}
 
-    if png_bytes.is_empty() {
-        return Err("Capture produced empty file".to_string());
+/// Loupe for precise selection: a `source_size` square of the screen centered
+/// on the global coordinate (`x`, `y`), enlarged `zoom` times with hard pixel
+/// edges. Near a screen border the square is shifted to stay on the display.
+/// Each call grabs the screen, so the overlay should throttle it to its frame rate.
+#[tauri::command]
+async fn get_magnifier(x: i32, y: i32, source_size: u32, zoom: u32) -> Result<Magnifier, AppError> {
+    if source_size == 0 || zoom == 0 {
+        return Err(AppError::InvalidArgument("source_size and zoom must be at least 1".to_string()));
+    }
+    if source_size.saturating_mul(zoom) > MAGNIFIER_MAX_SIZE {
+        return Err(AppError::InvalidArgument(format!(
+            "source_size × zoom must be at most {} pixels",
+            MAGNIFIER_MAX_SIZE
+        )));
     }
+    tauri::async_runtime::spawn_blocking(move || {
+        let half = (source_size / 2) as i32;
+        let mut rect = capture::Rect { x: x - half, y: y - half, w: source_size, h: source_size };
+        if let Some(bounds) = capture::display_bounds(capture::Rect { x, y, w: 1, h: 1 }, 1.0) {
+            let max_x = bounds.x + bounds.w.saturating_sub(rect.w) as i32;
+            let max_y = bounds.y + bounds.h.saturating_sub(rect.h) as i32;
+            rect.x = rect.x.clamp(bounds.x, max_x.max(bounds.x));
+            rect.y = rect.y.clamp(bounds.y, max_y.max(bounds.y));
+            rect.w = rect.w.min(bounds.w);
+            rect.h = rect.h.min(bounds.h);
+        }
+        let (image, _, _) = capture::grab_rect(rect, 1.0, CaptureOptions::default())?;
 
-    let base64_data = BASE64.encode(&png_bytes);
-    let data_url = format!("data:image/png;base64,{}", base64_data);
+        // HiDPI grabs have several image pixels per point; find the one under (x, y)
+        let locate = |offset: i32, span: u32, size: u32| {
+            let at = ((offset as f64 + 0.5) * size as f64 / span as f64) as u32;
+            at.min(size.saturating_sub(1))
+        };
+        let (px, py) = (locate(x - rect.x, rect.w, image.width()), locate(y - rect.y, rect.h, image.height()));
+        let [r, g, b, _] = image.get_pixel(px, py).0;
+
+        let (width, height) = (rect.w * zoom, rect.h * zoom);
+        let zoomed = image::imageops::resize(&image, width, height, image::imageops::FilterType::Nearest);
+        let bytes = imaging::encode(&zoomed, OutputFormat::Png)?;
+        let cell = |at: u32, size: u32, out: u32| ((at as f64 + 0.5) * out as f64 / size as f64) as u32;
+        Ok(Magnifier {
+            data_url: imaging::to_data_url(&bytes, OutputFormat::Png),
+            width,
+            height,
+            center_x: cell(px, image.width(), width),
+            center_y: cell(py, image.height(), height),
+            hex: format!("#{:02x}{:02x}{:02x}", r, g, b),
+        })
+    })
+    .await
+    .unwrap_or_else(|e| Err(AppError::CaptureFailed(e.to_string())))
+}
 
-    Ok(CapturePayload {
-        data_url,
-        mode: mode.to_string(),
+/// Screen ruler reading, returned by `measure_distance`
+#[derive(Clone, Serialize)]
+struct Measurement {
+    /// Straight-line distance, in logical pixels
+    distance: f64,
+    /// Bounding box of the two points, in logical pixels
+    width: u32,
+    height: u32,
+    /// The same in physical pixels — the size `capture_region` would return
+    physical_distance: f64,
+    physical_width: u32,
+    physical_height: u32,
+    scale_factor: f64,
+}
+
+/// Ruler: distance and bounding box between two global logical coordinates.
+/// Physical values use the same rounding as `capture_region`, so they match
+/// the pixel size of a capture of that box.
+#[tauri::command]
+fn measure_distance(
+    app: AppHandle,
+    x1: i32,
+    y1: i32,
+    x2: i32,
+    y2: i32,
+    scale_factor: Option<f64>,
+) -> Result<Measurement, AppError> {
+    let scale = selection_scale(&app, scale_factor)?;
+    let logical = capture::Rect { x: x1.min(x2), y: y1.min(y2), w: x1.abs_diff(x2), h: y1.abs_diff(y2) };
+    let physical = logical.scaled(scale);
+    Ok(Measurement {
+        distance: (logical.w as f64).hypot(logical.h as f64),
+        width: logical.w,
+        height: logical.h,
+        physical_distance: (physical.w as f64).hypot(physical.h as f64),
+        physical_width: physical.w,
+        physical_height: physical.h,
+        scale_factor: scale,
     })
 }
 
-// Capture fullscreen — hides app window first so it does not appear in screenshot
+/// Loupe: a `size`×`size` area (default 11, odd, max 63) centered on a global
+/// screen coordinate, enlarged `zoom` times (default 8) without smoothing.
 #[tauri::command]
-async fn capture_screen(app: AppHandle) -> Result<CapturePayload, String> {
-    if let Some(window) = app.get_window("main") { let _ = window.hide(); }
-    tokio::time::sleep(std::time::Duration::from_millis(800)).await;
-    let result = native_capture("fullscreen");
-    if let Some(window) = app.get_window("main") { let _ = window.show(); let _ = window.set_focus(); }
-    result
+async fn get_pixel_region(x: i32, y: i32, size: Option<u32>, zoom: Option<u32>) -> Result<String, AppError> {
+    let size = (size.unwrap_or(11).clamp(1, 63)) | 1;
+    let zoom = zoom.unwrap_or(8).clamp(1, 32);
+    let half = (size / 2) as i32;
+    let rect = capture::Rect { x: x - half, y: y - half, w: size, h: size };
+    let (image, _, _) = tauri::async_runtime::spawn_blocking(move || capture::grab_rect(rect, 1.0, CaptureOptions::default()))
+        .await
+        .map_err(|e| AppError::CaptureFailed(e.to_string()))??;
+    let zoomed = image::imageops::resize(&image, size * zoom, size * zoom, image::imageops::FilterType::Nearest);
+    let bytes = imaging::encode(&zoomed, OutputFormat::Png)?;
+    Ok(imaging::to_data_url(&bytes, OutputFormat::Png))
 }
 
-// Capture region — hides app window first
+/// Record the screen to an animated GIF in the captures folder until
+/// `stop_recording`. `region` is in logical pixels like `capture_region`
+/// (default: the primary display); `fps` is 1-30, 10 is a good trade-off.
+/// Frames wider than 1280 px are scaled down. Recordings stop by themselves
+/// after 10 minutes or at 512 MB ("recording-limit"). Emits "recording-progress"
+/// ({ frames, dropped, elapsed_ms }) and "recording-error"; a recording that
+/// ended by itself (error or limit) emits "recording-stopped" with the result
+/// `stop_recording` would have returned.
 #[tauri::command]
-async fn capture_region(app: AppHandle) -> Result<CapturePayload, String> {
-    if let Some(window) = app.get_window("main") { let _ = window.hide(); }
-    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
-    let result = native_capture("region");
-    if let Some(window) = app.get_window("main") { let _ = window.show(); let _ = window.set_focus(); }
-    result
+async fn start_recording(
+    app: AppHandle,
+    fps: Option<u32>,
+    region: Option<capture::Rect>,
+    scale_factor: Option<f64>,
+) -> Result<(), AppError> {
+    let area = match region {
+        Some(rect) => Some((rect, selection_scale(&app, scale_factor)?)),
+        None => None,
+    };
+    tauri::async_runtime::spawn_blocking(move || recording::start(&app, fps.unwrap_or(10), area))
+        .await
+        .unwrap_or_else(|e| Err(AppError::CaptureFailed(e.to_string())))
+}
+
+/// Finish the running recording and return the GIF's path, size and length
+#[tauri::command]
+async fn stop_recording() -> Result<recording::RecordingResult, AppError> {
+    tauri::async_runtime::spawn_blocking(recording::stop)
+        .await
+        .unwrap_or_else(|e| Err(AppError::EncodeFailed(e.to_string())))
+}
+
+/// Keep the last `seconds` (1-60, default 15) of the screen in memory at `fps`
+/// (default 10) so `save_replay_buffer` can save what just happened. `region`
+/// works like `start_recording`'s. Memory is capped at 256 MB: frames are
+/// stored smaller, or fewer seconds are kept, to fit; the result says what
+/// the buffer will hold. Emits "replay-error" if capturing stops, which also
+/// frees the buffer.
+#[tauri::command]
+async fn start_replay_buffer(
+    app: AppHandle,
+    fps: Option<u32>,
+    seconds: Option<u32>,
+    region: Option<capture::Rect>,
+    scale_factor: Option<f64>,
+) -> Result<replay::ReplayInfo, AppError> {
+    let area = match region {
+        Some(rect) => Some((rect, selection_scale(&app, scale_factor)?)),
+        None => None,
+    };
+    tauri::async_runtime::spawn_blocking(move || replay::start(&app, fps.unwrap_or(10), seconds.unwrap_or(15), area))
+        .await
+        .unwrap_or_else(|e| Err(AppError::CaptureFailed(e.to_string())))
+}
+
+/// Save the replay buffer's frames as a GIF without stopping it. `path` follows
+/// the `save_capture` rules and must end in .gif (default: a timestamped file
+/// in the captures folder).
+#[tauri::command]
+async fn save_replay_buffer(path: Option<String>) -> Result<recording::RecordingResult, AppError> {
+    let target = match path {
+        Some(path) => checked_save_path(&path, false)?,
+        None => {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            get_screenai_captures_dir().join(autosave::format_pattern("ScreenAI_replay_%Y%m%d_%H%M%S.gif", now))
+        }
+    };
+    if !target.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gif")) {
+        return Err(AppError::InvalidArgument("Replays are saved as GIF; use a .gif path".to_string()));
+    }
+    tauri::async_runtime::spawn_blocking(move || replay::save(&target))
+        .await
+        .unwrap_or_else(|e| Err(AppError::EncodeFailed(e.to_string())))
+}
+
+/// Stop the replay buffer and free its frames
+#[tauri::command]
+async fn stop_replay_buffer() -> Result<(), AppError> {
+    tauri::async_runtime::spawn_blocking(replay::stop)
+        .await
+        .unwrap_or_else(|e| Err(AppError::CaptureFailed(e.to_string())))
+}
+
+/// Combine captures (data URLs or bare base64) into an animated GIF in the
+/// captures folder and return its path. Frames of different sizes are rejected
+/// unless `pad` is true, which centers each on a canvas the size of the largest.
+#[tauri::command]
+async fn make_gif(
+    frames: Vec<String>,
+    delay_ms: u16,
+    loop_forever: Option<bool>,
+    pad: Option<bool>,
+) -> Result<String, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let frames = frames
+            .iter()
+            .map(|frame| imaging::decode_data_url(frame))
+            .collect::<Result<Vec<_>, _>>()?;
+        let path = recording::make_gif(frames, delay_ms as u32, loop_forever.unwrap_or(true), pad.unwrap_or(false))?;
+        Ok(path.to_string_lossy().to_string())
+    })
+    .await
+    .unwrap_or_else(|e| Err(AppError::EncodeFailed(e.to_string())))
+}
+
+/// Capture a display as raw RGBA pixels (no PNG encoding), for drawing straight
+/// into a canvas `ImageData`. Small captures come back as base64 in `rgba`; large
+/// ones (or all, with `as_file`) as a file `path` in the app cache dir to read
+/// with the fs API. The file is rewritten by each call for the same display and
+/// removed by `cleanup_temp_captures` once unused.
+/// Doesn't hide the main window and skips history and auto-save, for preview loops.
+#[tauri::command]
+async fn capture_screen_raw(
+    app: AppHandle,
+    monitor_index: Option<usize>,
+    include_cursor: Option<bool>,
+    as_file: Option<bool>,
+) -> Result<capture::RawCapture, AppError> {
+    let options = CaptureOptions { include_cursor: include_cursor.unwrap_or(false), ..CaptureOptions::default() };
+    let cache_dir = app.path_resolver().app_cache_dir();
+    tauri::async_runtime::spawn_blocking(move || {
+        capture::capture_screen_raw(monitor_index, as_file.unwrap_or(false), cache_dir.as_deref(), options)
+    })
+        .await
+        .unwrap_or_else(|e| Err(AppError::CaptureFailed(e.to_string())))
+}
+
+/// Capture a display with some windows (ids from `list_windows`) blanked out,
+/// e.g. a floating notes app before a demo screenshot. `fill` is a hex color;
+/// the default is transparent, which JPEG output turns black.
+/// (Linux has no window list yet, so nothing is masked there.)
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn capture_screen_excluding(
+    app: AppHandle,
+    window_ids: Vec<u64>,
+    monitor_index: Option<usize>,
+    fill: Option<String>,
+    format: Option<String>,
+    quality: Option<u8>,
+    include_cursor: Option<bool>,
+    color_mode: Option<String>,
+    mono_threshold: Option<u8>,
+    with_thumbnail: Option<bool>,
+    png_compression: Option<String>,
+    subsampling: Option<String>,
+) -> Result<CapturePayload, AppError> {
+    let fill = match fill.as_deref() {
+        Some(color) => annotate::parse_color(color)?,
+        None => image::Rgba([0, 0, 0, 0]),
+    };
+    let options = capture_options(&app, format, quality, include_cursor, color_mode, mono_threshold, with_thumbnail, png_compression, subsampling)?;
+    run_capture(&app, "fullscreen", move || {
+        capture::capture_screen_excluding(monitor_index, &window_ids, fill, options)
+    })
+    .await
+}
+
+/// List connected displays so the UI can build a monitor picker for `capture_screen`
+#[tauri::command]
+fn list_monitors() -> Result<Vec<MonitorInfo>, AppError> {
+    capture::monitors()
+}
+
+/// Per-display geometry, DPI scale, rotation and refresh rate.
+/// The scale factor is taken from Tauri's monitor list where a monitor matches,
+/// so it agrees with what the webview reports.
+#[tauri::command]
+fn get_screen_info(app: AppHandle) -> Result<Vec<capture::ScreenInfo>, AppError> {
+    let mut screens = capture::screens()?;
+    let monitors = app
+        .get_window("main")
+        .and_then(|w| w.available_monitors().ok())
+        .unwrap_or_default();
+    for screen in &mut screens {
+        if let Some(monitor) = monitors.iter().find(|m| same_display(m, screen.x, screen.y)) {
+            screen.scale_factor = monitor.scale_factor();
+        }
+    }
+    Ok(screens)
+}
+
+/// Whether a Tauri monitor is the display-info display at (`x`, `y`).
+/// Tauri positions are physical; display-info's are physical or logical depending on the OS.
+fn same_display(monitor: &tauri::Monitor, x: i32, y: i32) -> bool {
+    let pos = monitor.position();
+    let logical = pos.to_logical::<f64>(monitor.scale_factor());
+    (pos.x == x && pos.y == y) || (logical.x.round() as i32 == x && logical.y.round() as i32 == y)
+}
+
+/// Capture a single window (id from `list_windows`) — hides app window first
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn capture_window(
+    app: AppHandle,
+    window_id: u64,
+    format: Option<String>,
+    quality: Option<u8>,
+    include_cursor: Option<bool>,
+    color_mode: Option<String>,
+    mono_threshold: Option<u8>,
+    with_thumbnail: Option<bool>,
+    png_compression: Option<String>,
+    subsampling: Option<String>,
+) -> Result<CapturePayload, AppError> {
+    let options = capture_options(&app, format, quality, include_cursor, color_mode, mono_threshold, with_thumbnail, png_compression, subsampling)?;
+    run_capture(&app, "window", move || capture::capture_window(window_id, options)).await
+}
+
+/// Configure automatic saving of every capture.
+/// `dir` = None keeps the current folder, Some("") resets to ~/Documents/ScreenAI/Captures.
+/// `pattern` accepts %Y %m %d %H %M %S, %n (a counter that survives restarts, "001"),
+/// %i (monitor index) and %t (mode); its extension (png/jpg/webp) picks the format.
+/// Returns the folder captures will be written to.
+#[tauri::command]
+fn set_auto_save(app: AppHandle, enabled: bool, dir: Option<String>, pattern: Option<String>) -> Result<String, AppError> {
+    let mut config = Config::load(&app);
+    if let Some(dir) = dir {
+        let dir = dir.trim().to_string();
+        if dir.is_empty() {
+            config.auto_save_dir = None;
+        } else {
+            std::fs::create_dir_all(&dir)
+                .map_err(|e| AppError::InvalidArgument(format!("Cannot use {} as capture folder: {}", dir, e)))?;
+            config.auto_save_dir = Some(dir);
+        }
+    }
+    if let Some(pattern) = pattern {
+        let pattern = pattern.trim();
+        config.auto_save_pattern = if pattern.is_empty() { autosave::DEFAULT_PATTERN.to_string() } else { pattern.to_string() };
+    }
+    config.auto_save_enabled = enabled;
+    config.save(&app)?;
+    Ok(autosave::output_dir(&config).to_string_lossy().to_string())
+}
+
+/// The next auto-save file name (e.g. for a save dialog's default), with the
+/// `%n` counter past names already taken in the capture folder. Doesn't move
+/// the counter: that happens when a capture is saved.
+#[tauri::command]
+fn next_capture_filename(app: AppHandle, mode: Option<String>, monitor_index: Option<usize>) -> Result<String, AppError> {
+    let config = Config::load(&app);
+    let dir = autosave::output_dir(&config);
+    let ext = config.capture_format(None, None)?.extension();
+    autosave::next_filename(&app, &dir, mode.as_deref().unwrap_or("fullscreen"), monitor_index, ext)
+}
+
+/// Recent captures, newest first, each with a thumbnail data URL
+#[tauri::command]
+fn get_history(app: AppHandle) -> Result<Vec<history::HistoryItem>, AppError> {
+    history::list(&app)
+}
+
+/// Full-size image of a history entry as a data URL
+#[tauri::command]
+fn get_history_item(app: AppHandle, id: String) -> Result<String, AppError> {
+    history::get(&app, &id)
+}
+
+/// Delete all stored history captures; returns how many were removed
+#[tauri::command]
+fn clear_history(app: AppHandle) -> Result<usize, AppError> {
+    history::clear(&app)
+}
+
+/// Capture the focused window (ScreenAI's own windows are skipped) — hides app window first.
+/// Fails with "capture_failed" on Wayland, where the active window can't be known.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn capture_active_window(
+    app: AppHandle,
+    format: Option<String>,
+    quality: Option<u8>,
+    include_cursor: Option<bool>,
+    color_mode: Option<String>,
+    mono_threshold: Option<u8>,
+    with_thumbnail: Option<bool>,
+    png_compression: Option<String>,
+    subsampling: Option<String>,
+) -> Result<CapturePayload, AppError> {
+    let options = capture_options(&app, format, quality, include_cursor, color_mode, mono_threshold, with_thumbnail, png_compression, subsampling)?;
+    run_capture(&app, "window", move || capture::capture_active_window(options)).await
 }
 
 // Return app version from tauri.conf.json
@@ -131,21 +1031,212 @@ fn get_app_version(app: AppHandle) -> String {
     app.package_info().version.to_string()
 }
 
-// Check for updates — returns not available when updater is disabled
+/// Versions, OS, displays, session type and shortcut registration in one
+/// struct for a "Copy diagnostics" button. Holds nothing that identifies the user.
+#[tauri::command]
+fn get_diagnostics(app: AppHandle) -> diagnostics::Diagnostics {
+    diagnostics::collect(&app)
+}
+
+// Check the saved release channel for a newer version
 #[tauri::command]
-async fn check_for_updates(_app: AppHandle) -> Result<UpdateInfo, String> {
-    Ok(UpdateInfo {
-        available: false,
-        version: String::new(),
-        body: String::new(),
-        date: String::new(),
+async fn check_for_updates(app: AppHandle) -> Result<updater::UpdateInfo, AppError> {
+    updater::check(&app).await
+}
+
+/// Result of the last update check (startup or `check_for_updates`), no network call.
+/// `null` until a check has succeeded on the current channel.
+#[tauri::command]
+fn get_cached_update_info(app: AppHandle) -> Option<updater::UpdateInfo> {
+    updater::cached(&app)
+}
+
+/// Current settings, as stored in config.json
+#[tauri::command]
+fn get_config(app: AppHandle) -> Config {
+    Config::load(&app)
+}
+
+/// Change some settings and save them. `partial` uses the same camelCase keys
+/// as `get_config`; fields left out keep their value. Returns the new config.
+/// Shortcuts are changed through `set_shortcut`, which also re-registers them.
+#[tauri::command]
+async fn update_config(app: AppHandle, partial: serde_json::Value) -> Result<Config, AppError> {
+    if partial.get("shortcuts").is_some() {
+        return Err(AppError::InvalidArgument("Use set_shortcut to change shortcuts".to_string()));
+    }
+    let previous = Config::load(&app);
+    let mut config = previous.merged(&partial)?;
+    // Keys sent along here go to the keychain like those from `set_secret`;
+    // off the main thread, as the keychain may wait on an unlock prompt
+    let config = tauri::async_runtime::spawn_blocking(move || {
+        secrets::migrate(&mut config);
+        config
     })
+    .await
+    .map_err(|e| AppError::Io(e.to_string()))?;
+    if config.launch_at_login != previous.launch_at_login {
+        apply_autostart(&app, config.launch_at_login)?;
+    }
+    config.save(&app)?;
+    logging::set_level(logging::parse_level(&config.log_level)?);
+    capture::set_capture_timeout(config.capture_timeout_ms);
+    Ok(config)
 }
 
-// Install update — no-op when updater is disabled
+/// Register or remove the OS login entry and sync the tray check mark.
+/// Emits "autostart-changed" so an open settings page can follow tray clicks.
+fn apply_autostart(app: &AppHandle, enabled: bool) -> Result<(), AppError> {
+    autostart::set(enabled)?;
+    log::info!("Launch at login {}", if enabled { "enabled" } else { "disabled" });
+    tray::set_launch_at_login(app, enabled);
+    let _ = app.emit_all("autostart-changed", enabled);
+    Ok(())
+}
+
+/// Start ScreenAI when the user logs in (registry Run key on Windows,
+/// LaunchAgent on macOS, ~/.config/autostart on Linux). The choice is saved
+/// as `launchAtLogin`. Fails with `autostart_failed` if the OS refuses.
+#[tauri::command]
+fn set_autostart(app: AppHandle, enabled: bool) -> Result<(), AppError> {
+    apply_autostart(&app, enabled)?;
+    let mut config = Config::load(&app);
+    config.launch_at_login = enabled;
+    config.save(&app)
+}
+
+/// Whether the OS will start ScreenAI at login. Reads the OS entry, so it
+/// also reflects changes made in the system's own settings.
+#[tauri::command]
+fn get_autostart() -> Result<bool, AppError> {
+    autostart::is_enabled()
+}
+
+/// Save an API key or upload credential in the OS keychain. `name` is one of
+/// `secrets::NAMES` ("aiApiKey", "uploadImgurClientId", "uploadS3SecretAccessKey",
+/// "uploadWebdavPassword", "uploadHttpHeaders" as a JSON object); an empty
+/// `value` deletes it. Returns "keychain", or "config" when no keychain is
+/// available and the value had to go into config.json instead.
+#[tauri::command]
+async fn set_secret(app: AppHandle, name: String, value: String) -> Result<secrets::Location, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut config = Config::load(&app);
+        let location = secrets::store(&mut config, &name, &value)?;
+        config.save(&app)?;
+        Ok(location)
+    })
+    .await
+    .unwrap_or_else(|e| Err(AppError::Io(e.to_string())))
+}
+
+/// A secret saved with `set_secret` (or still in config.json), `null` if unset
 #[tauri::command]
-async fn install_update(_app: AppHandle) -> Result<(), String> {
-    Err("Updater is not configured yet".to_string())
+async fn get_secret(app: AppHandle, name: String) -> Result<Option<String>, AppError> {
+    tauri::async_runtime::spawn_blocking(move || secrets::get(&Config::load(&app), &name))
+        .await
+        .unwrap_or_else(|e| Err(AppError::Io(e.to_string())))
+}
+
+/// Switch between the "stable" and "beta" release channels (persisted)
+#[tauri::command]
+fn set_update_channel(app: AppHandle, channel: String) -> Result<(), AppError> {
+    let channel = updater::Channel::parse(&channel)?;
+    let mut config = Config::load(&app);
+    config.update_channel = channel.as_str().to_string();
+    config.save(&app)
+}
+
+// Download and install the latest release, then restart.
+// Fails with `updater_unavailable` until a signing pubkey is configured.
+#[tauri::command]
+async fn install_update(app: AppHandle) -> Result<(), AppError> {
+    let _busy = tray::busy(&app);
+    updater::install(&app).await
+}
+
+/// Versions before/after the last in-app update and whether it looks broken
+/// (crashed or quit within a minute of its first launch)
+#[tauri::command]
+fn get_update_status(app: AppHandle) -> updater::UpdateStatus {
+    updater::status(&app)
+}
+
+/// Restore the version the last update replaced and restart (macOS/Linux AppImage)
+#[tauri::command]
+fn rollback_update(app: AppHandle) -> Result<(), AppError> {
+    updater::rollback(&app)
+}
+
+/// Stop an in-flight `install_update` download. Returns false if none was running.
+#[tauri::command]
+fn cancel_update() -> bool {
+    updater::cancel()
+}
+
+/// Path of the log file, for attaching to bug reports
+#[tauri::command]
+fn get_log_path(app: AppHandle) -> Result<String, AppError> {
+    logging::log_path(&app)
+        .map(|path| path.to_string_lossy().to_string())
+        .ok_or_else(|| AppError::Io("No app log directory".to_string()))
+}
+
+/// Change the log level now and for future launches
+#[tauri::command]
+fn set_log_level(app: AppHandle, level: String) -> Result<(), AppError> {
+    let filter = logging::parse_level(&level)?;
+    logging::set_level(filter);
+    let mut config = Config::load(&app);
+    config.log_level = filter.to_string().to_ascii_lowercase();
+    config.save(&app)
+}
+
+/// Ask the configured vision model about a capture, e.g. "what's the error here?".
+/// `provider` ("anthropic", "openai", "ollama") overrides the saved one for this call.
+#[tauri::command]
+async fn analyze_capture(
+    app: AppHandle,
+    data_url: String,
+    prompt: String,
+    provider: Option<String>,
+) -> Result<String, AppError> {
+    if prompt.trim().is_empty() {
+        return Err(AppError::InvalidArgument("Prompt is empty".to_string()));
+    }
+    let config = tauri::async_runtime::spawn_blocking(move || {
+        let mut config = Config::load(&app);
+        secrets::fill(&mut config);
+        config
+    })
+    .await
+    .map_err(|e| AppError::Io(e.to_string()))?;
+    ai::analyze(&config, &data_url, &prompt, provider.as_deref()).await.inspect_err(|e| log::warn!("{}", e))
+}
+
+/// Save the provider, API key, base URL and model used by `analyze_capture`.
+/// Omitting `api_key` keeps the saved key; empty base URL / model mean the provider defaults.
+#[tauri::command]
+async fn set_ai_settings(
+    app: AppHandle,
+    provider: String,
+    api_key: Option<String>,
+    base_url: Option<String>,
+    model: Option<String>,
+) -> Result<(), AppError> {
+    let provider = ai::Provider::parse(&provider)?;
+    let non_empty = |v: Option<String>| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut config = Config::load(&app);
+        config.ai_provider = provider.as_str().to_string();
+        if let Some(api_key) = api_key {
+            secrets::store(&mut config, "aiApiKey", &api_key)?;
+        }
+        config.ai_base_url = non_empty(base_url);
+        config.ai_model = non_empty(model);
+        config.save(&app)
+    })
+    .await
+    .unwrap_or_else(|e| Err(AppError::Io(e.to_string())))
 }
 
 /// Web search via DuckDuckGo — runs from Rust to bypass CORS restrictions in WebView2
@@ -269,6 +1360,7 @@ async fn call_claude_simple(
 
 /// Call Claude API from Rust — bypasses WebView2 CORS entirely
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn invoke_claude(
     app: AppHandle,
     api_key: String,
@@ -337,24 +1429,19 @@ async fn invoke_claude(
             Ok(Some(chunk)) => {
                 let data: Vec<u8> = chunk.to_vec();
                 buffer.push_str(&String::from_utf8_lossy(&data));
-                loop {
-                    match buffer.find('\n') {
-                        Some(pos) => {
-                            let line = buffer[..pos].trim().to_string();
-                            buffer = buffer[pos + 1..].to_string();
-                            if line.starts_with("data: ") {
-                                let data = line[6..].trim();
-                                if data == "[DONE]" { continue; }
-                                if let Ok(ev) = serde_json::from_str::<serde_json::Value>(data) {
-                                    if ev["type"].as_str() == Some("content_block_delta") {
-                                        if let Some(text) = ev["delta"]["text"].as_str() {
-                                            let _ = window.emit("claude-chunk", serde_json::json!({"rid": &request_id, "text": text}));
-                                        }
-                                    }
+                while let Some(pos) = buffer.find('\n') {
+                    let line = buffer[..pos].trim().to_string();
+                    buffer = buffer[pos + 1..].to_string();
+                    if let Some(data) = line.strip_prefix("data: ") {
+                        let data = data.trim();
+                        if data == "[DONE]" { continue; }
+                        if let Ok(ev) = serde_json::from_str::<serde_json::Value>(data) {
+                            if ev["type"].as_str() == Some("content_block_delta") {
+                                if let Some(text) = ev["delta"]["text"].as_str() {
+                                    let _ = window.emit("claude-chunk", serde_json::json!({"rid": &request_id, "text": text}));
                                 }
                             }
                         }
-                        None => break,
                     }
                 }
             }
@@ -376,75 +1463,321 @@ async fn set_fullscreen(app: AppHandle, fullscreen: bool) {
     }
 }
 
-/// Pending capture data for the overlay window to retrieve
-static PENDING_CAPTURE: std::sync::Mutex<Option<(String, String)>> = std::sync::Mutex::new(None);
+/// What an overlay window shows, returned by `get_pending_capture`
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PendingCapture {
+    /// "toolbar", "region" or "text"
+    mode: String,
+    data_url: String,
+    /// Tells this overlay's entry apart from a newer overlay reusing the label
+    #[serde(skip)]
+    id: u64,
+}
+
+static NEXT_OVERLAY_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// Capture data for each overlay window, by label. Stored before the window is
+/// created and kept until it is destroyed, so the overlay can ask for it
+/// whenever its script is ready (reloads included) — no event to miss.
+static PENDING_CAPTURE: std::sync::Mutex<std::collections::BTreeMap<String, PendingCapture>> =
+    std::sync::Mutex::new(std::collections::BTreeMap::new());
+
+/// Label of the toolbar overlay, and prefix of the per-display region overlays
+const OVERLAY_LABEL: &str = "capture-overlay";
+
+/// Every open capture overlay window
+fn capture_overlays(app: &AppHandle) -> Vec<tauri::Window> {
+    app.windows()
+        .into_iter()
+        .filter(|(label, _)| label.starts_with(OVERLAY_LABEL))
+        .map(|(_, window)| window)
+        .collect()
+}
+
+/// Close all capture overlays and drop their pending captures
+async fn close_capture_overlays(app: &AppHandle) {
+    preview::stop();
+    PENDING_CAPTURE.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    for overlay in capture_overlays(app) {
+        close_and_wait(overlay, std::time::Duration::from_secs(1)).await;
+    }
+}
+
+/// Hide the main window and wait until it is really off screen: poll
+/// `is_visible()` (bounded by `Config::hide_timeout_ms`), then leave
+/// `Config::hide_settle_ms` for the compositor's fade-out animation.
+/// Returns at once if the window was not visible to begin with.
+async fn hide_main_window(app: &AppHandle) {
+    let Some(window) = app.get_window("main") else { return };
+    if !window.is_visible().unwrap_or(true) {
+        return;
+    }
+    let config = Config::load(app);
+    let _ = window.hide();
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_millis(config.hide_timeout_ms);
+    while window.is_visible().unwrap_or(false) {
+        if std::time::Instant::now() >= deadline {
+            log::warn!("Main window still visible after {} ms, capturing anyway", config.hide_timeout_ms);
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(16)).await;
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(config.hide_settle_ms)).await;
+}
+
+/// Close `window` and wait until it is actually destroyed (at most `max`)
+async fn close_and_wait(window: tauri::Window, max: std::time::Duration) {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let tx = std::sync::Mutex::new(Some(tx));
+    window.on_window_event(move |event| {
+        if let tauri::WindowEvent::Destroyed = event {
+            if let Some(tx) = tx.lock().ok().and_then(|mut tx| tx.take()) {
+                let _ = tx.send(());
+            }
+        }
+    });
+    if window.close().is_ok() {
+        let _ = tokio::time::timeout(max, rx).await;
+    }
+}
+
+/// Shows the main window again when dropped, unless `disarm`ed. Keeps a
+/// failed or panicking capture from leaving the app hidden in the tray.
+struct RestoreMainWindow(Option<AppHandle>);
+
+impl RestoreMainWindow {
+    fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for RestoreMainWindow {
+    fn drop(&mut self) {
+        let Some(app) = self.0.take() else { return };
+        if let Some(w) = app.get_window("main") {
+            let _ = w.unminimize();
+            let _ = w.show();
+            let _ = w.set_focus();
+        }
+    }
+}
+
+/// Set while `open_capture_overlay` is capturing and building the overlay
+static OVERLAY_BUSY: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+/// When the last overlay capture was started
+static LAST_OVERLAY: std::sync::Mutex<Option<std::time::Instant>> = std::sync::Mutex::new(None);
+/// Triggers this soon after the previous one are ignored
+const OVERLAY_COOLDOWN: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Clears `OVERLAY_BUSY` however the overlay task ends
+struct OverlayBusy;
+
+impl Drop for OverlayBusy {
+    fn drop(&mut self) {
+        OVERLAY_BUSY.store(false, std::sync::atomic::Ordering::SeqCst);
+    }
+}
 
 /// Open a separate fullscreen overlay window for capture (toolbar or direct region).
-/// The main window stays hidden — only the overlay appears.
+/// The main window stays hidden — only the overlay appears. Region mode opens
+/// one overlay per display, each showing that display's screenshot, so the
+/// selection can be made on any screen; closing one closes them all.
+/// On any failure the main window comes back and gets a "capture-error" event.
+/// Repeated triggers (key mashing, double clicks) are ignored while a capture
+/// is in progress or within `OVERLAY_COOLDOWN` of the last one.
 fn open_capture_overlay(app: &AppHandle, mode: &str) {
+    if ensure_display(app).is_err() {
+        return;
+    }
+    {
+        let mut last = LAST_OVERLAY.lock().unwrap_or_else(|e| e.into_inner());
+        if last.is_some_and(|t| t.elapsed() < OVERLAY_COOLDOWN)
+            || OVERLAY_BUSY.swap(true, std::sync::atomic::Ordering::SeqCst)
+        {
+            log::debug!("Ignoring {} capture trigger: another capture is starting", mode);
+            return;
+        }
+        *last = Some(std::time::Instant::now());
+    }
+    let busy = OverlayBusy;
+
     let app_clone = app.clone();
     let mode_str = mode.to_string();
     tauri::async_runtime::spawn(async move {
+        let _busy = busy;
+        // A previous overlay would show up in the new screenshot — close it first
+        close_capture_overlays(&app_clone).await;
         // Hide main window so it doesn't appear in the screenshot
-        if let Some(window) = app_clone.get_window("main") {
-            let _ = window.hide();
-        }
-        tokio::time::sleep(std::time::Duration::from_millis(800)).await;
+        hide_main_window(&app_clone).await;
+        let restore = RestoreMainWindow(Some(app_clone.clone()));
+
+        let fail = |e: &dyn std::fmt::Display| {
+            log::error!("Capture overlay failed: {}", e);
+            PENDING_CAPTURE.lock().unwrap_or_else(|e| e.into_inner()).clear();
+            for overlay in capture_overlays(&app_clone) {
+                let _ = overlay.close();
+            }
+            let _ = app_clone.emit_to("main", "capture-error", e.to_string());
+        };
 
-        match native_capture("fullscreen") {
-            Ok(payload) => {
-                // Store capture data for the overlay to retrieve
-                if let Ok(mut pending) = PENDING_CAPTURE.lock() {
-                    *pending = Some((mode_str.clone(), payload.data_url));
-                }
+        // (label, display to capture, monitor to cover — None = the current one)
+        let targets: Vec<(String, Option<usize>, Option<tauri::Monitor>)> = match overlay_monitors(&app_clone) {
+            monitors if (mode_str == "region" || mode_str == "text") && monitors.len() > 1 => monitors
+                .into_iter()
+                .map(|(index, monitor)| (format!("{}-{}", OVERLAY_LABEL, index), Some(index), Some(monitor)))
+                .collect(),
+            _ => vec![(OVERLAY_LABEL.to_string(), None, None)],
+        };
 
-                // Close existing overlay if any
-                if let Some(old) = app_clone.get_window("capture-overlay") {
-                    let _ = old.close();
-                    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
-                }
+        // Grab every display before any overlay appears on screen
+        let indexes: Vec<Option<usize>> = targets.iter().map(|(_, index, _)| *index).collect();
+        let captures = tauri::async_runtime::spawn_blocking(move || {
+            indexes
+                .into_iter()
+                .map(|index| native_capture("fullscreen", index, CaptureOptions::default()))
+                .collect::<Result<Vec<_>, AppError>>()
+        })
+        .await
+        .unwrap_or_else(|e| Err(AppError::CaptureFailed(e.to_string())));
+        let captures = match captures {
+            Ok(captures) => captures,
+            Err(e) => return fail(&e),
+        };
 
-                // Create fullscreen overlay window (no decorations, always on top)
-                if let Err(e) = tauri::WindowBuilder::new(
-                    &app_clone,
-                    "capture-overlay",
-                    tauri::WindowUrl::App("index.html".into())
-                )
-                .title("ScreenAI Capture")
-                .decorations(false)
-                .always_on_top(true)
-                .fullscreen(true)
-                .skip_taskbar(true)
-                .build() {
-                    eprintln!("Failed to create capture overlay: {}", e);
-                    if let Some(w) = app_clone.get_window("main") {
-                        let _ = w.unminimize();
-                        let _ = w.show();
-                        let _ = w.set_focus();
-                    }
-                }
+        for ((label, _, monitor), payload) in targets.into_iter().zip(captures) {
+            // Store capture data for the overlay to retrieve
+            let id = NEXT_OVERLAY_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            PENDING_CAPTURE
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(label.clone(), PendingCapture { mode: mode_str.clone(), data_url: payload.data_url, id });
+            if let Err(e) = build_overlay(&app_clone, &label, id, monitor.as_ref()) {
+                return fail(&format!("could not create the overlay window: {}", e));
             }
-            Err(e) => {
-                if let Some(w) = app_clone.get_window("main") {
-                    let _ = w.unminimize();
-                    let _ = w.show();
-                    let _ = w.set_focus();
-                    let _ = w.emit("capture-error", &e);
-                }
+        }
+        restore.disarm();
+    });
+}
+
+/// Displays to cover with region overlays, paired with their `capture::screens()` index
+fn overlay_monitors(app: &AppHandle) -> Vec<(usize, tauri::Monitor)> {
+    let Ok(screens) = capture::screens() else { return Vec::new() };
+    app.get_window("main")
+        .and_then(|w| w.available_monitors().ok())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|monitor| {
+            let index = screens.iter().position(|s| same_display(&monitor, s.x, s.y))?;
+            Some((index, monitor))
+        })
+        .collect()
+}
+
+/// Create one fullscreen overlay window (no decorations, always on top),
+/// on `monitor` or wherever the OS puts new windows. Its `PendingCapture`
+/// (stored under `label` with `id`) is dropped when the window is destroyed.
+fn build_overlay(app: &AppHandle, label: &str, id: u64, monitor: Option<&tauri::Monitor>) -> tauri::Result<()> {
+    let window = tauri::WindowBuilder::new(app, label, tauri::WindowUrl::App("index.html".into()))
+        .title("ScreenAI Capture")
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .visible(monitor.is_none())
+        .fullscreen(monitor.is_none())
+        .build()?;
+    if let Some(monitor) = monitor {
+        // Fullscreen applies to the display the window is on, so move it there first
+        window.set_position(*monitor.position())?;
+        window.set_size(*monitor.size())?;
+        window.set_fullscreen(true)?;
+        window.show()?;
+        let _ = window.set_focus();
+    }
+    // One overlay closing (Escape, a finished selection) closes its siblings
+    let app = app.clone();
+    let label = label.to_string();
+    window.on_window_event(move |event| {
+        if let tauri::WindowEvent::Destroyed = event {
+            preview::stop();
+            let mut pending = PENDING_CAPTURE.lock().unwrap_or_else(|e| e.into_inner());
+            if pending.get(&label).is_some_and(|p| p.id == id) {
+                pending.remove(&label);
+            }
+            drop(pending);
+            for overlay in capture_overlays(&app) {
+                let _ = overlay.close();
             }
         }
     });
+    Ok(())
+}
+
+/// Show the capture toolbar overlay — used by the captureFullscreen shortcut
+fn show_capture_toolbar(app: &AppHandle) {
+    open_capture_overlay(app, "toolbar");
+}
+
+/// Tauri command to open capture overlay from JS
+#[tauri::command]
+fn open_capture_overlay_cmd(app: AppHandle, mode: String) {
+    open_capture_overlay(&app, &mode);
+}
+
+/// Make the calling overlay's backdrop live instead of frozen: fresh grabs of
+/// the screen under it at `fps` (1-15, default `overlayPreviewFps`) arrive as
+/// "overlay-preview-frame" (a JPEG data URL) until the selection is committed
+/// or cancelled. Not available on Linux; keep the frozen screenshot there.
+#[tauri::command]
+async fn start_overlay_preview(app: AppHandle, window: tauri::Window, fps: Option<u32>) -> Result<(), AppError> {
+    if !window.label().starts_with(OVERLAY_LABEL) {
+        return Err(AppError::InvalidArgument("start_overlay_preview must be called from a capture overlay".to_string()));
+    }
+    preview::start(&window, fps.unwrap_or(Config::load(&app).overlay_preview_fps))
+}
+
+/// Freeze the overlay backdrops on their last frame
+#[tauri::command]
+fn stop_overlay_preview() {
+    preview::stop();
+}
+
+/// Pin a capture on screen as a floating always-on-top window.
+/// Returns the new window's label; several pins can be open at once.
+/// Async so the window is not built on the main thread (deadlocks on Windows).
+#[tauri::command]
+async fn pin_capture(app: AppHandle, data_url: String) -> Result<String, AppError> {
+    pin::open(&app, data_url)
+}
+
+/// Image shown by the calling pin window
+#[tauri::command]
+fn get_pinned_capture(window: tauri::Window) -> Result<String, AppError> {
+    pin::image_for(window.label())
+}
+
+/// Let pin window `window_label` drop behind other windows (`on_top` false)
+/// or keep it above them again
+#[tauri::command]
+fn set_pin_always_on_top(app: AppHandle, window_label: String, on_top: bool) -> Result<(), AppError> {
+    pin::set_always_on_top(&app, &window_label, on_top)
 }
 
-/// Show the capture toolbar overlay — used by Alt+Shift+S
-fn show_capture_toolbar(app: &AppHandle) {
-    open_capture_overlay(app, "toolbar");
+/// Make pin window `label` see-through, for comparing it with what's under it.
+/// `opacity` is 0-1; below 0.1 counts as 0.1 so the pin stays findable.
+#[tauri::command]
+async fn set_pin_opacity(app: AppHandle, label: String, opacity: f64) -> Result<(), AppError> {
+    // Off the main thread, which `set_opacity` waits on
+    tauri::async_runtime::spawn_blocking(move || pin::set_opacity(&app, &label, opacity))
+        .await
+        .unwrap_or_else(|e| Err(AppError::Io(e.to_string())))
 }
 
-/// Tauri command to open capture overlay from JS
+/// Close the calling pin window (Escape)
 #[tauri::command]
-fn open_capture_overlay_cmd(app: AppHandle, mode: String) {
-    open_capture_overlay(&app, &mode);
+fn close_pin(window: tauri::Window) {
+    pin::close(&window);
 }
 
 #[tauri::command]
@@ -452,32 +1785,110 @@ fn get_window_label(window: tauri::Window) -> String {
     window.label().to_string()
 }
 
+/// The capture for the calling overlay window. Can be asked for any number of
+/// times until the overlay closes.
 #[tauri::command]
-fn get_pending_capture() -> Result<serde_json::Value, String> {
-    let mut pending = PENDING_CAPTURE.lock().map_err(|e| e.to_string())?;
-    match pending.take() {
-        Some((mode, data_url)) => Ok(serde_json::json!({"mode": mode, "dataUrl": data_url})),
-        None => Err("No pending capture".to_string()),
-    }
+fn get_pending_capture(window: tauri::Window) -> Result<PendingCapture, AppError> {
+    PENDING_CAPTURE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(window.label())
+        .cloned()
+        .ok_or_else(|| AppError::InvalidArgument(format!("No pending capture for {}", window.label())))
 }
 
 #[tauri::command]
 async fn send_capture_to_main(app: AppHandle, data_url: String, mode: String) -> Result<(), String> {
-    if let Some(overlay) = app.get_window("capture-overlay") {
+    for overlay in capture_overlays(&app) {
         let _ = overlay.close();
     }
     if let Some(window) = app.get_window("main") {
         let _ = window.unminimize();
         let _ = window.show();
         let _ = window.set_focus();
-        let _ = window.emit("shortcut-capture", CapturePayload { data_url, mode });
+        let payload = capture::payload_from_data_url(data_url, mode);
+        after_capture(&app, &payload);
+        let _ = window.emit("shortcut-capture", payload);
     }
     Ok(())
 }
 
+/// Finish a drag in the capture overlay: close the overlay, capture the
+/// selected area live and emit it to the main window as "shortcut-capture".
+/// `x`, `y`, `w`, `h` are CSS pixels relative to the calling overlay window,
+/// i.e. to the display it covers. With `extract_text` (the captureText
+/// shortcut) the area is also OCR'd and "capture-text" is emitted instead.
+#[tauri::command]
+async fn commit_region(
+    app: AppHandle,
+    window: tauri::Window,
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    extract_text: Option<bool>,
+) -> Result<CapturePayload, AppError> {
+    if !window.label().starts_with(OVERLAY_LABEL) {
+        return Err(AppError::InvalidArgument("commit_region must be called from a capture overlay".to_string()));
+    }
+    let overlay = window;
+    if !(w.is_finite() && h.is_finite() && w >= 1.0 && h >= 1.0) {
+        return Err(AppError::InvalidArgument("Selection is empty".to_string()));
+    }
+
+    // Overlay-relative CSS pixels → global logical coordinates
+    let scale = overlay.scale_factor().unwrap_or(1.0);
+    let origin = overlay
+        .inner_position()
+        .map_err(|e| AppError::CaptureFailed(e.to_string()))?
+        .to_logical::<f64>(scale);
+    let rect = capture::Rect {
+        x: (origin.x + x).round() as i32,
+        y: (origin.y + y).round() as i32,
+        w: w.round() as u32,
+        h: h.round() as u32,
+    };
+
+    let settle_ms = Config::load(&app).hide_settle_ms;
+    close_capture_overlays(&app).await;
+    // Let the compositor finish removing the overlay before grabbing pixels
+    tokio::time::sleep(std::time::Duration::from_millis(settle_ms)).await;
+
+    let options = capture_options(&app, None, None, None, None, None, None, None, None)?;
+    let result = capture_region_rect(&app, rect, scale, options).await;
+    let Some(window) = app.get_window("main") else { return result };
+    let _ = window.unminimize();
+    match (&result, extract_text.unwrap_or(false)) {
+        (Ok(payload), true) => {
+            let payload = payload.clone();
+            let lang = ocr::DEFAULT_LANG;
+            match tauri::async_runtime::spawn_blocking(move || ocr::recognize_capture(payload, lang)).await {
+                Ok(Ok(ocr)) => { let _ = window.emit("capture-text", ocr); }
+                Ok(Err(e)) => { let _ = window.emit("capture-error", e.to_string()); }
+                Err(e) => { let _ = window.emit("capture-error", e.to_string()); }
+            }
+        }
+        (Ok(payload), false) => { let _ = window.emit("shortcut-capture", payload); }
+        (Err(e), _) => { let _ = window.emit("capture-error", e.to_string()); }
+    }
+    result
+}
+
+/// Dismiss the capture overlay without capturing (Escape) and bring the main window back
+#[tauri::command]
+async fn cancel_capture(app: AppHandle) {
+    close_capture_overlays(&app).await;
+    if let Some(window) = app.get_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+        let _ = window.emit("capture-cancelled", ());
+    }
+}
+
 #[tauri::command]
 async fn close_capture_overlay(app: AppHandle) {
-    if let Some(overlay) = app.get_window("capture-overlay") {
+    for overlay in capture_overlays(&app) {
         let _ = overlay.close();
     }
     if let Some(window) = app.get_window("main") {
@@ -533,29 +1944,111 @@ async fn share_native(file_path: String) -> Result<(), String> {
     Ok(())
 }
 
-/// Update a global shortcut: unregister the old one and register the new one.
-/// `action` is one of: "captureFullscreen" (opens toolbar), "captureRegion", "captureWindow"
+/// Run the capture flow bound to a shortcut `action`
+fn run_shortcut_action(app: &AppHandle, action: &str) {
+    match action {
+        "captureFullscreen" => show_capture_toolbar(app),
+        "captureRegion" => open_capture_overlay(app, "region"),
+        "captureWindow" => open_capture_overlay(app, "window"),
+        "captureText" => open_capture_overlay(app, "text"),
+        "captureLastRegion" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = capture_last_region(app.clone(), None, None, None, None, None, None, None, None).await {
+                    if let Some(window) = app.get_window("main") {
+                        let _ = window.emit("capture-error", e.to_string());
+                    }
+                }
+            });
+        }
+        _ => {}
+    }
+}
+
+/// Register `accelerator` as the global shortcut for `action`
+fn register_shortcut(app: &AppHandle, action: &str, accelerator: &str) -> Result<(), AppError> {
+    let handle = app.clone();
+    let act = action.to_string();
+    app.global_shortcut_manager()
+        .register(accelerator, move || run_shortcut_action(&handle, &act))
+        .map_err(|e| AppError::ShortcutUnavailable(format!("{}: {}", accelerator, e)))
+}
+
+/// Show the current bindings next to the tray capture items
+fn refresh_tray_labels(app: &AppHandle, config: &Config) {
+    let tray = app.tray_handle();
+    for (id, label, action) in [("capture", "Capture", "captureFullscreen"), ("capture_region", "Region", "captureRegion")] {
+        let title = match config.shortcuts.get(action) {
+            Some(accelerator) if !accelerator.is_empty() => format!("{} ({})", label, accelerator),
+            _ => label.to_string(),
+        };
+        let _ = tray.get_item(id).set_title(title);
+    }
+}
+
+/// Rebuild the tray menu, e.g. after a monitor was plugged in or removed
 #[tauri::command]
-fn update_shortcut(app_handle: AppHandle, old_shortcut: String, new_shortcut: String, action: String) -> Result<(), String> {
-    let mut manager = app_handle.global_shortcut_manager();
+fn rebuild_tray(app: AppHandle) -> Result<(), AppError> {
+    tray::rebuild(&app)?;
+    refresh_tray_labels(&app, &Config::load(&app));
+    Ok(())
+}
 
+/// Update a global shortcut: unregister the old one and register the new one.
+/// `action` is one of: "captureFullscreen" (opens toolbar), "captureRegion", "captureWindow", "captureText" (region + OCR),
+/// "captureLastRegion" (repeats the last region without a selection)
+/// Saved to the config like `set_shortcut`, which this otherwise matches; `old_shortcut`
+/// is also released in case the UI still had it bound from before the config it reads.
+#[tauri::command]
+fn update_shortcut(app_handle: AppHandle, old_shortcut: String, new_shortcut: String, action: String) -> Result<(), AppError> {
+    let old_shortcut = old_shortcut.trim();
+    let saved = Config::load(&app_handle).shortcuts.get(&action).cloned().unwrap_or_default();
     // Unregister old shortcut (ignore errors — it may not exist)
-    if !old_shortcut.is_empty() {
-        let _ = manager.unregister(&old_shortcut);
+    if !old_shortcut.is_empty() && old_shortcut != saved && old_shortcut != new_shortcut.trim() {
+        let _ = app_handle.global_shortcut_manager().unregister(old_shortcut);
+    }
+    set_shortcut(app_handle, action, new_shortcut)
+}
+
+/// Rebind `action` to `accelerator` and persist it to the config file.
+/// An empty `accelerator` removes the binding. If the new combo cannot be
+/// registered the previous one is restored and the error is returned.
+#[tauri::command]
+fn set_shortcut(app: AppHandle, action: String, accelerator: String) -> Result<(), AppError> {
+    if !config::SHORTCUT_ACTIONS.contains(&action.as_str()) {
+        return Err(AppError::InvalidArgument(format!("Unknown shortcut action: {}", action)));
+    }
+    let accelerator = accelerator.trim().to_string();
+
+    let mut config = Config::load(&app);
+    let old = config.shortcuts.get(&action).cloned().unwrap_or_default();
+    if old == accelerator {
+        return Ok(());
+    }
+    if !accelerator.is_empty() {
+        if let Some((other, _)) = config.shortcuts.iter().find(|(a, acc)| **a != action && **acc == accelerator) {
+            return Err(AppError::InvalidArgument(format!("{} is already used by {}", accelerator, other)));
+        }
     }
 
-    // Register new shortcut
-    let handle = app_handle.clone();
-    let act = action.clone();
-    manager.register(&new_shortcut, move || {
-        match act.as_str() {
-            "captureFullscreen" => open_capture_overlay(&handle, "toolbar"),
-            "captureRegion" => open_capture_overlay(&handle, "region"),
-            "captureWindow" => open_capture_overlay(&handle, "window"),
-            _ => {}
+    let mut manager = app.global_shortcut_manager();
+    if !old.is_empty() {
+        let _ = manager.unregister(&old);
+    }
+    if !accelerator.is_empty() {
+        if let Err(e) = register_shortcut(&app, &action, &accelerator) {
+            if !old.is_empty() {
+                let _ = register_shortcut(&app, &action, &old);
+            }
+            return Err(e);
         }
-    }).map_err(|e| format!("Impossible d'enregistrer le raccourci {}: {}", new_shortcut, e))?;
+        config.shortcuts.insert(action, accelerator);
+    } else {
+        config.shortcuts.remove(&action);
+    }
 
+    config.save(&app)?;
+    refresh_tray_labels(&app, &config);
     Ok(())
 }
 
@@ -583,12 +2076,296 @@ fn unix_days_to_date(days: u64) -> (u64, u64, u64) {
 
 /// Get the dedicated ScreenAI captures directory (~/Documents/ScreenAI/Captures/)
 fn get_screenai_captures_dir() -> std::path::PathBuf {
-    let base = dirs::document_dir().unwrap_or_else(|| dirs::home_dir().unwrap_or_else(|| std::env::temp_dir()));
+    let base = dirs::document_dir().unwrap_or_else(|| dirs::home_dir().unwrap_or_else(std::env::temp_dir));
     let captures_dir = base.join("ScreenAI").join("Captures");
     std::fs::create_dir_all(&captures_dir).ok();
     captures_dir
 }
 
+/// Keeps the clipboard handle alive — on Linux the copied image is only served while it exists
+static CLIPBOARD: std::sync::Mutex<Option<arboard::Clipboard>> = std::sync::Mutex::new(None);
+
+/// Copy a captured image to the OS clipboard (accepts a data URL or raw base64)
+#[tauri::command]
+fn copy_image_to_clipboard(data_url: String) -> Result<(), AppError> {
+    let image = imaging::decode_data_url(&data_url)?;
+    let mut guard = CLIPBOARD
+        .lock()
+        .map_err(|e| AppError::ClipboardUnavailable(e.to_string()))?;
+    let clipboard = match guard.as_mut() {
+        Some(clipboard) => clipboard,
+        None => guard.insert(
+            arboard::Clipboard::new().map_err(|e| AppError::ClipboardUnavailable(e.to_string()))?,
+        ),
+    };
+    clipboard
+        .set_image(arboard::ImageData {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            bytes: std::borrow::Cow::Owned(image.into_raw()),
+        })
+        .map_err(|e| AppError::ClipboardUnavailable(e.to_string()))
+}
+
+/// Use the image on the OS clipboard as a capture (mode "clipboard", PNG), so
+/// pasted images go through the same annotation and redaction commands.
+/// Fails with `clipboard_no_image` when the clipboard holds no image.
+#[tauri::command]
+async fn capture_from_clipboard() -> Result<CapturePayload, AppError> {
+    tauri::async_runtime::spawn_blocking(|| {
+        let data = {
+            let mut guard = CLIPBOARD
+                .lock()
+                .map_err(|e| AppError::ClipboardUnavailable(e.to_string()))?;
+            // Reuse the handle so reading doesn't drop an image we are still serving
+            let clipboard = match guard.as_mut() {
+                Some(clipboard) => clipboard,
+                None => guard.insert(
+                    arboard::Clipboard::new().map_err(|e| AppError::ClipboardUnavailable(e.to_string()))?,
+                ),
+            };
+            clipboard.get_image().map_err(|e| match e {
+                arboard::Error::ContentNotAvailable => AppError::ClipboardNoImage,
+                e => AppError::ClipboardUnavailable(e.to_string()),
+            })?
+        };
+        let image = image::RgbaImage::from_raw(data.width as u32, data.height as u32, data.bytes.into_owned())
+            .ok_or_else(|| AppError::DecodeFailed("Clipboard image has an unexpected size".to_string()))?;
+        let bytes = imaging::encode(&image, OutputFormat::Png)?;
+        log::info!("Clipboard image {}x{} used as a capture", image.width(), image.height());
+        Ok(capture::payload_from_data_url(imaging::to_data_url(&bytes, OutputFormat::Png), "clipboard".to_string()))
+    })
+    .await
+    .unwrap_or_else(|e| Err(AppError::ClipboardUnavailable(e.to_string())))
+}
+
+/// Burn redactions into a capture before sharing it.
+/// `regions` are in image pixels; `style` is "blur", "pixelate" or "blackbox".
+/// `blur_radius` (default 12) and `block_size` (default 16 px) tune the effect.
+/// The result is re-encoded as `format` (PNG by default).
+#[tauri::command]
+fn redact_regions(
+    data_url: String,
+    regions: Vec<capture::Rect>,
+    style: String,
+    blur_radius: Option<f32>,
+    block_size: Option<u32>,
+    format: Option<String>,
+    quality: Option<u8>,
+) -> Result<CapturePayload, AppError> {
+    let style = edit::RedactStyle::parse(&style, blur_radius, block_size)?;
+    let format = OutputFormat::parse(format.as_deref(), quality)?;
+    let mut image = imaging::decode_data_url(&data_url)?;
+    edit::redact(&mut image, &regions, style);
+    let bytes = imaging::encode(&image, format)?;
+    Ok(capture::payload_from_data_url(imaging::to_data_url(&bytes, format), "redacted".to_string()))
+}
+
+/// Blur along a freehand stroke — for irregular areas `redact_regions` can't cover.
+/// `points` are image pixels (`[[x, y], ...]`, clamped to the image); `radius` is
+/// the blur strength (default 12) and `brush_size` the stroke width in pixels.
+#[tauri::command]
+fn blur_path(
+    data_url: String,
+    points: Vec<(u32, u32)>,
+    radius: Option<u32>,
+    brush_size: u32,
+    format: Option<String>,
+    quality: Option<u8>,
+) -> Result<CapturePayload, AppError> {
+    if brush_size == 0 {
+        return Err(AppError::InvalidArgument("Brush size must be at least 1 pixel".to_string()));
+    }
+    let radius = radius.map_or(edit::DEFAULT_BLUR_RADIUS, |r| r as f32).clamp(1.0, 100.0);
+    let format = OutputFormat::parse(format.as_deref(), quality)?;
+    let mut image = imaging::decode_data_url(&data_url)?;
+    edit::blur_path(&mut image, &points, radius, brush_size);
+    let bytes = imaging::encode(&image, format)?;
+    Ok(capture::payload_from_data_url(imaging::to_data_url(&bytes, format), "redacted".to_string()))
+}
+
+/// Trim a capture to `w`×`h` pixels at (`x`, `y`) without capturing again.
+/// The rectangle is in image pixels and must lie inside the image.
+#[tauri::command]
+fn crop_capture(
+    data_url: String,
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+    format: Option<String>,
+    quality: Option<u8>,
+) -> Result<CapturePayload, AppError> {
+    let format = OutputFormat::parse(format.as_deref(), quality)?;
+    let image = edit::crop(&imaging::decode_data_url(&data_url)?, x, y, w, h)?;
+    let bytes = imaging::encode(&image, format)?;
+    Ok(capture::payload_from_data_url(imaging::to_data_url(&bytes, format), "cropped".to_string()))
+}
+
+/// Scale a capture down to fit `max_width`×`max_height`, keeping its aspect ratio.
+/// Never upscales. `filter` is "nearest", "triangle" (default) or "lanczos3".
+#[tauri::command]
+fn resize_capture(
+    data_url: String,
+    max_width: u32,
+    max_height: u32,
+    filter: Option<String>,
+    format: Option<String>,
+    quality: Option<u8>,
+) -> Result<CapturePayload, AppError> {
+    let filter = edit::parse_filter(filter.as_deref())?;
+    let format = OutputFormat::parse(format.as_deref(), quality)?;
+    let image = edit::downscale(imaging::decode_data_url(&data_url)?, max_width, max_height, filter)?;
+    let bytes = imaging::encode(&image, format)?;
+    Ok(capture::payload_from_data_url(imaging::to_data_url(&bytes, format), "resized".to_string()))
+}
+
+/// Rotate or mirror a capture: `op` is "rotate90", "rotate180", "rotate270"
+/// (clockwise), "flip_h" or "flip_v". The payload carries the new dimensions.
+#[tauri::command]
+fn transform_capture(data_url: String, op: String, format: Option<String>, quality: Option<u8>) -> Result<CapturePayload, AppError> {
+    let transform = edit::Transform::parse(&op)?;
+    let format = OutputFormat::parse(format.as_deref(), quality)?;
+    let image = transform.apply(&imaging::decode_data_url(&data_url)?);
+    let bytes = imaging::encode(&image, format)?;
+    Ok(capture::payload_from_data_url(imaging::to_data_url(&bytes, format), "transformed".to_string()))
+}
+
+/// Frame a capture like a macOS window shot: `padding` pixels of `background`
+/// ("#rrggbb", or "#rrggbbaa" for a see-through canvas) on every side, a soft
+/// drop shadow and corners rounded by `corner_radius` pixels. `shadow` is
+/// `{ blur, offset_x, offset_y, color }`, each field optional (default a soft
+/// "#00000066" shadow 12 px below). The payload is `2 × padding` larger.
+#[tauri::command]
+async fn decorate_capture(
+    data_url: String,
+    corner_radius: u32,
+    shadow: Option<decorate::ShadowSpec>,
+    padding: u32,
+    background: String,
+    format: Option<String>,
+    quality: Option<u8>,
+) -> Result<CapturePayload, AppError> {
+    let format = OutputFormat::parse(format.as_deref(), quality)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let image = imaging::decode_data_url(&data_url)?;
+        let image = decorate::decorate(&image, corner_radius, &shadow.unwrap_or_default(), padding, &background)?;
+        let bytes = imaging::encode(&image, format)?;
+        Ok(capture::payload_from_data_url(imaging::to_data_url(&bytes, format), "decorated".to_string()))
+    })
+    .await
+    .unwrap_or_else(|e| Err(AppError::EncodeFailed(e.to_string())))
+}
+
+/// Visual diff for regression checks: `b` faded to gray with the pixels that
+/// differ from `a` in red, plus how much changed. Both captures must be the same
+/// size. `threshold` (0-1, default 0.1) is the channel difference that counts.
+#[tauri::command]
+async fn diff_captures(a: String, b: String, threshold: Option<f64>) -> Result<diff::DiffResult, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (a, b) = (imaging::decode_data_url(&a)?, imaging::decode_data_url(&b)?);
+        let (image, changed_pixels, bounds) = diff::diff(&a, &b, threshold.unwrap_or(diff::DEFAULT_THRESHOLD))?;
+        let total_pixels = image.width() as u64 * image.height() as u64;
+        let bytes = imaging::encode(&image, OutputFormat::Png)?;
+        Ok(diff::DiffResult {
+            data_url: imaging::to_data_url(&bytes, OutputFormat::Png),
+            changed_pixels,
+            total_pixels,
+            changed_percent: changed_pixels as f64 * 100.0 / total_pixels.max(1) as f64,
+            bounds,
+        })
+    })
+    .await
+    .unwrap_or_else(|e| Err(AppError::EncodeFailed(e.to_string())))
+}
+
+/// Small JPEG preview of a capture that fits within `max_dim`×`max_dim`, as a data URL.
+/// Cheaper for galleries than letting the webview decode the full image.
+#[tauri::command]
+fn make_thumbnail(data_url: String, max_dim: u32) -> Result<String, AppError> {
+    imaging::thumbnail(&imaging::decode_data_url(&data_url)?, max_dim)
+}
+
+/// Flatten arrows, rectangles, text, highlights and step badges onto a capture.
+/// Coordinates are image pixels; colors are hex strings ("#ff3b30", "#ffeb3b80").
+/// Step badges are numbered 1, 2, 3… across calls until `reset_step_counter`.
+#[tauri::command]
+fn annotate(
+    data_url: String,
+    ops: Vec<annotate::AnnotationOp>,
+    format: Option<String>,
+    quality: Option<u8>,
+) -> Result<CapturePayload, AppError> {
+    let format = OutputFormat::parse(format.as_deref(), quality)?;
+    let image = annotate::apply(imaging::decode_data_url(&data_url)?, &ops)?;
+    let bytes = imaging::encode(&image, format)?;
+    Ok(capture::payload_from_data_url(imaging::to_data_url(&bytes, format), "annotated".to_string()))
+}
+
+/// Number the next step badge 1 again, e.g. when a new capture is opened for editing
+#[tauri::command]
+fn reset_step_counter() {
+    annotate::reset_steps();
+}
+
+/// Upload a capture and return its public link. `target` is "imgur", "s3",
+/// "webdav" or "http" (custom endpoint); each is configured in settings
+/// (`uploadImgurClientId`, `uploadS3`, `uploadWebdav`, `uploadHttp`). A WebDAV
+/// upload returns the file's URL on the server, which needs the account to open. Emits "upload-progress" ({ target, bytes_sent, total_bytes }).
+#[tauri::command]
+async fn upload_capture(app: AppHandle, data_url: String, target: String) -> Result<upload::UploadResult, AppError> {
+    let target = upload::Target::parse(&target)?;
+    let _busy = tray::busy(&app);
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut config = Config::load(&app);
+        secrets::fill(&mut config);
+        upload::upload(&app, &config.upload_settings(), &data_url, target)
+    })
+        .await
+        .unwrap_or_else(|e| Err(AppError::UploadFailed(e.to_string())))
+}
+
+/// Stamp a text or logo watermark into a corner of a capture, alpha-blended at
+/// `watermark.opacity` (default 0.5). See `watermark::WatermarkSpec` for the JSON shape.
+#[tauri::command]
+fn add_watermark(
+    data_url: String,
+    watermark: watermark::WatermarkSpec,
+    format: Option<String>,
+    quality: Option<u8>,
+) -> Result<CapturePayload, AppError> {
+    let format = OutputFormat::parse(format.as_deref(), quality)?;
+    let image = watermark::apply(imaging::decode_data_url(&data_url)?, &watermark)?;
+    let bytes = imaging::encode(&image, format)?;
+    Ok(capture::payload_from_data_url(imaging::to_data_url(&bytes, format), "watermarked".to_string()))
+}
+
+/// Dominant colours of a capture as "#rrggbb", most frequent first (median
+/// cut). `count` is 1-32; fewer come back for images with fewer colours.
+/// Transparent pixels are ignored.
+#[tauri::command]
+async fn extract_palette(data_url: String, count: usize) -> Result<Vec<String>, AppError> {
+    tauri::async_runtime::spawn_blocking(move || palette::extract(&imaging::decode_data_url(&data_url)?, count))
+        .await
+        .unwrap_or_else(|e| Err(AppError::DecodeFailed(e.to_string())))
+}
+
+/// Burn a date/time into a corner of a capture, e.g. for evidence screenshots.
+/// See `watermark::TimestampSpec` for the JSON shape; pass `unix_secs` to
+/// show when the capture was taken rather than now.
+#[tauri::command]
+fn burn_timestamp(
+    data_url: String,
+    timestamp: watermark::TimestampSpec,
+    format: Option<String>,
+    quality: Option<u8>,
+) -> Result<CapturePayload, AppError> {
+    let format = OutputFormat::parse(format.as_deref(), quality)?;
+    let image = watermark::burn_timestamp(imaging::decode_data_url(&data_url)?, &timestamp)?;
+    let bytes = imaging::encode(&image, format)?;
+    Ok(capture::payload_from_data_url(imaging::to_data_url(&bytes, format), "timestamped".to_string()))
+}
+
 /// Save annotated capture to a temp file and return its path
 #[tauri::command]
 fn save_temp_capture(data: Vec<u8>) -> Result<String, String> {
@@ -609,34 +2386,256 @@ fn save_temp_capture(data: Vec<u8>) -> Result<String, String> {
     let filename = format!("ScreenAI_{}-{:02}-{:02}_{:02}-{:02}-{:02}.png", year, month, day, hours, minutes, seconds);
     let path = captures_dir.join(&filename);
     std::fs::write(&path, &data)
-        .map_err(|e| format!("Erreur ecriture fichier temp: {}", e))?;
+        .map_err(|e| format!("Could not write the temporary file: {}", e))?;
     Ok(path.to_string_lossy().to_string())
 }
 
-/// Reveal a file in Windows Explorer (select it)
+/// Deprecated: use `reveal_in_folder`, which also works on macOS and Linux and
+/// reports failures. Kept for older frontends; errors are only logged.
 #[tauri::command]
 fn reveal_in_explorer(path: String) {
-    #[cfg(target_os = "windows")]
-    {
-        let _ = std::process::Command::new("explorer")
-            .arg("/select,")
-            .arg(&path)
-            .spawn();
+    if let Err(e) = reveal_in_folder(path) {
+        log::warn!("reveal_in_explorer: {}", e);
+    }
+}
+
+/// Open the OS file manager on the folder containing `path`, with the file
+/// selected (Explorer, Finder, or the freedesktop FileManager1 service on Linux)
+#[tauri::command]
+fn reveal_in_folder(path: String) -> Result<(), AppError> {
+    let path = std::path::Path::new(&path);
+    if !path.exists() {
+        return Err(AppError::InvalidArgument(format!("{} does not exist", path.display())));
+    }
+    let path = path.canonicalize().map_err(|e| AppError::Io(e.to_string()))?;
+    let spawned = {
+        #[cfg(target_os = "windows")]
+        {
+            // canonicalize() adds a \\?\ prefix Explorer doesn't understand
+            let plain = path.to_string_lossy().trim_start_matches(r"\\?\").to_string();
+            std::process::Command::new("explorer").arg(format!("/select,{}", plain)).spawn()
+        }
+        #[cfg(target_os = "macos")]
+        {
+            std::process::Command::new("open").arg("-R").arg(&path).spawn()
+        }
+        #[cfg(target_os = "linux")]
+        {
+            if show_in_file_manager(&path).is_ok() {
+                return Ok(());
+            }
+            // No FileManager1 service: open the folder without selecting the file
+            let folder = if path.is_dir() { path.as_path() } else { path.parent().unwrap_or(&path) };
+            std::process::Command::new("xdg-open").arg(folder).spawn()
+        }
+    };
+    spawned
+        .map(|_| ())
+        .map_err(|e| AppError::Io(format!("Could not open the file manager: {}", e)))
+}
+
+/// Ask the desktop's file manager to show `path` selected
+#[cfg(target_os = "linux")]
+fn show_in_file_manager(path: &std::path::Path) -> Result<(), dbus::Error> {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => uri.push(byte as char),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
     }
+    let conn = dbus::blocking::Connection::new_session()?;
+    let proxy = conn.with_proxy("org.freedesktop.FileManager1", "/org/freedesktop/FileManager1", std::time::Duration::from_secs(5));
+    proxy.method_call("org.freedesktop.FileManager1", "ShowItems", (vec![uri], ""))
 }
 
 /// Save bytes to an arbitrary path (used for PDF export + image save from overlay)
 #[tauri::command]
 fn write_file_bytes(path: String, data: Vec<u8>) -> Result<(), String> {
+    disk::ensure_space(std::path::Path::new(&path), data.len() as u64).map_err(|e| e.to_string())?;
     std::fs::write(&path, &data)
-        .map_err(|e| format!("Erreur ecriture: {}", e))
+        .map_err(|e| format!("Could not write {}: {}", path, e))
+}
+
+/// Directories `save_capture` is allowed to write into
+fn allowed_save_roots() -> Vec<std::path::PathBuf> {
+    let mut roots = vec![get_screenai_captures_dir()];
+    if let Some(home) = dirs::home_dir() {
+        roots.push(home);
+    }
+    roots
+}
+
+/// Resolve `.` / `..` components without touching the filesystem
+fn normalize_path(path: &std::path::Path) -> std::path::PathBuf {
+    use std::path::Component;
+    let mut out = std::path::PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => { out.pop(); }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Resolve a save destination for `save_capture` / `capture_screen_to_file`.
+/// Relative paths land in the ScreenAI captures folder; anything outside the
+/// user's home directory is rejected, symlinks included. Creates the parent folder.
+fn checked_save_path(path: &str, overwrite: bool) -> Result<std::path::PathBuf, AppError> {
+    let requested = std::path::Path::new(path);
+    let target = if requested.is_absolute() {
+        normalize_path(requested)
+    } else {
+        normalize_path(&get_screenai_captures_dir().join(requested))
+    };
+    let outside = |path: &std::path::Path| {
+        AppError::InvalidArgument(format!("Refusing to write outside the home directory: {}", path.display()))
+    };
+
+    let roots: Vec<_> = allowed_save_roots()
+        .into_iter()
+        .map(|root| root.canonicalize().unwrap_or(root))
+        .collect();
+    let parent = target.parent().ok_or_else(|| outside(&target))?;
+    // Resolve symlinks in the part of the folder that exists before creating
+    // anything, so a link can't make us create folders outside the allowed roots
+    let existing = parent.ancestors().find(|dir| dir.exists()).ok_or_else(|| outside(&target))?;
+    let real_parent = existing
+        .canonicalize()
+        .map_err(|e| AppError::Io(e.to_string()))?
+        .join(parent.strip_prefix(existing).unwrap_or(std::path::Path::new("")));
+    if !roots.iter().any(|root| real_parent.starts_with(root)) {
+        return Err(outside(&real_parent));
+    }
+    // A symlink at the target itself would be followed by the write
+    if let Ok(real_target) = target.canonicalize() {
+        if !roots.iter().any(|root| real_target.starts_with(root)) {
+            return Err(outside(&real_target));
+        }
+    }
+    if target.exists() && !overwrite {
+        return Err(AppError::InvalidArgument(format!("File already exists: {}", target.display())));
+    }
+
+    std::fs::create_dir_all(parent)
+        .map_err(|e| AppError::Io(format!("Cannot create {}: {}", parent.display(), e)))?;
+    Ok(target)
+}
+
+/// Decode a capture and write it to `path`, encoding by file extension (png / jpg / webp).
+/// Relative paths land in the ScreenAI captures folder; anything outside the user's
+/// home directory is rejected. Returns the absolute path written.
+/// `strip_metadata` (default true) saves no text, timestamp, software, EXIF or
+/// colour-profile data: PNGs lose their ancillary chunks, other formats are
+/// re-encoded. With false, a capture already in the target format is written
+/// byte for byte.
+#[tauri::command]
+fn save_capture(data_url: String, path: String, overwrite: Option<bool>, strip_metadata: Option<bool>) -> Result<String, AppError> {
+    let target = checked_save_path(&path, overwrite.unwrap_or(false))?;
+    let format = OutputFormat::from_extension(&target)?;
+    let source = imaging::data_url_bytes(&data_url)?;
+    let same_format = imaging::sniff_mime(&source) == Some(format.mime());
+    let passthrough = match (strip_metadata.unwrap_or(true), same_format) {
+        (false, true) => Some(source),
+        (true, true) if format == OutputFormat::Png => imaging::strip_png_metadata(&source),
+        _ => None,
+    };
+    let bytes = match passthrough {
+        Some(bytes) => bytes,
+        // Our encoders write pixel data only, never metadata
+        None => imaging::encode(&imaging::decode_data_url(&data_url)?, format)?,
+    };
+
+    disk::ensure_space(&target, bytes.len() as u64)?;
+    std::fs::write(&target, &bytes)
+        .map_err(|e| AppError::Io(format!("Could not write {}: {}", target.display(), e)))?;
+    Ok(target.to_string_lossy().to_string())
+}
+
+/// Image files in the auto-save folder with their size and modification time.
+/// `sort_by` is "modified" (newest first, the default), "name" or "size" (largest first).
+#[tauri::command]
+async fn list_saved_captures(app: AppHandle, sort_by: Option<String>) -> Result<Vec<autosave::SavedCapture>, AppError> {
+    let sort = autosave::SortBy::parse(sort_by.as_deref())?;
+    let config = Config::load(&app);
+    tauri::async_runtime::spawn_blocking(move || autosave::list_saved(&config, sort))
+        .await
+        .unwrap_or_else(|e| Err(AppError::Io(e.to_string())))
+}
+
+/// Delete a file listed by `list_saved_captures`. Refuses anything outside the
+/// auto-save folder.
+#[tauri::command]
+fn delete_saved_capture(app: AppHandle, path: String) -> Result<(), AppError> {
+    autosave::delete_saved(&Config::load(&app), std::path::Path::new(path.trim()))
+}
+
+/// Bytes free for the current user on the volume holding `path` (default: the
+/// auto-save folder), so the UI can warn before saves start failing with
+/// `insufficient_space`. Relative paths are taken from the captures folder,
+/// like `save_capture`; the path doesn't need to exist yet.
+#[tauri::command]
+fn get_free_space(app: AppHandle, path: Option<String>) -> Result<u64, AppError> {
+    let target = match path.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        Some(path) if std::path::Path::new(path).is_absolute() => std::path::PathBuf::from(path),
+        Some(path) => get_screenai_captures_dir().join(path),
+        None => autosave::output_dir(&Config::load(&app)),
+    };
+    disk::free_space(&target)
+}
+
+/// Capture a display and write it straight to `path` — no data URL crosses the
+/// IPC bridge, which matters for 4K/5K captures. `format` defaults to the
+/// file extension. Same path rules as `save_capture`. Not added to the history.
+#[tauri::command]
+async fn capture_screen_to_file(
+    app: AppHandle,
+    path: String,
+    format: Option<String>,
+    quality: Option<u8>,
+    monitor_index: Option<usize>,
+    include_cursor: Option<bool>,
+    overwrite: Option<bool>,
+) -> Result<capture::SavedCapture, AppError> {
+    let target = checked_save_path(&path, overwrite.unwrap_or(false))?;
+    let format = match format {
+        Some(format) => OutputFormat::parse(Some(&format), quality)?,
+        None => {
+            let ext = OutputFormat::from_extension(&target)?;
+            OutputFormat::parse(Some(ext.extension()), quality)?
+        }
+    };
+    let config = Config::load(&app);
+    let options = CaptureOptions {
+        format,
+        include_cursor: include_cursor.unwrap_or(false),
+        limit: config.pixel_limit(),
+        auto_rotate: config.auto_rotate,
+        ..CaptureOptions::default()
+    };
+
+    ensure_display(&app)?;
+    let _busy = tray::busy(&app);
+    let _ = app.emit_all("capture-started", "fullscreen");
+    hide_main_window(&app).await;
+    let result = tauri::async_runtime::spawn_blocking(move || capture::capture_screen_to_file(&target, monitor_index, options))
+        .await
+        .unwrap_or_else(|e| Err(AppError::CaptureFailed(e.to_string())));
+    if let Some(window) = app.get_window("main") { let _ = window.show(); let _ = window.set_focus(); }
+    result
 }
 
-/// Clean up old ScreenAI captures (older than 24 hours)
+/// Clean up old ScreenAI captures (older than 24 hours) and raw capture files
+/// no preview loop has rewritten for a while
 #[tauri::command]
-fn cleanup_temp_captures() -> u32 {
+fn cleanup_temp_captures(app: AppHandle) -> u32 {
     let captures_dir = get_screenai_captures_dir();
-    let mut removed = 0u32;
+    let mut removed = app
+        .path_resolver()
+        .app_cache_dir()
+        .map_or(0, |dir| capture::remove_stale_raw_files(&dir, std::time::Duration::from_secs(10 * 60)));
     if let Ok(entries) = std::fs::read_dir(&captures_dir) {
         for entry in entries.flatten() {
             let name = entry.file_name().to_string_lossy().to_string();
@@ -658,9 +2657,9 @@ fn cleanup_temp_captures() -> u32 {
     removed
 }
 
-/// OCR: decode data URL to a temp PNG, run tesseract CLI, return text
+/// OCR: decode data URL to a temp PNG, run tesseract CLI (eng+fra), return text
 #[tauri::command]
-fn ocr_extract(image_data_url: String) -> Result<String, String> {
+fn ocr_extract(image_data_url: String) -> Result<String, AppError> {
     // Strip data URL prefix
     let b64 = image_data_url
         .find(",")
@@ -668,112 +2667,163 @@ fn ocr_extract(image_data_url: String) -> Result<String, String> {
         .unwrap_or(&image_data_url);
 
     let bytes = BASE64.decode(b64)
-        .map_err(|e| format!("Base64 decode error: {}", e))?;
-
-    let tmp_dir = std::env::temp_dir().join("screenai-ocr");
-    let _ = std::fs::create_dir_all(&tmp_dir);
-    let input_path = tmp_dir.join("ocr_input.png");
-    let output_path = tmp_dir.join("ocr_output");
-
-    std::fs::write(&input_path, &bytes)
-        .map_err(|e| format!("Write temp file error: {}", e))?;
-
-    // Run tesseract CLI
-    let result = std::process::Command::new("tesseract")
-        .arg(input_path.to_string_lossy().to_string())
-        .arg(output_path.to_string_lossy().to_string())
-        .arg("-l").arg("eng+fra")
-        .output();
-
-    match result {
-        Ok(output) => {
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                return Err(format!("Tesseract error: {}", stderr));
-            }
-            // tesseract writes to output_path.txt
-            let txt_path = tmp_dir.join("ocr_output.txt");
-            let text = std::fs::read_to_string(&txt_path)
-                .map_err(|e| format!("Read OCR result error: {}", e))?;
-            // Cleanup
-            let _ = std::fs::remove_file(&input_path);
-            let _ = std::fs::remove_file(&txt_path);
-            Ok(text)
-        }
-        Err(_) => {
-            // tesseract CLI not installed — let JS handle it with tesseract.js
-            let _ = std::fs::remove_file(&input_path);
-            Err("tesseract_not_found".to_string())
-        }
-    }
+        .map_err(|e| AppError::InvalidArgument(format!("Base64 decode error: {}", e)))?;
+
+    ocr::recognize(&bytes, "eng+fra")
 }
 
-/// List visible windows with their screen positions (for window capture mode)
+/// OCR a capture in any supported format. `lang` takes Tesseract codes
+/// ("eng", "deu", "eng+fra", ...) and defaults to "eng".
+/// Returns an empty string when no text is detected; fails with "tesseract_not_found"
+/// when the tesseract CLI is missing and "ocr_failed" when it errors.
 #[tauri::command]
-fn list_windows() -> Vec<serde_json::Value> {
-    let mut windows = Vec::new();
-
-    #[cfg(target_os = "windows")]
-    {
-        use std::ffi::OsString;
-        use std::os::windows::ffi::OsStringExt;
-
-        #[link(name = "user32")]
-        extern "system" {
-            fn EnumWindows(lpEnumFunc: unsafe extern "system" fn(isize, isize) -> i32, lParam: isize) -> i32;
-            fn IsWindowVisible(hWnd: isize) -> i32;
-            fn GetWindowTextW(hWnd: isize, lpString: *mut u16, nMaxCount: i32) -> i32;
-            fn GetWindowRect(hWnd: isize, lpRect: *mut [i32; 4]) -> i32;
-            fn GetWindowLongW(hWnd: isize, nIndex: i32) -> i32;
-        }
-
-        static mut WIN_LIST: Vec<serde_json::Value> = Vec::new();
+fn ocr_capture(data_url: String, lang: Option<String>) -> Result<String, AppError> {
+    ocr::recognize_data_url(&data_url, ocr::lang_or_default(lang.as_deref()))
+}
 
-        unsafe extern "system" fn enum_cb(hwnd: isize, _: isize) -> i32 {
-            if IsWindowVisible(hwnd) == 0 { return 1; }
+/// Bounding boxes of the text in a capture, in image pixels, for suggesting
+/// what to redact. Words by default; `lines` merges each line's words into one box.
+/// Empty when no text is found. `lang` as for `ocr_capture`.
+#[tauri::command]
+async fn detect_text_regions(
+    data_url: String,
+    lang: Option<String>,
+    lines: Option<bool>,
+) -> Result<Vec<ocr::TextRegion>, AppError> {
+    let lang = ocr::lang_or_default(lang.as_deref()).to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        let png = imaging::encode(&imaging::decode_data_url(&data_url)?, OutputFormat::Png)?;
+        ocr::text_regions(&png, &lang, lines.unwrap_or(false))
+    })
+    .await
+    .unwrap_or_else(|e| Err(AppError::OcrFailed(e.to_string())))
+}
 
-            // Skip tool windows, popups, etc.
-            let ex_style = GetWindowLongW(hwnd, -20); // GWL_EXSTYLE
-            if ex_style & 0x00000080 != 0 { return 1; } // WS_EX_TOOLWINDOW
+/// Tesseract language codes that are installed, for a language picker.
+/// Empty (not an error) when tesseract or its language data is missing.
+#[tauri::command]
+async fn list_ocr_languages() -> Vec<String> {
+    tauri::async_runtime::spawn_blocking(ocr::languages).await.unwrap_or_default()
+}
 
-            let mut title_buf = [0u16; 256];
-            let len = GetWindowTextW(hwnd, title_buf.as_mut_ptr(), 256);
-            if len <= 0 { return 1; }
-            let title = OsString::from_wide(&title_buf[..len as usize]).to_string_lossy().to_string();
-            if title.is_empty() { return 1; }
+/// Capture and OCR in one call, for "screenshot to text". `mode` is
+/// "fullscreen" (display `monitor_index`, default primary) or "region": `rect`
+/// in logical pixels, or the native interactive selection on macOS when omitted.
+/// `lang` as for `ocr_capture`. Fails with code "tesseract_not_found" when
+/// the tesseract CLI is missing.
+#[tauri::command]
+async fn capture_and_ocr(
+    app: AppHandle,
+    mode: String,
+    lang: Option<String>,
+    monitor_index: Option<usize>,
+    rect: Option<capture::Rect>,
+) -> Result<ocr::OcrCapture, AppError> {
+    let lang = ocr::lang_or_default(lang.as_deref()).to_string();
+    if !ocr::valid_lang(&lang) {
+        return Err(AppError::InvalidArgument(format!("Invalid OCR language: {}", lang)));
+    }
+    let options = CaptureOptions { format: OutputFormat::Png, ..CaptureOptions::default() };
+    let capture = match (mode.as_str(), rect) {
+        ("fullscreen", _) => run_capture(&app, "fullscreen", move || native_capture("fullscreen", monitor_index, options)).await?,
+        ("region", Some(rect)) => {
+            let scale = selection_scale(&app, None)?;
+            run_capture(&app, "region", move || capture::capture_rect("region", rect, scale, options)).await?
+        }
+        ("region", None) => run_capture(&app, "region", move || native_capture("region", None, options)).await?,
+        (other, _) => {
+            return Err(AppError::InvalidArgument(format!("Unknown mode \"{}\" (expected fullscreen or region)", other)))
+        }
+    };
+    tauri::async_runtime::spawn_blocking(move || ocr::recognize_capture(capture, &lang))
+        .await
+        .unwrap_or_else(|e| Err(AppError::OcrFailed(e.to_string())))
+}
 
-            let mut rect = [0i32; 4];
-            GetWindowRect(hwnd, &mut rect);
-            let x = rect[0]; let y = rect[1];
-            let w = rect[2] - rect[0]; let h = rect[3] - rect[1];
-            if w <= 0 || h <= 0 { return 1; }
+/// List visible top-level windows with their id and screen bounds (for window capture mode)
+#[tauri::command]
+fn list_windows() -> Vec<window_list::WindowInfo> {
+    window_list::list()
+}
 
-            WIN_LIST.push(serde_json::json!({
-                "title": title,
-                "x": x, "y": y, "w": w, "h": h,
-            }));
-            1
+/// Longest the frontend gets to answer "before-quit" with "before-quit-done"
+const QUIT_ACK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Orderly teardown before quitting. With `ask`, the frontend is warned with
+/// "before-quit" and may answer "before-quit-done" with `{ "cancel": true }`
+/// to keep running (unsaved input); no answer within `QUIT_ACK_TIMEOUT` counts
+/// as consent. Then finishes a running GIF recording, stops the replay buffer
+/// and any delayed capture, stops an update download, lets a history write
+/// finish, releases the global shortcuts and flushes the log. Returns false
+/// when the quit was cancelled. Blocks, so it must not run on the event loop
+/// thread the answer arrives on.
+fn shutdown(app: &AppHandle, ask: bool) -> bool {
+    if ask {
+        let (ack_tx, ack_rx) = std::sync::mpsc::channel();
+        app.once_global("before-quit-done", move |event| {
+            let cancel = event
+                .payload()
+                .and_then(|p| serde_json::from_str::<serde_json::Value>(p).ok())
+                .and_then(|v| v.get("cancel")?.as_bool())
+                .unwrap_or(false);
+            let _ = ack_tx.send(cancel);
+        });
+        let _ = app.emit_all("before-quit", ());
+        match ack_rx.recv_timeout(QUIT_ACK_TIMEOUT) {
+            Ok(true) => {
+                log::info!("Quit cancelled by the frontend");
+                if let Some(window) = app.get_window("main") {
+                    let _ = window.unminimize();
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
+                return false;
+            }
+            Ok(false) => {}
+            Err(_) => log::warn!("Frontend did not answer before-quit; quitting anyway"),
         }
+    }
 
-        unsafe {
-            WIN_LIST.clear();
-            EnumWindows(enum_cb, 0);
-            windows = WIN_LIST.clone();
-        }
+    log::info!("Shutting down");
+    DELAYED_CAPTURE_GEN.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    if let Ok(result) = recording::stop() {
+        let _ = app.emit_all("recording-stopped", &result);
+    }
+    let _ = replay::stop();
+    updater::cancel();
+    history::flush();
+    if let Err(e) = app.global_shortcut_manager().unregister_all() {
+        log::warn!("Could not unregister shortcuts: {}", e);
     }
+    log::logger().flush();
+    true
+}
+
+/// Quit without asking again, after the frontend cancelled a tray Quit and
+/// the user confirmed
+#[tauri::command]
+fn quit_app(app: AppHandle) {
+    std::thread::spawn(move || {
+        shutdown(&app, false);
+        app.exit(0);
+    });
+}
 
-    windows
+/// Other apps' windows, front-most first, for snapping the capture overlay to
+/// the window under the cursor. Bounds are the same screen coordinates
+/// `capture_window` uses. ScreenAI's own windows are left out.
+#[tauri::command]
+fn get_window_rects() -> Vec<window_list::WindowInfo> {
+    window_list::others()
 }
 
 fn main() {
-    let tray_menu = SystemTrayMenu::new()
-        .add_item(CustomMenuItem::new("capture", "Capture (Alt+Shift+S)"))
-        .add_item(CustomMenuItem::new("capture_region", "Region (Alt+Shift+A)"))
-        .add_native_item(SystemTrayMenuItem::Separator)
-        .add_item(CustomMenuItem::new("show", "Open ScreenAI"))
-        .add_item(CustomMenuItem::new("quit", "Quit"));
+    // `--capture …` runs headless: no tray, no webview
+    if let Some(code) = cli::run_from_args() {
+        std::process::exit(code);
+    }
 
-    let system_tray = SystemTray::new().with_menu(tray_menu);
+    let system_tray = SystemTray::new().with_menu(tray::menu());
 
     tauri::Builder::default()
         .system_tray(system_tray)
@@ -789,13 +2839,42 @@ fn main() {
                             let _ = window.set_focus();
                         }
                     }
-                    "quit" => std::process::exit(0),
-                    _ => {}
+                    "quit" => {
+                        let app = app.clone();
+                        std::thread::spawn(move || {
+                            if shutdown(&app, true) {
+                                app.exit(0);
+                            }
+                        });
+                    }
+                    tray::LAUNCH_AT_LOGIN => {
+                        let enabled = !autostart::is_enabled().unwrap_or(false);
+                        if let Err(e) = set_autostart(app.clone(), enabled) {
+                            log::warn!("{}", e);
+                            // Put the check mark back to what the OS has
+                            tray::set_launch_at_login(app, !enabled);
+                        }
+                    }
+                    id => {
+                        if tray::handle_monitor_item(app, id) {
+                            // A rebuilt menu starts with the default labels
+                            refresh_tray_labels(app, &Config::load(app));
+                        }
+                    }
                 },
                 SystemTrayEvent::LeftClick { .. } => {
-                    if let Some(window) = app.get_window("main") {
-                        let _ = window.show();
-                        let _ = window.set_focus();
+                    let action = tray::ClickAction::parse(&Config::load(app).tray_click_action)
+                        .unwrap_or(tray::ClickAction::Open);
+                    match action {
+                        tray::ClickAction::Capture => open_capture_overlay(app, "toolbar"),
+                        tray::ClickAction::Region => open_capture_overlay(app, "region"),
+                        tray::ClickAction::Open => {
+                            if let Some(window) = app.get_window("main") {
+                                let _ = window.show();
+                                let _ = window.set_focus();
+                            }
+                        }
+                        tray::ClickAction::None => {}
                     }
                 }
                 _ => {}
@@ -804,60 +2883,194 @@ fn main() {
         .setup(|app| {
             let handle = app.handle();
 
-            let handle_fs = handle.clone();
-            match app.global_shortcut_manager().register("Alt+Shift+S", move || {
-                show_capture_toolbar(&handle_fs);
-            }) {
-                Ok(_) => println!("   Alt+Shift+S → Show capture toolbar"),
-                Err(e) => eprintln!("Warning: could not register Alt+Shift+S (shortcut already taken?): {}", e),
+            logging::init(&handle);
+            let config = Config::load(&handle);
+            match logging::parse_level(&config.log_level) {
+                Ok(level) => logging::set_level(level),
+                Err(e) => log::warn!("{}; logging at {}", e, logging::DEFAULT_LEVEL),
             }
-
-            let handle_rg = handle.clone();
-            match app.global_shortcut_manager().register("Alt+Shift+A", move || {
-                open_capture_overlay(&handle_rg, "region");
-            }) {
-                Ok(_) => println!("   Alt+Shift+A → Capture region"),
-                Err(e) => eprintln!("Warning: could not register Alt+Shift+A (shortcut already taken?): {}", e),
+            capture::set_capture_timeout(config.capture_timeout_ms);
+            // Keys saved by older versions sit in config.json in plain text. Moved
+            // in the background: an unlock prompt must not hold up the launch.
+            let migrate_handle = handle.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                let mut config = Config::load(&migrate_handle);
+                if secrets::migrate(&mut config) {
+                    if let Err(e) = config.save(&migrate_handle) {
+                        log::warn!("Could not save the config after moving secrets: {}", e);
+                    }
+                }
+            });
+            // Register the configured shortcuts (defaults: Alt+Shift+S toolbar, Alt+Shift+A region)
+            // A refused binding is not fatal: the tray menu still works
+            let mut failures = Vec::new();
+            for (action, accelerator) in &config.shortcuts {
+                if accelerator.is_empty() { continue; }
+                match register_shortcut(&handle, action, accelerator) {
+                    Ok(_) => log::info!("Shortcut {} → {}", accelerator, action),
+                    Err(e) => {
+                        log::warn!("{} (shortcut already taken?)", e);
+                        failures.push(ShortcutFailure {
+                            action: action.clone(),
+                            accelerator: accelerator.clone(),
+                            message: e.to_string(),
+                        });
+                    }
+                }
+            }
+            refresh_tray_labels(&handle, &config);
+
+            // Report failures once the frontend is listening
+            if !failures.is_empty() {
+                let handle_ready = handle.clone();
+                handle.once_global("ready", move |_| {
+                    for failure in failures {
+                        let _ = handle_ready.emit_to("main", "shortcut-registration-failed", failure);
+                    }
+                });
             }
-
-            // Alt+Shift+W (window capture) removed — not implemented yet
 
             if let Some(window) = app.get_window("main") {
                 let _ = window.show();
                 let _ = window.set_focus();
             }
 
+            // Offer a rollback when the last update keeps dying right after launch
+            let update_status = updater::record_launch(&handle);
+            if update_status.failed {
+                let handle_ready = handle.clone();
+                handle.once_global("ready", move |_| {
+                    let _ = handle_ready.emit_to("main", "update-failed", update_status);
+                });
+            }
+
+            // Re-register at login so the entry follows the app after an update or a move
+            if config.launch_at_login {
+                if let Err(e) = autostart::set(true) {
+                    log::warn!("{}", e);
+                }
+            }
+
+            // Periodic background update checks on the saved channel
+            updater::spawn_periodic_checks(handle.clone());
+
             // Clean old temp captures at startup
-            let cleaned = cleanup_temp_captures();
-            if cleaned > 0 { println!("   Cleaned {} old temp capture(s)", cleaned); }
+            let cleaned = cleanup_temp_captures(handle.clone());
+            if cleaned > 0 { log::info!("Cleaned {} old temp capture(s)", cleaned); }
 
-            println!("🚀 ScreenAI running in system tray");
+            log::info!("ScreenAI {} running in system tray", app.package_info().version);
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             capture_screen,
             capture_region,
+            capture_region_pct,
+            capture_work_area,
+            capture_last_region,
+            capture_all_monitors,
+            capture_each_monitor,
+            capture_screen_excluding,
+            capture_screen_raw,
+            start_recording,
+            stop_recording,
+            start_replay_buffer,
+            save_replay_buffer,
+            stop_replay_buffer,
+            make_gif,
+            capture_scroll_start,
+            capture_scroll_frame,
+            capture_scroll_finish,
+            capture_screen_delayed,
+            cancel_delayed_capture,
+            list_monitors,
+            get_screen_info,
+            pick_color,
+            get_magnifier,
+            get_pixel_region,
+            measure_distance,
+            capture_window,
+            capture_active_window,
+            get_history,
+            get_history_item,
+            clear_history,
+            set_auto_save,
+            next_capture_filename,
             get_app_version,
+            get_diagnostics,
             check_for_updates,
+            get_cached_update_info,
             install_update,
+            get_update_status,
+            rollback_update,
+            cancel_update,
+            set_update_channel,
+            set_autostart,
+            get_autostart,
+            set_secret,
+            get_secret,
+            get_config,
+            update_config,
+            get_log_path,
+            set_log_level,
             set_fullscreen,
             web_search,
             invoke_claude,
             call_claude_simple,
+            analyze_capture,
+            set_ai_settings,
             read_file_bytes,
             list_windows,
+            quit_app,
+            get_window_rects,
             save_temp_capture,
+            copy_image_to_clipboard,
+            capture_from_clipboard,
+            redact_regions,
+            blur_path,
+            crop_capture,
+            resize_capture,
+            transform_capture,
+            decorate_capture,
+            make_thumbnail,
+            diff_captures,
+            add_watermark,
+            burn_timestamp,
+            extract_palette,
+            upload_capture,
+            annotate,
+            reset_step_counter,
             reveal_in_explorer,
+            reveal_in_folder,
             write_file_bytes,
+            save_capture,
+            get_free_space,
+            list_saved_captures,
+            delete_saved_capture,
+            capture_screen_to_file,
             cleanup_temp_captures,
             update_shortcut,
+            set_shortcut,
             ocr_extract,
+            ocr_capture,
+            capture_and_ocr,
+            list_ocr_languages,
+            detect_text_regions,
             get_window_label,
+            pin_capture,
+            get_pinned_capture,
+            close_pin,
+            set_pin_always_on_top,
+            set_pin_opacity,
             get_pending_capture,
+            rebuild_tray,
             send_capture_to_main,
             close_capture_overlay,
+            commit_region,
+            cancel_capture,
             open_capture_overlay_cmd,
+            start_overlay_preview,
+            stop_overlay_preview,
             share_native
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,178 @@
+// ============================================
+// ScreenAI Desktop — Text recognition
+// ============================================
+//
+// Shells out to the tesseract CLI. When it is not installed the commands fail
+// with "tesseract_not_found" so the frontend can fall back to tesseract.js;
+// other failures are "ocr_failed", and a malformed language "invalid_argument".
+
+use crate::capture::CapturePayload;
+use crate::error::AppError;
+use crate::imaging::{self, OutputFormat};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub const DEFAULT_LANG: &str = "eng";
+
+/// Returned by `capture_and_ocr`
+#[derive(Clone, Serialize)]
+pub struct OcrCapture {
+    pub capture: CapturePayload,
+    pub text: String,
+}
+
+/// Unique suffix so concurrent OCR calls don't share temp files
+static OCR_RUN: AtomicU64 = AtomicU64::new(0);
+
+/// Tesseract language spec: codes like `eng`, `chi_sim`, joined with `+`
+pub fn valid_lang(lang: &str) -> bool {
+    !lang.is_empty()
+        && lang
+            .split('+')
+            .all(|code| !code.is_empty() && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+}
+
+/// One word or line found by `text_regions`, in image pixels
+#[derive(Clone, Serialize)]
+pub struct TextRegion {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+    pub text: String,
+    /// Tesseract's confidence, 0-100
+    pub confidence: f32,
+}
+
+/// Run tesseract on an encoded image and return the recognized text,
+/// trimmed — empty when nothing was detected.
+pub fn recognize(image_bytes: &[u8], lang: &str) -> Result<String, AppError> {
+    Ok(run_tesseract(image_bytes, lang, &[])?.trim().to_string())
+}
+
+/// Run tesseract with output to stdout; `config` names output configs such as "tsv"
+fn run_tesseract(image_bytes: &[u8], lang: &str, config: &[&str]) -> Result<String, AppError> {
+    if !valid_lang(lang) {
+        return Err(AppError::InvalidArgument(format!("Invalid OCR language: {}", lang)));
+    }
+
+    let tmp_dir = std::env::temp_dir().join("screenai-ocr");
+    let _ = std::fs::create_dir_all(&tmp_dir);
+    let run = OCR_RUN.fetch_add(1, Ordering::Relaxed);
+    let input_path = tmp_dir.join(format!("ocr_input_{}_{}.png", std::process::id(), run));
+
+    std::fs::write(&input_path, image_bytes)
+        .map_err(|e| AppError::OcrFailed(format!("Write temp file error: {}", e)))?;
+
+    // "stdout" as the output base makes tesseract print instead of writing a .txt
+    let result = std::process::Command::new("tesseract")
+        .arg(&input_path)
+        .arg("stdout")
+        .arg("-l").arg(lang)
+        .args(config)
+        .output();
+    let _ = std::fs::remove_file(&input_path);
+
+    match result {
+        Ok(output) if output.status.success() => {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        }
+        Ok(output) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(AppError::OcrFailed(format!("Tesseract error: {}", stderr.trim())))
+        }
+        Err(_) => Err(AppError::OcrUnavailable),
+    }
+}
+
+/// Installed Tesseract languages, sorted. Asks the CLI first and falls back to
+/// the `*.traineddata` files in `$TESSDATA_PREFIX`. Empty when tesseract or
+/// its data is missing. "osd" (orientation detection) is not a language and
+/// is left out.
+pub fn languages() -> Vec<String> {
+    let from_cli = std::process::Command::new("tesseract")
+        .arg("--list-langs")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| {
+            // Older versions print the list on stderr. The first line is a header
+            let text = if output.stdout.is_empty() { output.stderr } else { output.stdout };
+            String::from_utf8_lossy(&text).lines().skip(1).map(|l| l.trim().to_string()).collect::<Vec<_>>()
+        });
+    let mut langs = from_cli.unwrap_or_else(|| {
+        let Some(dir) = std::env::var_os("TESSDATA_PREFIX") else { return Vec::new() };
+        std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|e| e.file_name().to_str()?.strip_suffix(".traineddata").map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    });
+    langs.retain(|lang| lang != "osd" && valid_lang(lang));
+    langs.sort();
+    langs.dedup();
+    langs
+}
+
+/// Bounding boxes of the words (`lines` = false) or lines of text in an
+/// encoded image, from tesseract's layout analysis. Empty when there is no text.
+pub fn text_regions(image_bytes: &[u8], lang: &str, lines: bool) -> Result<Vec<TextRegion>, AppError> {
+    let tsv = run_tesseract(image_bytes, lang, &["tsv"])?;
+    // Columns: level page block par line word left top width height conf text
+    let rows = tsv.lines().skip(1).filter_map(|row| {
+        let cols: Vec<&str> = row.splitn(12, '\t').collect();
+        if cols.len() < 12 {
+            return None;
+        }
+        let num = |i: usize| cols[i].parse::<u32>().ok();
+        let key = (num(1)?, num(2)?, num(3)?, num(4)?);
+        Some((num(0)?, key, num(6)?, num(7)?, num(8)?, num(9)?, cols[10].parse::<f32>().ok()?, cols[11].trim()))
+    });
+
+    let mut regions: Vec<TextRegion> = Vec::new();
+    let mut current_line = None;
+    // Words (level 5) carry the text and confidence; lines are built from their words
+    // so lines of blank boxes that tesseract sometimes reports are skipped
+    for (level, line, x, y, w, h, confidence, text) in rows {
+        if level != 5 || text.is_empty() || confidence < 0.0 {
+            continue;
+        }
+        let word = TextRegion { x, y, w, h, text: text.to_string(), confidence };
+        match regions.last_mut() {
+            Some(last) if lines && current_line == Some(line) => {
+                let (right, bottom) = ((last.x + last.w).max(x + w), (last.y + last.h).max(y + h));
+                let words = last.text.split(' ').count() as f32;
+                last.confidence = (last.confidence * words + confidence) / (words + 1.0);
+                last.x = last.x.min(x);
+                last.y = last.y.min(y);
+                last.w = right - last.x;
+                last.h = bottom - last.y;
+                last.text.push(' ');
+                last.text.push_str(text);
+            }
+            _ => regions.push(word),
+        }
+        current_line = Some(line);
+    }
+    Ok(regions)
+}
+
+/// Decode a capture data URL, normalize it to PNG and run OCR on it
+pub fn recognize_data_url(data_url: &str, lang: &str) -> Result<String, AppError> {
+    let png = imaging::encode(&imaging::decode_data_url(data_url)?, OutputFormat::Png)?;
+    recognize(&png, lang)
+}
+
+/// `lang` if given and non-blank, else `DEFAULT_LANG`
+pub fn lang_or_default(lang: Option<&str>) -> &str {
+    lang.map(str::trim).filter(|l| !l.is_empty()).unwrap_or(DEFAULT_LANG)
+}
+
+/// OCR a fresh capture
+pub fn recognize_capture(capture: CapturePayload, lang: &str) -> Result<OcrCapture, AppError> {
+    let text = recognize_data_url(&capture.data_url, lang)?;
+    Ok(OcrCapture { capture, text })
+}
@@ -0,0 +1,120 @@
+// ============================================
+// ScreenAI Desktop — Dominant colours of a capture
+// ============================================
+//
+// Median cut over a histogram of the image: pixels are binned at 5 bits per
+// channel, the bin set is split along its widest channel at the weighted
+// median until there are enough boxes, and each box reports the average of
+// the pixels in it. Large captures are sampled down first; nearest-neighbour
+// sampling keeps flat UI colours exact instead of blending neighbours.
+
+use crate::error::AppError;
+use image::RgbaImage;
+
+pub const MAX_COLORS: usize = 32;
+/// Longest side of the image the histogram is built from
+const SAMPLE_SIZE: u32 = 256;
+/// Pixels more transparent than this are ignored
+const MIN_ALPHA: u8 = 128;
+
+/// One 5-bit-per-channel histogram bin
+#[derive(Clone, Copy)]
+struct Bin {
+    key: [u8; 3],
+    count: u64,
+    sum: [u64; 3],
+}
+
+fn histogram(image: &RgbaImage) -> Vec<Bin> {
+    let mut bins = vec![Bin { key: [0; 3], count: 0, sum: [0; 3] }; 1 << 15];
+    for pixel in image.pixels().filter(|p| p.0[3] >= MIN_ALPHA) {
+        let [r, g, b, _] = pixel.0;
+        let key = [r >> 3, g >> 3, b >> 3];
+        let bin = &mut bins[(key[0] as usize) << 10 | (key[1] as usize) << 5 | key[2] as usize];
+        bin.key = key;
+        bin.count += 1;
+        for (sum, value) in bin.sum.iter_mut().zip([r, g, b]) {
+            *sum += value as u64;
+        }
+    }
+    bins.retain(|bin| bin.count > 0);
+    bins
+}
+
+/// Widest channel of a box and how wide it is
+fn widest(bins: &[Bin]) -> (usize, u8) {
+    (0..3)
+        .map(|c| {
+            let min = bins.iter().map(|b| b.key[c]).min().unwrap_or(0);
+            let max = bins.iter().map(|b| b.key[c]).max().unwrap_or(0);
+            (c, max - min)
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap_or((0, 0))
+}
+
+/// Split `bins` at the weighted median of its widest channel
+fn split(mut bins: Vec<Bin>) -> (Vec<Bin>, Vec<Bin>) {
+    let (channel, _) = widest(&bins);
+    bins.sort_unstable_by_key(|b| b.key[channel]);
+    let half = bins.iter().map(|b| b.count).sum::<u64>() / 2;
+    let mut seen = 0;
+    let mut at = bins.len() - 1;
+    for (i, bin) in bins.iter().enumerate() {
+        seen += bin.count;
+        if seen >= half {
+            at = i + 1;
+            break;
+        }
+    }
+    // Both halves keep at least one bin
+    let at = at.clamp(1, bins.len() - 1);
+    let upper = bins.split_off(at);
+    (bins, upper)
+}
+
+/// The `count` most common colours of `image` as "#rrggbb", most frequent first.
+/// Fewer are returned when the image has fewer distinct colours.
+pub fn extract(image: &RgbaImage, count: usize) -> Result<Vec<String>, AppError> {
+    if !(1..=MAX_COLORS).contains(&count) {
+        return Err(AppError::InvalidArgument(format!("count must be between 1 and {}", MAX_COLORS)));
+    }
+    let (width, height) = image.dimensions();
+    let ratio = (SAMPLE_SIZE as f64 / width.max(height).max(1) as f64).min(1.0);
+    let sample = if ratio < 1.0 {
+        let w = ((width as f64 * ratio).round() as u32).max(1);
+        let h = ((height as f64 * ratio).round() as u32).max(1);
+        image::imageops::resize(image, w, h, image::imageops::FilterType::Nearest)
+    } else {
+        image.clone()
+    };
+
+    let bins = histogram(&sample);
+    if bins.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut boxes = vec![bins];
+    while boxes.len() < count {
+        // Split the box with the most pixels among those that can still be split
+        let Some(index) = (0..boxes.len())
+            .filter(|&i| boxes[i].len() > 1)
+            .max_by_key(|&i| boxes[i].iter().map(|b| b.count).sum::<u64>())
+        else {
+            break;
+        };
+        let (lower, upper) = split(boxes.swap_remove(index));
+        boxes.push(lower);
+        boxes.push(upper);
+    }
+
+    let mut colors: Vec<(u64, [u8; 3])> = boxes
+        .iter()
+        .map(|bins| {
+            let count: u64 = bins.iter().map(|b| b.count).sum();
+            let mean = |c: usize| (bins.iter().map(|b| b.sum[c]).sum::<u64>() as f64 / count as f64).round() as u8;
+            (count, [mean(0), mean(1), mean(2)])
+        })
+        .collect();
+    colors.sort_by_key(|&(count, _)| std::cmp::Reverse(count));
+    Ok(colors.into_iter().map(|(_, [r, g, b])| format!("#{:02x}{:02x}{:02x}", r, g, b)).collect())
+}
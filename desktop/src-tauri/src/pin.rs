@@ -0,0 +1,160 @@
+// ============================================
+// ScreenAI Desktop — Pinned captures
+// ============================================
+//
+// A pin is a borderless always-on-top window showing one capture. Each gets its
+// own "pin-N" label; the frontend fetches its image with `get_pinned_capture`.
+// A pin can drop behind other windows and be made see-through, for holding a
+// reference over a design. Tauri has no opacity setting, so that goes through
+// the native window: a layered window on Windows, NSWindow's alpha on macOS
+// and GTK's widget opacity on Linux (needs a compositor).
+
+use crate::error::AppError;
+use crate::imaging;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+pub const LABEL_PREFIX: &str = "pin-";
+/// Lowest opacity a pin can be set to, so it never vanishes altogether
+const MIN_OPACITY: f64 = 0.1;
+/// Longest wait for the main thread to apply a native window change
+const MAIN_THREAD_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+static NEXT_PIN: AtomicU64 = AtomicU64::new(1);
+/// Window label → data URL shown in that pin
+static PINS: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+fn with_pins<T>(f: impl FnOnce(&mut BTreeMap<String, String>) -> T) -> T {
+    f(&mut PINS.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Open a new pin window sized to the image. Returns its window label.
+pub fn open(app: &AppHandle, data_url: String) -> Result<String, AppError> {
+    let bytes = imaging::data_url_bytes(&data_url)?;
+    let (width, height) = imaging::dimensions(&bytes)
+        .ok_or_else(|| AppError::DecodeFailed("unrecognized image data".to_string()))?;
+
+    // Physical pixels → logical size, shrunk to fit the current monitor
+    let reference = app.get_window("main");
+    let scale = reference.as_ref().and_then(|w| w.scale_factor().ok()).unwrap_or(1.0);
+    let (max_w, max_h) = reference
+        .as_ref()
+        .and_then(|w| w.current_monitor().ok().flatten())
+        .map(|m| {
+            let size = m.size().to_logical::<f64>(m.scale_factor());
+            (size.width * 0.9, size.height * 0.9)
+        })
+        .unwrap_or((f64::MAX, f64::MAX));
+    let (mut w, mut h) = (width as f64 / scale, height as f64 / scale);
+    let fit = (max_w / w).min(max_h / h).min(1.0);
+    w = (w * fit).max(32.0);
+    h = (h * fit).max(32.0);
+
+    let label = format!("{}{}", LABEL_PREFIX, NEXT_PIN.fetch_add(1, Ordering::Relaxed));
+    with_pins(|pins| pins.insert(label.clone(), data_url));
+
+    let built = tauri::WindowBuilder::new(app, &label, tauri::WindowUrl::App("index.html".into()))
+        .title("ScreenAI Pin")
+        .inner_size(w, h)
+        .decorations(false)
+        .always_on_top(true)
+        .resizable(true)
+        .skip_taskbar(true)
+        .build();
+    if let Err(e) = built {
+        with_pins(|pins| pins.remove(&label));
+        return Err(AppError::Io(format!("Failed to create pin window: {}", e)));
+    }
+    Ok(label)
+}
+
+/// Image for the pin window with `label`
+pub fn image_for(label: &str) -> Result<String, AppError> {
+    with_pins(|pins| pins.get(label).cloned())
+        .ok_or_else(|| AppError::InvalidArgument(format!("No pinned capture for window {}", label)))
+}
+
+/// Close a pin window and drop its image
+pub fn close(window: &tauri::Window) {
+    with_pins(|pins| pins.remove(window.label()));
+    let _ = window.close();
+}
+
+/// The open pin window with `label`
+fn pin_window(app: &AppHandle, label: &str) -> Result<tauri::Window, AppError> {
+    let known = label.starts_with(LABEL_PREFIX) && with_pins(|pins| pins.contains_key(label));
+    known
+        .then(|| app.get_window(label))
+        .flatten()
+        .ok_or_else(|| AppError::InvalidArgument(format!("No pinned capture for window {}", label)))
+}
+
+/// Keep the pin above other windows, or let it drop behind them
+pub fn set_always_on_top(app: &AppHandle, label: &str, on_top: bool) -> Result<(), AppError> {
+    pin_window(app, label)?
+        .set_always_on_top(on_top)
+        .map_err(|e| AppError::Io(format!("Could not change the pin window: {}", e)))
+}
+
+/// Make the pin see-through: `opacity` is 0-1, raised to `MIN_OPACITY`.
+/// Waits for the main thread to apply it, so it must not be called from there.
+pub fn set_opacity(app: &AppHandle, label: &str, opacity: f64) -> Result<(), AppError> {
+    if !(0.0..=1.0).contains(&opacity) {
+        return Err(AppError::InvalidArgument("opacity must be between 0 and 1".to_string()));
+    }
+    let window = pin_window(app, label)?;
+    let opacity = opacity.max(MIN_OPACITY);
+    let target = window.clone();
+    let (tx, rx) = std::sync::mpsc::channel();
+    // Native window calls belong on the main thread
+    window
+        .run_on_main_thread(move || {
+            let _ = tx.send(apply_opacity(&target, opacity));
+        })
+        .map_err(|e| AppError::Io(format!("Could not change the pin window: {}", e)))?;
+    rx.recv_timeout(MAIN_THREAD_TIMEOUT)
+        .unwrap_or_else(|_| Err(AppError::Io("The pin window did not respond".to_string())))
+        .map_err(|e| AppError::Io(format!("Could not change the opacity of {}: {}", label, e)))
+}
+
+#[cfg(target_os = "windows")]
+fn apply_opacity(window: &tauri::Window, opacity: f64) -> Result<(), AppError> {
+    const GWL_EXSTYLE: i32 = -20;
+    const WS_EX_LAYERED: isize = 0x0008_0000;
+    const LWA_ALPHA: u32 = 0x2;
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetWindowLongPtrW(hwnd: isize, index: i32) -> isize;
+        fn SetWindowLongPtrW(hwnd: isize, index: i32, value: isize) -> isize;
+        fn SetLayeredWindowAttributes(hwnd: isize, key: u32, alpha: u8, flags: u32) -> i32;
+    }
+    let hwnd = window.hwnd().map_err(|e| AppError::Io(e.to_string()))?.0;
+    unsafe {
+        let style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+        if style & WS_EX_LAYERED == 0 {
+            SetWindowLongPtrW(hwnd, GWL_EXSTYLE, style | WS_EX_LAYERED);
+        }
+        if SetLayeredWindowAttributes(hwnd, 0, (opacity * 255.0).round() as u8, LWA_ALPHA) == 0 {
+            return Err(AppError::Io(std::io::Error::last_os_error().to_string()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn apply_opacity(window: &tauri::Window, opacity: f64) -> Result<(), AppError> {
+    use objc::runtime::Object;
+    use objc::{msg_send, sel, sel_impl};
+    let ns_window = window.ns_window().map_err(|e| AppError::Io(e.to_string()))? as *mut Object;
+    let () = unsafe { msg_send![ns_window, setAlphaValue: opacity] };
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn apply_opacity(window: &tauri::Window, opacity: f64) -> Result<(), AppError> {
+    use gtk::prelude::WidgetExt;
+    window.gtk_window().map_err(|e| AppError::Io(e.to_string()))?.set_opacity(opacity);
+    Ok(())
+}
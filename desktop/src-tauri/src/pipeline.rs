@@ -0,0 +1,122 @@
+// ============================================
+// ScreenAI Desktop — Post-capture actions
+// ============================================
+//
+// `postCaptureActions` in config.json is an ordered list of steps every
+// finished capture runs through in the background, e.g.
+//
+//     [{ "action": "copy_to_clipboard" },
+//      { "action": "save_to_disk" },
+//      { "action": "upload", "target": "imgur" },
+//      { "action": "show_notification" }]
+//
+// Each step reuses the command that does the same thing on request. A step
+// that fails is logged and reported, and the next one still runs.
+
+use crate::capture::CapturePayload;
+use crate::config::Config;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+/// One step of `postCaptureActions`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ActionSpec {
+    /// Like `copy_image_to_clipboard`
+    CopyToClipboard,
+    /// Like auto-save, with the auto-save name pattern. `dir` overrides the
+    /// auto-save folder (`None` = the same folder).
+    SaveToDisk {
+        #[serde(default)]
+        dir: Option<String>,
+    },
+    /// Like `upload_capture`: "imgur", "s3", "webdav" or "http"
+    Upload { target: String },
+    ShowNotification,
+    PlaySound,
+}
+
+impl ActionSpec {
+    fn name(&self) -> &'static str {
+        match self {
+            ActionSpec::CopyToClipboard => "copy_to_clipboard",
+            ActionSpec::SaveToDisk { .. } => "save_to_disk",
+            ActionSpec::Upload { .. } => "upload",
+            ActionSpec::ShowNotification => "show_notification",
+            ActionSpec::PlaySound => "play_sound",
+        }
+    }
+
+    /// Reject bad settings when the config is saved, not after the next capture
+    pub fn validate(&self) -> Result<(), AppError> {
+        if let ActionSpec::Upload { target } = self {
+            crate::upload::Target::parse(target)?;
+        }
+        Ok(())
+    }
+}
+
+/// Payload of "post-capture-action", sent after each step
+#[derive(Clone, Serialize)]
+struct ActionOutcome {
+    action: &'static str,
+    /// Saved path or uploaded link, when the step has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<AppError>,
+}
+
+fn run_action(app: &AppHandle, config: &Config, action: &ActionSpec, payload: &CapturePayload) -> Result<Option<String>, AppError> {
+    match action {
+        ActionSpec::CopyToClipboard => crate::copy_image_to_clipboard(payload.data_url.clone()).map(|()| None),
+        ActionSpec::SaveToDisk { dir } => {
+            let mut config = config.clone();
+            config.auto_save_enabled = true;
+            if let Some(dir) = dir.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+                config.auto_save_dir = Some(dir.to_string());
+            }
+            let path = crate::autosave::save(app, &config, payload)?;
+            Ok(path.map(|p| p.to_string_lossy().to_string()))
+        }
+        ActionSpec::Upload { target } => {
+            let target = crate::upload::Target::parse(target)?;
+            let mut config = config.clone();
+            crate::secrets::fill(&mut config);
+            let uploaded = crate::upload::upload(app, &config.upload_settings(), &payload.data_url, target)?;
+            Ok(Some(uploaded.url))
+        }
+        ActionSpec::ShowNotification => {
+            crate::feedback::signal(app, payload, false, true);
+            Ok(None)
+        }
+        ActionSpec::PlaySound => {
+            crate::feedback::signal(app, payload, true, false);
+            Ok(None)
+        }
+    }
+}
+
+/// Run the configured actions for `payload` in order, in the background.
+/// Emits "post-capture-action" ({ action, result?, error? }) after each one.
+pub fn run_in_background(app: &AppHandle, payload: &CapturePayload) {
+    let config = Config::load(app);
+    if config.post_capture_actions.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    let payload = payload.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        for action in &config.post_capture_actions {
+            let outcome = match run_action(&app, &config, action, &payload) {
+                Ok(result) => ActionOutcome { action: action.name(), result, error: None },
+                Err(e) => {
+                    log::warn!("Post-capture action {} failed: {}", action.name(), e);
+                    ActionOutcome { action: action.name(), result: None, error: Some(e) }
+                }
+            };
+            let _ = app.emit_all("post-capture-action", outcome);
+        }
+    });
+}
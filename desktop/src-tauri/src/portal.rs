@@ -0,0 +1,102 @@
+// ============================================
+// ScreenAI Desktop — Wayland capture through xdg-desktop-portal
+// ============================================
+//
+// Wayland compositors don't let clients read the screen directly. The
+// Screenshot portal asks the compositor (possibly showing a permission
+// dialog) and answers with a file URI on a Request object's Response signal.
+
+use crate::error::AppError;
+use dbus::arg::{PropMap, RefArg, Variant};
+use dbus::blocking::Connection;
+use dbus::message::MatchRule;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Long enough for the user to answer a permission dialog
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(120);
+
+static NEXT_TOKEN: AtomicU64 = AtomicU64::new(1);
+
+/// True when running in a Wayland session, where X11 grabbing is blocked
+pub fn is_wayland() -> bool {
+    std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t.eq_ignore_ascii_case("wayland"))
+        || std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+/// Take a screenshot of the whole desktop (all monitors) through the portal
+pub fn screenshot() -> Result<image::RgbaImage, AppError> {
+    let failed = |e: dbus::Error| AppError::CaptureFailed(format!("screenshot portal: {}", e));
+    let conn = Connection::new_session().map_err(failed)?;
+
+    // The portal derives the Request path from our bus name and this token, so
+    // the Response handler is registered before the call to avoid missing it
+    let token = format!("screenai{}", NEXT_TOKEN.fetch_add(1, Ordering::Relaxed));
+    let sender = conn.unique_name().trim_start_matches(':').replace('.', "_");
+    let request_path = format!("/org/freedesktop/portal/desktop/request/{}/{}", sender, token);
+
+    let (tx, rx) = mpsc::channel::<(u32, PropMap)>();
+    let rule = MatchRule::new_signal("org.freedesktop.portal.Request", "Response")
+        .with_path(dbus::Path::from(request_path));
+    conn.add_match(rule, move |response: (u32, PropMap), _, _| {
+        let _ = tx.send(response);
+        false
+    })
+    .map_err(failed)?;
+
+    let mut options = PropMap::new();
+    options.insert("handle_token".to_string(), Variant(Box::new(token) as Box<dyn RefArg>));
+    options.insert("interactive".to_string(), Variant(Box::new(false) as Box<dyn RefArg>));
+    let proxy = conn.with_proxy("org.freedesktop.portal.Desktop", "/org/freedesktop/portal/desktop", Duration::from_secs(10));
+    let _: (dbus::Path,) = proxy
+        .method_call("org.freedesktop.portal.Screenshot", "Screenshot", ("", options))
+        .map_err(failed)?;
+
+    let deadline = Instant::now() + RESPONSE_TIMEOUT;
+    let (code, results) = loop {
+        if let Ok(response) = rx.try_recv() {
+            break response;
+        }
+        if Instant::now() >= deadline {
+            return Err(AppError::CaptureFailed("screenshot portal did not respond".to_string()));
+        }
+        conn.process(Duration::from_millis(100)).map_err(failed)?;
+    };
+
+    // 0 = success, 1 = the user declined, 2 = anything else
+    match code {
+        0 => {}
+        1 => return Err(AppError::PermissionDenied("screen capture was declined".to_string())),
+        _ => return Err(AppError::CaptureFailed("screenshot portal refused the request".to_string())),
+    }
+    let uri = results
+        .get("uri")
+        .and_then(|v| v.0.as_str())
+        .ok_or_else(|| AppError::CaptureFailed("screenshot portal returned no image".to_string()))?;
+    let path = uri.strip_prefix("file://").unwrap_or(uri);
+    let path = percent_decode(path);
+
+    let image = image::open(&path).map_err(|e| AppError::DecodeFailed(format!("{}: {}", path, e)))?;
+    Ok(image.to_rgba8())
+}
+
+/// Undo %XX escapes in a file URI path
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
@@ -0,0 +1,136 @@
+// ============================================
+// ScreenAI Desktop — Live backdrop for the region overlay
+// ============================================
+//
+// The region overlay normally shows one frozen screenshot. With a live
+// preview it gets fresh grabs of the screen under it as
+// "overlay-preview-frame" events, a few per second, until the selection is
+// committed or cancelled; `commit_region` then captures the area at full
+// quality as usual. The overlay is kept out of screen grabs (display affinity
+// on Windows, the window sharing type on macOS) so it doesn't appear in its
+// own frames. Linux has no such switch, so the overlay stays frozen there.
+
+use crate::capture::{self, CaptureOptions, Rect};
+use crate::error::AppError;
+use crate::imaging::{self, JpegSubsampling, OutputFormat};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::Window;
+
+pub const DEFAULT_FPS: u32 = 5;
+pub const MAX_FPS: u32 = 15;
+/// Preview frames are shown once and dropped, so they go as cheap JPEGs
+const FRAME_QUALITY: u8 = 70;
+
+/// Stop flag of each overlay's running stream, by window label
+static STREAMS: Mutex<BTreeMap<String, Arc<AtomicBool>>> = Mutex::new(BTreeMap::new());
+
+/// Stream the screen under `window` to it at `fps`. Replaces a stream the
+/// same window already had. Emits "overlay-preview-error" and stops if a grab fails.
+pub fn start(window: &Window, fps: u32) -> Result<(), AppError> {
+    if !(1..=MAX_FPS).contains(&fps) {
+        return Err(AppError::InvalidArgument(format!("fps must be between 1 and {}", MAX_FPS)));
+    }
+    exclude_from_capture(window)?;
+
+    let scale = window.scale_factor().unwrap_or(1.0);
+    let failed = |e: tauri::Error| AppError::CaptureFailed(e.to_string());
+    let position = window.outer_position().map_err(failed)?.to_logical::<i32>(scale);
+    let size = window.outer_size().map_err(failed)?.to_logical::<u32>(scale);
+    let rect = Rect { x: position.x, y: position.y, w: size.width, h: size.height };
+
+    let stop = Arc::new(AtomicBool::new(false));
+    if let Some(previous) = STREAMS.lock().unwrap_or_else(|e| e.into_inner()).insert(window.label().to_string(), stop.clone()) {
+        previous.store(true, Ordering::SeqCst);
+    }
+    let window = window.clone();
+    std::thread::spawn(move || {
+        let interval = Duration::from_secs_f64(1.0 / fps as f64);
+        let mut next = Instant::now();
+        loop {
+            if let Some(wait) = next.checked_duration_since(Instant::now()) {
+                std::thread::sleep(wait);
+            }
+            // Checked after the wait, so a commit never receives another frame
+            if stop.load(Ordering::SeqCst) {
+                break;
+            }
+            next = (next + interval).max(Instant::now());
+            let frame = capture::grab_rect(rect, scale, CaptureOptions::default())
+                .and_then(|(image, _, _)| imaging::encode_jpeg(&image, FRAME_QUALITY, JpegSubsampling::S420));
+            match frame {
+                Ok(bytes) => {
+                    let _ = window.emit("overlay-preview-frame", imaging::to_data_url(&bytes, OutputFormat::Jpeg(FRAME_QUALITY)));
+                }
+                Err(e) => {
+                    log::warn!("Overlay preview stopped: {}", e);
+                    let _ = window.emit("overlay-preview-error", e.to_string());
+                    break;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// End every overlay's stream at once (commit, cancel, overlay closed)
+pub fn stop() {
+    for (_, stop) in std::mem::take(&mut *STREAMS.lock().unwrap_or_else(|e| e.into_inner())) {
+        stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Run a native window call on the main thread, which owns the window, and wait for it
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn on_main_thread(window: &Window, f: impl FnOnce(&Window) -> Result<(), AppError> + Send + 'static) -> Result<(), AppError> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let target = window.clone();
+    window
+        .run_on_main_thread(move || {
+            let _ = tx.send(f(&target));
+        })
+        .map_err(|e| AppError::CaptureFailed(e.to_string()))?;
+    rx.recv_timeout(Duration::from_secs(2))
+        .unwrap_or_else(|_| Err(AppError::CaptureFailed("The overlay window did not respond".to_string())))
+}
+
+#[cfg(target_os = "windows")]
+fn exclude_from_capture(window: &Window) -> Result<(), AppError> {
+    const WDA_EXCLUDEFROMCAPTURE: u32 = 0x11;
+    #[link(name = "user32")]
+    extern "system" {
+        fn SetWindowDisplayAffinity(hwnd: isize, affinity: u32) -> i32;
+    }
+    on_main_thread(window, |window| {
+        let hwnd = window.hwnd().map_err(|e| AppError::CaptureFailed(e.to_string()))?.0;
+        // Needs Windows 10 2004; older versions only black the window out, which is no use here
+        if unsafe { SetWindowDisplayAffinity(hwnd, WDA_EXCLUDEFROMCAPTURE) } == 0 {
+            return Err(AppError::CaptureFailed(format!(
+                "Could not exclude the overlay from capture: {}",
+                std::io::Error::last_os_error()
+            )));
+        }
+        Ok(())
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn exclude_from_capture(window: &Window) -> Result<(), AppError> {
+    use objc::runtime::Object;
+    use objc::{msg_send, sel, sel_impl};
+    const NS_WINDOW_SHARING_NONE: usize = 0;
+    on_main_thread(window, |window| {
+        let ns_window = window.ns_window().map_err(|e| AppError::CaptureFailed(e.to_string()))? as *mut Object;
+        let () = unsafe { msg_send![ns_window, setSharingType: NS_WINDOW_SHARING_NONE] };
+        Ok(())
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn exclude_from_capture(_window: &Window) -> Result<(), AppError> {
+    Err(AppError::CaptureFailed(
+        "Live preview is not available on Linux: the overlay can't be kept out of its own frames".to_string(),
+    ))
+}
@@ -0,0 +1,313 @@
+// ============================================
+// ScreenAI Desktop — Screen recording to animated GIF
+// ============================================
+//
+// Two threads: one grabs frames at the requested rate with the normal capture
+// path, the other quantizes and appends them to the GIF. They are joined by a
+// bounded channel; when encoding falls behind, frames are dropped rather than
+// queued, and each frame's delay is taken from the capture timestamps so
+// playback speed stays right either way. A recording ends by itself after
+// `MAX_DURATION` or once the GIF reaches `MAX_FILE_BYTES`, so one left running
+// can't fill the disk.
+//
+// `make_gif` reuses the encoder helpers for animations built from captures
+// the user already took.
+
+use crate::capture::{self, CaptureOptions, Rect};
+use crate::error::AppError;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+pub const MAX_FPS: u32 = 30;
+/// Frames wider than this are scaled down — GIF quantization is the bottleneck
+const MAX_FRAME_WIDTH: u32 = 1280;
+/// NeuQuant sampling factor (1 = best, 30 = fastest)
+const QUANTIZE_SPEED: i32 = 10;
+const FILE_PATTERN: &str = "ScreenAI_recording_%Y%m%d_%H%M%S.gif";
+const ANIMATION_PATTERN: &str = "ScreenAI_animation_%Y%m%d_%H%M%S.gif";
+/// Frames buffered between the capture and encoder threads
+const QUEUE_FRAMES: usize = 8;
+/// Longest recording; it is finished and saved when this is reached
+const MAX_DURATION: Duration = Duration::from_secs(10 * 60);
+/// Largest GIF a recording writes before it is finished and saved
+const MAX_FILE_BYTES: u64 = 512 * 1024 * 1024;
+
+struct Recording {
+    stop: Arc<AtomicBool>,
+    capture: JoinHandle<()>,
+    encoder: JoinHandle<Result<RecordingResult, AppError>>,
+    _busy: crate::tray::BusyGuard,
+}
+
+static RECORDING: Mutex<Option<Recording>> = Mutex::new(None);
+
+/// Payload of "recording-progress", sent after every captured frame
+#[derive(Clone, Serialize)]
+struct RecordingProgress {
+    frames: u32,
+    dropped: u32,
+    elapsed_ms: u64,
+}
+
+/// Returned by `stop_recording`
+#[derive(Clone, Serialize)]
+pub struct RecordingResult {
+    pub path: String,
+    pub frames: u32,
+    pub width: u32,
+    pub height: u32,
+    pub duration_ms: u64,
+}
+
+/// Scale a frame down to `MAX_FRAME_WIDTH` if needed
+fn fit_frame(image: RgbaImage) -> RgbaImage {
+    if image.width() <= MAX_FRAME_WIDTH {
+        return image;
+    }
+    let height = ((image.height() as f64 * MAX_FRAME_WIDTH as f64 / image.width() as f64).round() as u32).max(1);
+    image::imageops::resize(&image, MAX_FRAME_WIDTH, height, image::imageops::FilterType::Triangle)
+}
+
+/// Open `path` for a GIF that loops forever or plays once
+pub fn gif_encoder(path: &Path, loop_forever: bool, speed: i32) -> Result<GifEncoder<std::io::BufWriter<std::fs::File>>, AppError> {
+    let file = std::fs::File::create(path).map_err(|e| AppError::Io(format!("{}: {}", path.display(), e)))?;
+    let mut encoder = GifEncoder::new_with_speed(std::io::BufWriter::new(file), speed);
+    encoder
+        .set_repeat(if loop_forever { Repeat::Infinite } else { Repeat::Finite(0) })
+        .map_err(|e| AppError::EncodeFailed(e.to_string()))?;
+    Ok(encoder)
+}
+
+/// Append one frame shown for `delay_ms`
+pub fn push_frame<W: std::io::Write>(encoder: &mut GifEncoder<W>, image: RgbaImage, delay_ms: u32) -> Result<(), AppError> {
+    // GIF delays are in hundredths of a second; anything under 20 ms is played slowly by browsers
+    let delay = Delay::from_numer_denom_ms(delay_ms.max(20), 1);
+    encoder
+        .encode_frame(Frame::from_parts(image, 0, 0, delay))
+        .map_err(|e| AppError::EncodeFailed(e.to_string()))
+}
+
+/// Timestamped file name from `pattern` in the captures folder
+fn output_path(pattern: &str) -> PathBuf {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    crate::get_screenai_captures_dir().join(crate::autosave::format_pattern(pattern, now))
+}
+
+/// One frame of `area` (`None` = the primary display), scaled to `MAX_FRAME_WIDTH`
+pub fn grab(area: Option<(Rect, f64)>) -> Result<RgbaImage, AppError> {
+    let options = CaptureOptions::default();
+    let image = match area {
+        Some((rect, scale)) => capture::grab_rect(rect, scale, options)?.0,
+        None => capture::grab_screen(None, options)?.0,
+    };
+    Ok(fit_frame(image))
+}
+
+/// Start recording `area` (global logical coordinates and their DPI scale;
+/// `None` = the primary display) at `fps` into a GIF in the captures folder.
+/// Emits "recording-progress" per frame and "recording-error" if a frame
+/// can't be captured or encoded, which also ends the recording; so do
+/// `MAX_DURATION` and `MAX_FILE_BYTES` ("recording-limit"). A recording that
+/// ends by itself emits "recording-stopped" with what `stop` would return.
+pub fn start(app: &AppHandle, fps: u32, area: Option<(Rect, f64)>) -> Result<(), AppError> {
+    if !(1..=MAX_FPS).contains(&fps) {
+        return Err(AppError::InvalidArgument(format!("fps must be between 1 and {}", MAX_FPS)));
+    }
+    let mut slot = RECORDING.lock().unwrap_or_else(|e| e.into_inner());
+    if slot.is_some() {
+        return Err(AppError::InvalidArgument("A recording is already running".to_string()));
+    }
+
+    // First frame up front: fixes the size and surfaces capture errors to the caller
+    let first = grab(area)?;
+    let (width, height) = first.dimensions();
+    let path = output_path(FILE_PATTERN);
+    let mut encoder = gif_encoder(&path, true, QUANTIZE_SPEED)?;
+    log::info!("Recording {}x{} at {} fps to {}", width, height, fps, path.display());
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = mpsc::sync_channel::<(RgbaImage, Instant)>(QUEUE_FRAMES);
+    let started = Instant::now();
+    let _ = tx.try_send((first, started));
+
+    let capture = {
+        let (app, stop) = (app.clone(), stop.clone());
+        std::thread::spawn(move || {
+            let interval = Duration::from_secs_f64(1.0 / fps as f64);
+            let (mut frames, mut dropped) = (1u32, 0u32);
+            let mut next = started + interval;
+            while !stop.load(Ordering::SeqCst) {
+                if let Some(wait) = next.checked_duration_since(Instant::now()) {
+                    std::thread::sleep(wait);
+                }
+                next += interval;
+                if started.elapsed() >= MAX_DURATION {
+                    let _ = app.emit_all("recording-limit", format!("Recordings stop after {} minutes", MAX_DURATION.as_secs() / 60));
+                    break;
+                }
+                let image = match grab(area) {
+                    // A resized region (display change) can't go in the same GIF
+                    Ok(image) if image.dimensions() == (width, height) => image,
+                    Ok(_) => {
+                        let _ = app.emit_all("recording-error", "Capture area changed size");
+                        break;
+                    }
+                    Err(e) => {
+                        let _ = app.emit_all("recording-error", e.to_string());
+                        break;
+                    }
+                };
+                match tx.try_send((image, Instant::now())) {
+                    Ok(()) => frames += 1,
+                    Err(mpsc::TrySendError::Full(_)) => dropped += 1,
+                    Err(mpsc::TrySendError::Disconnected(_)) => break,
+                }
+                let elapsed_ms = started.elapsed().as_millis() as u64;
+                let _ = app.emit_all("recording-progress", RecordingProgress { frames, dropped, elapsed_ms });
+            }
+            // Lets the encoder finish the file
+            drop(tx);
+            finish_unattended(&app, &stop);
+        })
+    };
+
+    let encoder = {
+        let (app, stop) = (app.clone(), stop.clone());
+        std::thread::spawn(move || {
+            let mut frames = 0u32;
+            let mut previous: Option<(RgbaImage, Instant)> = None;
+            let mut last_time = started;
+            // Each frame is written once the next one arrives, so its delay is known
+            for (image, at) in rx {
+                if let Some((frame, shown)) = previous.take() {
+                    let delay = at.duration_since(shown).as_millis() as u32;
+                    if let Err(e) = push_frame(&mut encoder, frame, delay) {
+                        stop.store(true, Ordering::SeqCst);
+                        let _ = app.emit_all("recording-error", e.to_string());
+                        return Err(e);
+                    }
+                    frames += 1;
+                    if std::fs::metadata(&path).is_ok_and(|meta| meta.len() >= MAX_FILE_BYTES) {
+                        stop.store(true, Ordering::SeqCst);
+                        let _ = app.emit_all("recording-limit", format!("Recordings stop at {} MB", MAX_FILE_BYTES / (1024 * 1024)));
+                        last_time = at;
+                        previous = Some((image, at));
+                        break;
+                    }
+                }
+                last_time = at;
+                previous = Some((image, at));
+            }
+            if let Some((frame, _)) = previous {
+                push_frame(&mut encoder, frame, 1000 / fps)?;
+                frames += 1;
+            }
+            drop(encoder);
+            Ok(RecordingResult {
+                path: path.to_string_lossy().to_string(),
+                frames,
+                width,
+                height,
+                duration_ms: last_time.duration_since(started).as_millis() as u64 + (1000 / fps) as u64,
+            })
+        })
+    };
+
+    *slot = Some(Recording { stop, capture, encoder, _busy: crate::tray::busy(app) });
+    Ok(())
+}
+
+/// Clean up after a recording the capture thread ended itself (a capture or
+/// encoding error, a limit): free the slot so a new recording can start and
+/// emit "recording-stopped" with the finished GIF. Does nothing when `stop`
+/// took the recording, which then reports the result itself.
+fn finish_unattended(app: &AppHandle, stop: &Arc<AtomicBool>) {
+    let recording = {
+        let mut slot = RECORDING.lock().unwrap_or_else(|e| e.into_inner());
+        match slot.as_ref() {
+            Some(recording) if Arc::ptr_eq(&recording.stop, stop) => slot.take(),
+            _ => None,
+        }
+    };
+    let Some(recording) = recording else { return };
+    // `recording.capture` is the calling thread; it ends right after this
+    match recording.encoder.join() {
+        Ok(Ok(result)) => {
+            log::info!("Recording ended by itself after {} frames: {}", result.frames, result.path);
+            let _ = app.emit_all("recording-stopped", result);
+        }
+        Ok(Err(e)) => log::warn!("Recording ended by itself: {}", e),
+        Err(_) => log::warn!("GIF encoder stopped unexpectedly"),
+    }
+}
+
+/// Stop the recording, finish writing the GIF and return where it is
+pub fn stop() -> Result<RecordingResult, AppError> {
+    let recording = RECORDING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .ok_or_else(|| AppError::InvalidArgument("No recording is running".to_string()))?;
+    recording.stop.store(true, Ordering::SeqCst);
+    let _ = recording.capture.join();
+    let result = recording
+        .encoder
+        .join()
+        .unwrap_or_else(|_| Err(AppError::EncodeFailed("GIF encoder stopped unexpectedly".to_string())))?;
+    log::info!("Recorded {} frames to {}", result.frames, result.path);
+    Ok(result)
+}
+
+/// Write `frames` as a GIF in the captures folder, each shown for `delay_ms`.
+/// Frames must all be the same size unless `pad` is set, in which case each
+/// is centered on a transparent canvas as large as the largest frame.
+pub fn make_gif(frames: Vec<RgbaImage>, delay_ms: u32, loop_forever: bool, pad: bool) -> Result<PathBuf, AppError> {
+    let first = frames
+        .first()
+        .ok_or_else(|| AppError::InvalidArgument("At least one frame is required".to_string()))?
+        .dimensions();
+    let width = frames.iter().map(|f| f.width()).max().unwrap_or(0);
+    let height = frames.iter().map(|f| f.height()).max().unwrap_or(0);
+    if !pad {
+        if let Some((i, frame)) = frames.iter().enumerate().find(|(_, f)| f.dimensions() != first) {
+            return Err(AppError::InvalidArgument(format!(
+                "Frame {} is {}x{} but frame 0 is {}x{}; pass pad to combine frames of different sizes",
+                i,
+                frame.width(),
+                frame.height(),
+                first.0,
+                first.1
+            )));
+        }
+    }
+
+    let path = output_path(ANIMATION_PATTERN);
+    let mut encoder = gif_encoder(&path, loop_forever, QUANTIZE_SPEED)?;
+    let count = frames.len();
+    for frame in frames {
+        let frame = if frame.dimensions() == (width, height) {
+            frame
+        } else {
+            let mut canvas = RgbaImage::new(width, height);
+            let x = (width - frame.width()) / 2;
+            let y = (height - frame.height()) / 2;
+            image::imageops::replace(&mut canvas, &frame, x as i64, y as i64);
+            canvas
+        };
+        push_frame(&mut encoder, frame, delay_ms)?;
+    }
+    drop(encoder);
+    log::info!("Wrote {}-frame GIF to {}", count, path.display());
+    Ok(path)
+}
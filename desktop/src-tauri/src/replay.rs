@@ -0,0 +1,212 @@
+// ============================================
+// ScreenAI Desktop — Instant replay buffer
+// ============================================
+//
+// A background thread grabs frames like a recording but keeps only the last
+// `seconds` of them in memory, so the moments before the user asks can still
+// be saved. Memory is bounded by `MAX_BUFFER_BYTES`: frames are shrunk until
+// the whole window fits, and if even `MIN_FRAME_WIDTH` is too big the buffer
+// covers fewer seconds. Frames are shared with `Arc` so saving only holds the
+// lock long enough to copy the pointers, and capturing goes on meanwhile.
+
+use crate::capture::Rect;
+use crate::error::AppError;
+use crate::recording::{self, RecordingResult};
+use image::RgbaImage;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+pub const MAX_SECONDS: u32 = 60;
+/// Frame memory the buffer may hold
+const MAX_BUFFER_BYTES: u64 = 256 * 1024 * 1024;
+/// Frames are not shrunk below this width to fit more of them
+const MIN_FRAME_WIDTH: u32 = 320;
+/// GIF quantizer speed for saved replays; faster than recordings, the buffer can be long
+const QUANTIZE_SPEED: i32 = 20;
+
+type Frames = VecDeque<(Arc<RgbaImage>, Instant)>;
+
+struct Buffer {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+    frames: Arc<Mutex<Frames>>,
+    fps: u32,
+}
+
+static BUFFER: Mutex<Option<Buffer>> = Mutex::new(None);
+
+/// Returned by `start_replay_buffer`: what the buffer will actually hold
+#[derive(Clone, Serialize)]
+pub struct ReplayInfo {
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    pub frames: usize,
+    /// Less than requested when `MAX_BUFFER_BYTES` caps it
+    pub seconds: f64,
+    pub memory_bytes: u64,
+}
+
+/// Size frames are stored at and how many fit, for `wanted` frames of `width`×`height`
+fn plan(width: u32, height: u32, wanted: usize) -> (u32, u32, usize) {
+    let frame_bytes = |w: u32, h: u32| w as u64 * h as u64 * 4;
+    let mut size = (width, height);
+    if frame_bytes(width, height) * wanted as u64 > MAX_BUFFER_BYTES {
+        let fit = (MAX_BUFFER_BYTES as f64 / (frame_bytes(width, height) * wanted as u64) as f64).sqrt();
+        let w = ((width as f64 * fit) as u32).max(MIN_FRAME_WIDTH.min(width));
+        let h = ((height as f64 * w as f64 / width as f64).round() as u32).max(1);
+        size = (w, h);
+    }
+    let capacity = ((MAX_BUFFER_BYTES / frame_bytes(size.0, size.1).max(1)) as usize).clamp(1, wanted);
+    (size.0, size.1, capacity)
+}
+
+/// Keep the last `seconds` of `area` (global logical coordinates and their
+/// DPI scale; `None` = the primary display) at `fps`. Emits "replay-error" and
+/// stops if frames can't be captured; the buffer is then freed, as by `stop`.
+pub fn start(app: &AppHandle, fps: u32, seconds: u32, area: Option<(Rect, f64)>) -> Result<ReplayInfo, AppError> {
+    if !(1..=recording::MAX_FPS).contains(&fps) {
+        return Err(AppError::InvalidArgument(format!("fps must be between 1 and {}", recording::MAX_FPS)));
+    }
+    if !(1..=MAX_SECONDS).contains(&seconds) {
+        return Err(AppError::InvalidArgument(format!("seconds must be between 1 and {}", MAX_SECONDS)));
+    }
+    let mut slot = BUFFER.lock().unwrap_or_else(|e| e.into_inner());
+    if slot.is_some() {
+        return Err(AppError::InvalidArgument("The replay buffer is already running".to_string()));
+    }
+
+    // First frame up front: fixes the size and surfaces capture errors to the caller
+    let first = recording::grab(area)?;
+    let (width, height, capacity) = plan(first.width(), first.height(), (fps * seconds) as usize);
+    let fit = move |image: RgbaImage| {
+        if image.dimensions() == (width, height) {
+            image
+        } else {
+            image::imageops::resize(&image, width, height, image::imageops::FilterType::Triangle)
+        }
+    };
+    let frames = Arc::new(Mutex::new(Frames::with_capacity(capacity)));
+    frames.lock().unwrap_or_else(|e| e.into_inner()).push_back((Arc::new(fit(first)), Instant::now()));
+    let stop = Arc::new(AtomicBool::new(false));
+    log::info!("Replay buffer: {} frames of {}x{} at {} fps", capacity, width, height, fps);
+
+    let thread = {
+        let (app, stop, frames) = (app.clone(), stop.clone(), frames.clone());
+        std::thread::spawn(move || {
+            let interval = Duration::from_secs_f64(1.0 / fps as f64);
+            let mut next = Instant::now() + interval;
+            while !stop.load(Ordering::SeqCst) {
+                if let Some(wait) = next.checked_duration_since(Instant::now()) {
+                    std::thread::sleep(wait);
+                }
+                // Fall behind rather than burst to catch up
+                next = (next + interval).max(Instant::now());
+                let image = match recording::grab(area) {
+                    Ok(image) => fit(image),
+                    Err(e) => {
+                        log::warn!("Replay buffer stopped: {}", e);
+                        let _ = app.emit_all("replay-error", e.to_string());
+                        // Free the slot, unless `stop` already took it, so the
+                        // buffer can be started again and old frames aren't saved
+                        let mut slot = BUFFER.lock().unwrap_or_else(|e| e.into_inner());
+                        if slot.as_ref().is_some_and(|buffer| Arc::ptr_eq(&buffer.stop, &stop)) {
+                            *slot = None;
+                        }
+                        break;
+                    }
+                };
+                let mut frames = frames.lock().unwrap_or_else(|e| e.into_inner());
+                if frames.len() == capacity {
+                    frames.pop_front();
+                }
+                frames.push_back((Arc::new(image), Instant::now()));
+            }
+        })
+    };
+
+    *slot = Some(Buffer { stop, thread, frames, fps });
+    Ok(ReplayInfo {
+        width,
+        height,
+        fps,
+        frames: capacity,
+        seconds: capacity as f64 / fps as f64,
+        memory_bytes: capacity as u64 * width as u64 * height as u64 * 4,
+    })
+}
+
+/// Write what the buffer holds right now to `path` as a GIF. The buffer keeps running.
+pub fn save(path: &Path) -> Result<RecordingResult, AppError> {
+    let (snapshot, fps): (Vec<_>, u32) = {
+        let slot = BUFFER.lock().unwrap_or_else(|e| e.into_inner());
+        let buffer = slot
+            .as_ref()
+            .ok_or_else(|| AppError::InvalidArgument("The replay buffer is not running".to_string()))?;
+        let frames = buffer.frames.lock().unwrap_or_else(|e| e.into_inner());
+        (frames.iter().cloned().collect(), buffer.fps)
+    };
+    let (first, started) = snapshot
+        .first()
+        .cloned()
+        .ok_or_else(|| AppError::InvalidArgument("The replay buffer is empty".to_string()))?;
+    let frame_ms = 1000 / fps;
+
+    let mut encoder = recording::gif_encoder(path, true, QUANTIZE_SPEED)?;
+    for (i, (frame, at)) in snapshot.iter().enumerate() {
+        // Each frame lasts until the next was taken; the last one a nominal frame
+        let delay = snapshot.get(i + 1).map_or(frame_ms, |(_, next)| next.duration_since(*at).as_millis() as u32);
+        recording::push_frame(&mut encoder, (**frame).clone(), delay)?;
+    }
+    drop(encoder);
+
+    let last = snapshot.last().map_or(started, |(_, at)| *at);
+    let result = RecordingResult {
+        path: path.to_string_lossy().to_string(),
+        frames: snapshot.len() as u32,
+        width: first.width(),
+        height: first.height(),
+        duration_ms: last.duration_since(started).as_millis() as u64 + frame_ms as u64,
+    };
+    log::info!("Saved {} replay frames to {}", result.frames, result.path);
+    Ok(result)
+}
+
+/// Stop capturing and free the frames
+pub fn stop() -> Result<(), AppError> {
+    let buffer = BUFFER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .ok_or_else(|| AppError::InvalidArgument("The replay buffer is not running".to_string()))?;
+    buffer.stop.store(true, Ordering::SeqCst);
+    let _ = buffer.thread.join();
+    log::info!("Replay buffer stopped");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{plan, MAX_BUFFER_BYTES, MIN_FRAME_WIDTH};
+
+    #[test]
+    fn small_buffers_keep_full_size_frames() {
+        assert_eq!(plan(640, 480, 20), (640, 480, 20));
+    }
+
+    #[test]
+    fn large_buffers_shrink_frames_to_fit() {
+        let (w, h, frames) = plan(3840, 2160, 300);
+        assert!((MIN_FRAME_WIDTH..3840).contains(&w));
+        // Proportions are kept
+        assert!((w as f64 / h as f64 - 3840.0 / 2160.0).abs() < 0.01);
+        assert!(w as u64 * h as u64 * 4 * frames as u64 <= MAX_BUFFER_BYTES);
+        assert!((1..=300).contains(&frames));
+    }
+}
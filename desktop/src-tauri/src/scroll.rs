@@ -0,0 +1,180 @@
+// ============================================
+// ScreenAI Desktop — Scrolling (long) captures
+// ============================================
+//
+// The frontend scrolls the target between `capture_scroll_frame` calls; each
+// frame grabs the same screen rectangle and is stitched under the previous
+// one. The scroll delta is found by locating the previous frame's bottom
+// strip in the new frame (sum of absolute luma differences).
+
+use crate::capture::{self, CaptureOptions, Rect};
+use crate::error::AppError;
+use image::RgbaImage;
+use serde::Serialize;
+use std::sync::Mutex;
+
+/// Rows of the previous frame searched for in the next one
+const STRIP_ROWS: u32 = 48;
+/// Mean luma difference per sampled pixel above which no overlap is accepted
+const MATCH_THRESHOLD: u64 = 12;
+/// Only every Nth column is compared — plenty for text and UI, 4× faster
+const COLUMN_STEP: usize = 4;
+/// Stop growing past this height so memory stays bounded
+const MAX_HEIGHT: u32 = 40_000;
+
+struct Session {
+    rect: Rect,
+    scale: f64,
+    options: CaptureOptions,
+    image: RgbaImage,
+    /// Width and height of every frame; `last_luma` is laid out by it
+    frame_size: (u32, u32),
+    /// Luma of the last frame, row-major
+    last_luma: Vec<u8>,
+}
+
+static SESSION: Mutex<Option<Session>> = Mutex::new(None);
+
+/// Returned by `capture_scroll_frame`
+#[derive(Clone, Serialize)]
+pub struct ScrollProgress {
+    /// New rows appended by this frame (0 when the page did not move)
+    pub added_rows: u32,
+    pub total_height: u32,
+    /// False when no overlap was found and the whole frame was appended
+    pub matched: bool,
+}
+
+fn luma(image: &RgbaImage) -> Vec<u8> {
+    image
+        .pixels()
+        .map(|p| ((p.0[0] as u32 * 77 + p.0[1] as u32 * 150 + p.0[2] as u32 * 29) >> 8) as u8)
+        .collect()
+}
+
+/// Row in `next` where the bottom `STRIP_ROWS` of `prev` start, if found.
+/// Both frames have the same size `width`×`height`.
+fn find_overlap(prev: &[u8], next: &[u8], width: u32, height: u32) -> Option<u32> {
+    let strip = STRIP_ROWS.min(height / 2);
+    if strip == 0 {
+        return None;
+    }
+    let (w, strip_start) = (width as usize, (height - strip) as usize);
+    let samples = (strip as u64) * (w.div_ceil(COLUMN_STEP) as u64);
+
+    let mut best: Option<(u64, u32)> = None;
+    for offset in 0..=(height - strip) {
+        let limit = best.map_or(u64::MAX, |(score, _)| score);
+        let mut score = 0u64;
+        'rows: for row in 0..strip as usize {
+            let a = &prev[(strip_start + row) * w..(strip_start + row + 1) * w];
+            let b = &next[(offset as usize + row) * w..(offset as usize + row + 1) * w];
+            for x in (0..w).step_by(COLUMN_STEP) {
+                score += a[x].abs_diff(b[x]) as u64;
+            }
+            // Already worse than the best candidate — skip the rest
+            if score >= limit {
+                break 'rows;
+            }
+        }
+        if score < limit {
+            best = Some((score, offset));
+        }
+    }
+    best.filter(|(score, _)| score / samples.max(1) <= MATCH_THRESHOLD)
+        .map(|(_, offset)| offset)
+}
+
+/// Begin a scrolling capture of `rect` (global logical coordinates) and grab the first frame
+pub fn start(rect: Rect, scale: f64, options: CaptureOptions) -> Result<u32, AppError> {
+    let (image, _, _) = capture::grab_rect(rect, scale, options)?;
+    let frame_size = image.dimensions();
+    let height = image.height();
+    let last_luma = luma(&image);
+    *SESSION.lock().unwrap_or_else(|e| e.into_inner()) = Some(Session { rect, scale, options, image, frame_size, last_luma });
+    Ok(height)
+}
+
+/// Grab the next frame and append whatever scrolled into view
+pub fn frame() -> Result<ScrollProgress, AppError> {
+    let mut guard = SESSION.lock().unwrap_or_else(|e| e.into_inner());
+    let session = guard
+        .as_mut()
+        .ok_or_else(|| AppError::InvalidArgument("No scrolling capture in progress".to_string()))?;
+
+    let (frame, _, _) = capture::grab_rect(session.rect, session.scale, session.options)?;
+    let (width, height) = frame.dimensions();
+    // `find_overlap` indexes both frames by one size, so a resized window can't be stitched
+    if (width, height) != session.frame_size {
+        return Err(AppError::InvalidArgument(format!(
+            "Frame size changed during scrolling capture ({}x{} -> {}x{}); start it again",
+            session.frame_size.0, session.frame_size.1, width, height
+        )));
+    }
+    let next_luma = luma(&frame);
+
+    let overlap = find_overlap(&session.last_luma, &next_luma, width, height);
+    let strip = STRIP_ROWS.min(height / 2);
+    // Rows of `frame` below the matched strip are new
+    let first_new = overlap.map_or(0, |offset| offset + strip);
+    let added = (height - first_new).min(MAX_HEIGHT.saturating_sub(session.image.height()));
+
+    if added > 0 {
+        let old_height = session.image.height();
+        let mut grown = RgbaImage::new(width, old_height + added);
+        image::imageops::replace(&mut grown, &session.image, 0, 0);
+        let new_rows = image::imageops::crop_imm(&frame, 0, first_new, width, added).to_image();
+        image::imageops::replace(&mut grown, &new_rows, 0, old_height as i64);
+        session.image = grown;
+    }
+    session.last_luma = next_luma;
+
+    Ok(ScrollProgress {
+        added_rows: added,
+        total_height: session.image.height(),
+        matched: overlap.is_some(),
+    })
+}
+
+/// End the session and return the stitched image with the session's options
+pub fn finish() -> Result<(RgbaImage, CaptureOptions), AppError> {
+    SESSION
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .map(|session| (session.image, session.options))
+        .ok_or_else(|| AppError::InvalidArgument("No scrolling capture in progress".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_overlap, STRIP_ROWS};
+
+    const WIDTH: u32 = 16;
+    const HEIGHT: u32 = 120;
+
+    /// A frame whose rows each have their own shade, starting at page row `first`
+    fn frame(first: u32) -> Vec<u8> {
+        (first..first + HEIGHT)
+            .flat_map(|row| std::iter::repeat_n((row * 37 % 256) as u8, WIDTH as usize))
+            .collect()
+    }
+
+    #[test]
+    fn finds_how_far_the_page_scrolled() {
+        // Scrolled by 30 rows: the old bottom strip now starts 30 rows higher
+        let offset = find_overlap(&frame(0), &frame(30), WIDTH, HEIGHT);
+        assert_eq!(offset, Some(HEIGHT - STRIP_ROWS - 30));
+    }
+
+    #[test]
+    fn unmoved_page_matches_in_place() {
+        assert_eq!(find_overlap(&frame(0), &frame(0), WIDTH, HEIGHT), Some(HEIGHT - STRIP_ROWS));
+    }
+
+    #[test]
+    fn unrelated_frames_do_not_match() {
+        let blank = vec![0u8; (WIDTH * HEIGHT) as usize];
+        assert_eq!(find_overlap(&frame(0), &blank, WIDTH, HEIGHT), None);
+    }
+}
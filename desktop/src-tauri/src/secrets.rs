@@ -0,0 +1,210 @@
+// ============================================
+// ScreenAI Desktop — API keys and upload credentials in the OS keychain
+// ============================================
+//
+// Secrets live in Credential Manager on Windows, the login keychain on macOS
+// and the Secret Service (GNOME Keyring, KWallet) on Linux, never in
+// config.json. When no keychain is reachable — a minimal Linux session
+// without a secrets daemon, a locked keyring the user won't open — they fall
+// back to their config.json fields, which is where they lived before, so
+// nothing stops working. `migrate` moves such leftovers into the keychain as
+// soon as it becomes available.
+
+use crate::config::Config;
+use crate::error::AppError;
+use crate::upload::{HttpSettings, S3Settings, WebDavSettings};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Secrets `set_secret` / `get_secret` accept, named after the config fields
+/// they replace. `uploadHttpHeaders` is the custom endpoint's headers as a
+/// JSON object, since that's where its API key goes.
+pub const NAMES: [&str; 5] = ["aiApiKey", "uploadImgurClientId", "uploadS3SecretAccessKey", "uploadWebdavPassword", "uploadHttpHeaders"];
+
+/// Service / target prefix the entries are filed under
+const SERVICE: &str = "com.screenai.app";
+
+/// Where `store` put a secret
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Location {
+    Keychain,
+    /// No keychain available; kept in config.json
+    Config,
+}
+
+fn check_name(name: &str) -> Result<(), AppError> {
+    if NAMES.contains(&name) {
+        Ok(())
+    } else {
+        Err(AppError::InvalidArgument(format!("Unknown secret \"{}\" (expected one of {})", name, NAMES.join(", "))))
+    }
+}
+
+/// The secret's value in the config file, if it's still there
+fn config_value(config: &Config, name: &str) -> Option<String> {
+    match name {
+        "aiApiKey" => config.ai_api_key.clone(),
+        "uploadImgurClientId" => config.upload_imgur_client_id.clone(),
+        "uploadS3SecretAccessKey" => config.upload_s3.as_ref().map(|s3| s3.secret_access_key.clone()),
+        "uploadWebdavPassword" => config.upload_webdav.as_ref().map(|webdav| webdav.password.clone()),
+        "uploadHttpHeaders" => config
+            .upload_http
+            .as_ref()
+            .filter(|http| !http.headers.is_empty())
+            .and_then(|http| serde_json::to_string(&http.headers).ok()),
+        _ => None,
+    }
+    .filter(|value| !value.is_empty())
+}
+
+fn parse_headers(json: &str) -> Result<BTreeMap<String, String>, AppError> {
+    serde_json::from_str(json)
+        .map_err(|e| AppError::InvalidArgument(format!("uploadHttpHeaders must be a JSON object of strings: {}", e)))
+}
+
+/// Put a secret into (or with `None`, take it out of) the config fields.
+/// `create` adds missing `uploadS3` / `uploadHttp` sections to hold it.
+fn set_config_value(config: &mut Config, name: &str, value: Option<String>, create: bool) -> Result<(), AppError> {
+    match name {
+        "aiApiKey" => config.ai_api_key = value,
+        "uploadImgurClientId" => config.upload_imgur_client_id = value,
+        "uploadS3SecretAccessKey" => {
+            if config.upload_s3.is_none() && create && value.is_some() {
+                config.upload_s3 = Some(S3Settings::default());
+            }
+            if let Some(s3) = config.upload_s3.as_mut() {
+                s3.secret_access_key = value.unwrap_or_default();
+            }
+        }
+        "uploadWebdavPassword" => {
+            if config.upload_webdav.is_none() && create && value.is_some() {
+                config.upload_webdav = Some(WebDavSettings::default());
+            }
+            if let Some(webdav) = config.upload_webdav.as_mut() {
+                webdav.password = value.unwrap_or_default();
+            }
+        }
+        "uploadHttpHeaders" => {
+            let headers = match value {
+                Some(json) => parse_headers(&json)?,
+                None => BTreeMap::new(),
+            };
+            if config.upload_http.is_none() && create && !headers.is_empty() {
+                config.upload_http = Some(HttpSettings::default());
+            }
+            if let Some(http) = config.upload_http.as_mut() {
+                http.headers = headers;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Save `value` under `name`, in the keychain when there is one. An empty
+/// value deletes the secret. Either way the config field is updated so it
+/// never holds a stale copy.
+pub fn store(config: &mut Config, name: &str, value: &str) -> Result<Location, AppError> {
+    check_name(name)?;
+    let value = value.trim();
+    if value.is_empty() {
+        set_config_value(config, name, None, false)?;
+        return match keychain::delete(name) {
+            Ok(()) => Ok(Location::Keychain),
+            Err(e) => {
+                log::warn!("Could not remove {} from the keychain: {}", name, e);
+                Ok(Location::Config)
+            }
+        };
+    }
+    if name == "uploadHttpHeaders" {
+        parse_headers(value)?;
+    }
+    match keychain::set(name, value) {
+        Ok(()) => {
+            set_config_value(config, name, None, false)?;
+            Ok(Location::Keychain)
+        }
+        Err(e) => {
+            log::warn!("No keychain for {}, keeping it in config.json: {}", name, e);
+            set_config_value(config, name, Some(value.to_string()), true)?;
+            Ok(Location::Config)
+        }
+    }
+}
+
+/// The secret called `name`: from the keychain, else from config.json
+pub fn get(config: &Config, name: &str) -> Result<Option<String>, AppError> {
+    check_name(name)?;
+    match keychain::get(name) {
+        Ok(Some(value)) => Ok(Some(value)),
+        Ok(None) => Ok(config_value(config, name)),
+        Err(e) => {
+            log::warn!("Could not read {} from the keychain: {}", name, e);
+            Ok(config_value(config, name))
+        }
+    }
+}
+
+/// Fill the secret fields of a loaded config from the keychain, for code that
+/// needs the credentials (AI requests, uploads). The result must not be saved.
+pub fn fill(config: &mut Config) {
+    for name in NAMES {
+        if let Ok(Some(value)) = get(config, name) {
+            let _ = set_config_value(config, name, Some(value), false);
+        }
+    }
+}
+
+/// Move secrets still in the config fields into the keychain. Returns whether
+/// the config changed and needs saving.
+pub fn migrate(config: &mut Config) -> bool {
+    let mut changed = false;
+    for name in NAMES {
+        let Some(value) = config_value(config, name) else { continue };
+        match keychain::set(name, &value) {
+            Ok(()) => {
+                let _ = set_config_value(config, name, None, false);
+                log::info!("Moved {} from config.json to the keychain", name);
+                changed = true;
+            }
+            Err(e) => {
+                log::warn!("Could not move {} to the keychain: {}", name, e);
+                // The keychain is missing altogether; the others will fail too
+                break;
+            }
+        }
+    }
+    changed
+}
+
+/// The platform store through the `keyring` crate, one entry per secret
+/// under `SERVICE`. These calls can wait on an unlock prompt, so callers run
+/// them off the main thread.
+mod keychain {
+    use super::SERVICE;
+
+    fn entry(name: &str) -> Result<keyring::Entry, String> {
+        keyring::Entry::new(SERVICE, name).map_err(|e| e.to_string())
+    }
+
+    pub fn get(name: &str) -> Result<Option<String>, String> {
+        match entry(name)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub fn set(name: &str, value: &str) -> Result<(), String> {
+        entry(name)?.set_password(value).map_err(|e| e.to_string())
+    }
+
+    pub fn delete(name: &str) -> Result<(), String> {
+        match entry(name)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
@@ -0,0 +1,232 @@
+// ============================================
+// ScreenAI Desktop — Tray icon state
+// ============================================
+//
+// The tray shows a "busy" variant of the icon while a capture or an update
+// install runs. Work can overlap (an update downloading during a capture), so
+// a counter decides, not a flag: the icon goes back to idle when the last
+// `BusyGuard` drops. The busy icon is the idle one with a dot painted in the
+// bottom-right corner, so both stay in sync with `icons/icon.png`.
+//
+// The menu has a "Capture display" submenu listing the monitors; the one
+// picked there becomes the default display of `capture_screen`. Monitors are
+// only enumerated when the menu is built, so "Refresh monitors" rebuilds it
+// after a display is plugged in or removed.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use tauri::{AppHandle, CustomMenuItem, Icon, Manager, SystemTrayMenu, SystemTrayMenuItem, SystemTraySubmenu};
+
+const IDLE_TOOLTIP: &str = "ScreenAI";
+const ICON_PNG: &[u8] = include_bytes!("../icons/icon.png");
+/// Orange, or just a solid dot where the OS renders the icon as a template
+const BADGE_COLOR: image::Rgba<u8> = image::Rgba([255, 149, 0, 255]);
+
+static BUSY_COUNT: AtomicUsize = AtomicUsize::new(0);
+/// Display picked in the tray, `NO_MONITOR` = the primary one
+static SELECTED_MONITOR: AtomicUsize = AtomicUsize::new(NO_MONITOR);
+const NO_MONITOR: usize = usize::MAX;
+const MONITOR_ITEM: &str = "monitor_";
+const MONITOR_AUTO: &str = "monitor_auto";
+const MONITOR_REFRESH: &str = "monitor_refresh";
+pub const LAUNCH_AT_LOGIN: &str = "launch_at_login";
+
+/// What a left click on the tray icon does (`trayClickAction` in config.json).
+/// macOS opens the menu on left click whatever this says.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClickAction {
+    /// The capture toolbar, like the menu's "Capture"
+    Capture,
+    Open,
+    Region,
+    None,
+}
+
+impl ClickAction {
+    pub fn parse(action: &str) -> Result<Self, crate::error::AppError> {
+        match action.trim().to_ascii_lowercase().as_str() {
+            "capture" => Ok(ClickAction::Capture),
+            "open" => Ok(ClickAction::Open),
+            "region" => Ok(ClickAction::Region),
+            "none" => Ok(ClickAction::None),
+            other => Err(crate::error::AppError::InvalidArgument(format!(
+                "Unknown tray click action \"{}\" (capture, open, region or none)",
+                other
+            ))),
+        }
+    }
+}
+
+struct Icons {
+    idle: image::RgbaImage,
+    busy: image::RgbaImage,
+}
+
+fn icons() -> Option<&'static Icons> {
+    static ICONS: OnceLock<Option<Icons>> = OnceLock::new();
+    ICONS
+        .get_or_init(|| {
+            let idle = image::load_from_memory(ICON_PNG).ok()?.to_rgba8();
+            let mut busy = idle.clone();
+            let (w, h) = busy.dimensions();
+            let radius = (w.min(h) / 5).max(2) as i32;
+            let center = (w as i32 - radius - 1, h as i32 - radius - 1);
+            imageproc::drawing::draw_filled_circle_mut(&mut busy, center, radius, BADGE_COLOR);
+            Some(Icons { idle, busy })
+        })
+        .as_ref()
+}
+
+fn set_icon(app: &AppHandle, busy: bool) {
+    let Some(icons) = icons() else { return };
+    let image = if busy { &icons.busy } else { &icons.idle };
+    let tray = app.tray_handle();
+    let icon = Icon::Rgba { rgba: image.as_raw().clone(), width: image.width(), height: image.height() };
+    if let Err(e) = tray.set_icon(icon) {
+        log::debug!("Could not update tray icon: {}", e);
+    }
+    // Replacing the icon drops the template flag set from tauri.conf.json
+    #[cfg(target_os = "macos")]
+    let _ = tray.set_icon_as_template(true);
+}
+
+/// Keeps the tray icon busy until dropped
+pub struct BusyGuard {
+    app: AppHandle,
+}
+
+impl Drop for BusyGuard {
+    fn drop(&mut self) {
+        if BUSY_COUNT.fetch_sub(1, Ordering::SeqCst) == 1 {
+            set_icon(&self.app, false);
+            set_tooltip(&self.app, None);
+        }
+    }
+}
+
+/// Switch the tray to the busy icon for the lifetime of the returned guard
+pub fn busy(app: &AppHandle) -> BusyGuard {
+    if BUSY_COUNT.fetch_add(1, Ordering::SeqCst) == 0 {
+        set_icon(app, true);
+    }
+    BusyGuard { app: app.clone() }
+}
+
+/// Show a status next to the app name in the tray tooltip, or reset it.
+/// Tooltips are unsupported on Linux; the call is then a no-op.
+pub fn set_tooltip(app: &AppHandle, status: Option<&str>) {
+    let tooltip = match status {
+        Some(status) => format!("{} — {}", IDLE_TOOLTIP, status),
+        None => IDLE_TOOLTIP.to_string(),
+    };
+    let _ = app.tray_handle().set_tooltip(&tooltip);
+}
+
+/// Display chosen in the tray for `capture_screen`, `None` = the primary one
+pub fn selected_monitor() -> Option<usize> {
+    match SELECTED_MONITOR.load(Ordering::SeqCst) {
+        NO_MONITOR => None,
+        index => Some(index),
+    }
+}
+
+fn monitor_submenu() -> SystemTraySubmenu {
+    let selected = selected_monitor();
+    let item = |id: String, title: String, checked: bool| {
+        let item = CustomMenuItem::new(id, title);
+        if checked {
+            item.selected()
+        } else {
+            item
+        }
+    };
+    let mut menu = SystemTrayMenu::new().add_item(item(MONITOR_AUTO.to_string(), "Primary display".to_string(), selected.is_none()));
+    for monitor in crate::capture::monitors().unwrap_or_default() {
+        let title = format!(
+            "Display {} — {}×{}{}",
+            monitor.index + 1,
+            monitor.width,
+            monitor.height,
+            if monitor.is_primary { " (primary)" } else { "" }
+        );
+        menu = menu.add_item(item(format!("{}{}", MONITOR_ITEM, monitor.index), title, selected == Some(monitor.index)));
+    }
+    menu = menu
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new(MONITOR_REFRESH, "Refresh monitors"));
+    SystemTraySubmenu::new("Capture display", menu)
+}
+
+/// Check item for `set_autostart`, ticked from the OS entry
+fn launch_at_login_item() -> CustomMenuItem {
+    let item = CustomMenuItem::new(LAUNCH_AT_LOGIN, "Launch at login");
+    if crate::autostart::is_enabled().unwrap_or(false) {
+        item.selected()
+    } else {
+        item
+    }
+}
+
+/// Tick or untick "Launch at login" after it changed elsewhere
+pub fn set_launch_at_login(app: &AppHandle, enabled: bool) {
+    if let Some(item) = app.tray_handle().try_get_item(LAUNCH_AT_LOGIN) {
+        let _ = item.set_selected(enabled);
+    }
+}
+
+/// The tray menu, with the monitors connected right now
+pub fn menu() -> SystemTrayMenu {
+    SystemTrayMenu::new()
+        .add_item(CustomMenuItem::new("capture", "Capture (Alt+Shift+S)"))
+        .add_item(CustomMenuItem::new("capture_region", "Region (Alt+Shift+A)"))
+        .add_submenu(monitor_submenu())
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(launch_at_login_item())
+        .add_item(CustomMenuItem::new("show", "Open ScreenAI"))
+        .add_item(CustomMenuItem::new("quit", "Quit"))
+}
+
+/// Replace the tray menu, re-reading the monitor list. A selected monitor
+/// that is gone falls back to the primary display.
+pub fn rebuild(app: &AppHandle) -> Result<(), crate::error::AppError> {
+    let count = crate::capture::monitors().map(|m| m.len()).unwrap_or(0);
+    if selected_monitor().is_some_and(|index| index >= count) {
+        SELECTED_MONITOR.store(NO_MONITOR, Ordering::SeqCst);
+    }
+    app.tray_handle()
+        .set_menu(menu())
+        .map_err(|e| crate::error::AppError::Io(format!("Could not rebuild the tray menu: {}", e)))
+}
+
+/// Handle a click on a "Capture display" item (other ids are ignored).
+/// Returns true when the menu was rebuilt, which resets the item labels.
+pub fn handle_monitor_item(app: &AppHandle, id: &str) -> bool {
+    let selected = match id {
+        MONITOR_REFRESH => None,
+        MONITOR_AUTO => Some(NO_MONITOR),
+        _ => match id.strip_prefix(MONITOR_ITEM).and_then(|i| i.parse::<usize>().ok()) {
+            Some(index) => Some(index),
+            None => return false,
+        },
+    };
+    if let Some(index) = selected {
+        SELECTED_MONITOR.store(index, Ordering::SeqCst);
+        let _ = app.emit_all("capture-monitor-changed", selected_monitor());
+        // Move the check mark. The menu may list other monitors than are
+        // connected now, so missing items are skipped rather than looked up
+        let tray = app.tray_handle();
+        let _ = tray.get_item(MONITOR_AUTO).set_selected(index == NO_MONITOR);
+        for i in 0.. {
+            let Some(item) = tray.try_get_item(&format!("{}{}", MONITOR_ITEM, i)) else { break };
+            let _ = item.set_selected(i == index);
+        }
+        return false;
+    }
+    match rebuild(app) {
+        Ok(()) => true,
+        Err(e) => {
+            log::warn!("{}", e);
+            false
+        }
+    }
+}
@@ -0,0 +1,861 @@
+// ============================================
+// ScreenAI Desktop — Update checks
+// ============================================
+//
+// Reads Tauri-style `latest.json` manifests ourselves instead of relying on the
+// built-in updater, so the release channel can be switched at runtime.
+// Stable uses the updater endpoints from tauri.conf.json; beta uses the
+// `betaEndpoints` listed under `plugins.updaterChannels` in the same file.
+// Downloads are verified against the minisign `pubkey` from tauri.conf.json.
+
+use crate::config::Config;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// Set while `install` is downloading or installing
+static INSTALL_RUNNING: AtomicBool = AtomicBool::new(false);
+/// Where a running `install` is: `DOWNLOADING` until `cancel` moves it to
+/// `CANCELLED` or the verified download moves it to `COMMITTED`, whichever
+/// comes first. Checked between download chunks.
+static INSTALL_PHASE: AtomicU8 = AtomicU8::new(DOWNLOADING);
+const DOWNLOADING: u8 = 0;
+const CANCELLED: u8 = 1;
+const COMMITTED: u8 = 2;
+/// Set while `check` is talking to the update server
+static CHECK_RUNNING: AtomicBool = AtomicBool::new(false);
+/// Result of the last successful `check`
+static LAST_CHECK: Mutex<Option<UpdateInfo>> = Mutex::new(None);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channel {
+    Stable,
+    Beta,
+}
+
+impl Channel {
+    pub fn parse(channel: &str) -> Result<Self, AppError> {
+        match channel.trim().to_ascii_lowercase().as_str() {
+            "stable" => Ok(Channel::Stable),
+            "beta" => Ok(Channel::Beta),
+            other => Err(AppError::InvalidArgument(format!(
+                "Unknown update channel \"{}\" (expected stable or beta)",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Channel::Stable => "stable",
+            Channel::Beta => "beta",
+        }
+    }
+
+    /// Channel saved in the config, stable if unset or unknown
+    pub fn current(app: &AppHandle) -> Channel {
+        Channel::parse(&Config::load(app).update_channel).unwrap_or(Channel::Stable)
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct UpdateInfo {
+    pub available: bool,
+    pub version: String,
+    pub body: String,
+    pub date: String,
+    pub channel: String,
+    /// When this was fetched (unix seconds)
+    pub checked_at: u64,
+}
+
+/// `latest.json` as produced by the Tauri bundler
+#[derive(Deserialize)]
+struct Manifest {
+    version: String,
+    #[serde(default)]
+    notes: String,
+    #[serde(default)]
+    pub_date: String,
+    #[serde(default)]
+    platforms: BTreeMap<String, PlatformRelease>,
+}
+
+#[derive(Deserialize)]
+struct PlatformRelease {
+    url: String,
+    #[serde(default)]
+    signature: String,
+}
+
+/// OS name as used by the Tauri updater ("linux", "windows" or "darwin")
+fn target_os() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "darwin",
+        other => other,
+    }
+}
+
+/// Manifest key for this build, e.g. "windows-x86_64" or "darwin-aarch64"
+fn platform_key() -> String {
+    format!("{}-{}", target_os(), std::env::consts::ARCH)
+}
+
+fn endpoints(app: &AppHandle, channel: Channel) -> Vec<String> {
+    match channel {
+        Channel::Stable => app
+            .config()
+            .tauri
+            .updater
+            .endpoints
+            .iter()
+            .flatten()
+            .map(|e| e.to_string())
+            .collect(),
+        Channel::Beta => app
+            .config()
+            .plugins
+            .0
+            .get("updaterChannels")
+            .and_then(|channels| channels.get("betaEndpoints"))
+            .and_then(|endpoints| endpoints.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.as_str().map(str::to_string))
+            .collect(),
+    }
+}
+
+/// Split "v1.5.0-beta.2+build.7" into ([1, 5, 0], Some("beta.2")); build metadata is dropped
+fn parse_version(version: &str) -> (Vec<u64>, Option<&str>) {
+    let version = version.trim().trim_start_matches('v');
+    let version = version.split_once('+').map_or(version, |(version, _)| version);
+    let (core, pre) = match version.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (version, None),
+    };
+    (core.split('.').map(|n| n.parse().unwrap_or(0)).collect(), pre)
+}
+
+/// Semver precedence of two pre-release tags such as "beta.9" and "beta.10":
+/// numeric identifiers compare as numbers and sort before alphanumeric ones,
+/// and a tag that is a prefix of the other sorts first
+fn compare_pre(a: &str, b: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+    let mut a = a.split('.');
+    let mut b = b.split('.');
+    loop {
+        let ordering = match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (Some(x), Some(y)) => match (x.parse::<u64>(), y.parse::<u64>()) {
+                (Ok(x), Ok(y)) => x.cmp(&y),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => x.cmp(y),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Whether `candidate` is a newer release than `current`, by semver precedence.
+/// A pre-release sorts before the release with the same version number.
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    let (cand_core, cand_pre) = parse_version(candidate);
+    let (cur_core, cur_pre) = parse_version(current);
+    let len = cand_core.len().max(cur_core.len());
+    let pad = |v: &[u64]| (0..len).map(|i| v.get(i).copied().unwrap_or(0)).collect::<Vec<_>>();
+    match pad(&cand_core).cmp(&pad(&cur_core)) {
+        std::cmp::Ordering::Greater => true,
+        std::cmp::Ordering::Less => false,
+        std::cmp::Ordering::Equal => match (cand_pre, cur_pre) {
+            (None, Some(_)) => true,
+            (Some(a), Some(b)) => compare_pre(a, b).is_gt(),
+            _ => false,
+        },
+    }
+}
+
+/// Why fetching a manifest failed. Only `Network` is worth retrying; the
+/// others won't change by asking again a few seconds later.
+#[derive(Debug)]
+pub enum UpdateCheckError {
+    /// No connection, timeout, or the server answered 5xx / 429
+    Network(String),
+    /// The server answered but had no usable manifest (4xx, bad JSON)
+    Server(String),
+    /// No update endpoints are configured for the channel
+    NotConfigured,
+}
+
+impl UpdateCheckError {
+    fn transient(&self) -> bool {
+        matches!(self, UpdateCheckError::Network(_))
+    }
+}
+
+impl From<UpdateCheckError> for AppError {
+    fn from(e: UpdateCheckError) -> Self {
+        match e {
+            UpdateCheckError::Network(e) => AppError::UpdateNetwork(e),
+            UpdateCheckError::Server(e) => AppError::UpdateFailed(e),
+            UpdateCheckError::NotConfigured => AppError::UpdaterUnavailable,
+        }
+    }
+}
+
+/// Attempts per endpoint for transient errors
+const FETCH_ATTEMPTS: u32 = 3;
+/// Wait before the first retry; doubled for each further one
+const FETCH_BACKOFF: std::time::Duration = std::time::Duration::from_secs(2);
+const FETCH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(20);
+const CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+/// Longest wait for the next chunk of an update download before giving up
+const READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Client for talking to the update server; no request can wait forever to connect
+fn http_client() -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .user_agent("ScreenAI-Desktop")
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+}
+
+async fn fetch_manifest(url: &str, current_version: &str) -> Result<Manifest, UpdateCheckError> {
+    let url = url
+        .replace("{{current_version}}", current_version)
+        .replace("{{target}}", target_os())
+        .replace("{{arch}}", std::env::consts::ARCH);
+    let response = http_client()
+        .map_err(|e| UpdateCheckError::Network(e.to_string()))?
+        .get(&url)
+        .timeout(FETCH_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| UpdateCheckError::Network(e.to_string()))?;
+    let status = response.status();
+    if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(UpdateCheckError::Network(format!("{} returned {}", url, status)));
+    }
+    if !status.is_success() {
+        return Err(UpdateCheckError::Server(format!("{} returned {}", url, status)));
+    }
+    // A body cut off mid-transfer is a network problem, not a bad manifest
+    let text = response.text().await.map_err(|e| UpdateCheckError::Network(e.to_string()))?;
+    serde_json::from_str::<Manifest>(&text).map_err(|e| UpdateCheckError::Server(format!("invalid manifest: {}", e)))
+}
+
+/// `fetch_manifest`, retried with backoff while the error is transient
+async fn fetch_with_retry(url: &str, current_version: &str) -> Result<Manifest, UpdateCheckError> {
+    let mut delay = FETCH_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match fetch_manifest(url, current_version).await {
+            Err(e) if e.transient() && attempt < FETCH_ATTEMPTS => {
+                log::info!("Update manifest attempt {} failed ({:?}), retrying in {} s", attempt, e, delay.as_secs());
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Fetch the manifest of the saved channel, trying each endpoint in turn
+async fn latest(app: &AppHandle) -> Result<(Channel, Manifest), UpdateCheckError> {
+    let channel = Channel::current(app);
+    let current_version = app.package_info().version.to_string();
+    let mut last_error = UpdateCheckError::NotConfigured;
+
+    for url in endpoints(app, channel) {
+        match fetch_with_retry(&url, &current_version).await {
+            Ok(manifest) => return Ok((channel, manifest)),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// Clears `CHECK_RUNNING` however `check` exits
+struct CheckGuard;
+
+impl Drop for CheckGuard {
+    fn drop(&mut self) {
+        CHECK_RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Check the saved channel for a release newer than the running version.
+/// Only one check runs at a time; a second caller gets `update_check_in_progress`. Network
+/// errors are retried a few times and surface as `update_network`, so the
+/// caller can tell "offline" from "no update" (`available: false`).
+pub async fn check(app: &AppHandle) -> Result<UpdateInfo, AppError> {
+    if CHECK_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err(AppError::UpdateCheckInProgress);
+    }
+    let _running = CheckGuard;
+    let (channel, manifest) = latest(app).await?;
+    let current_version = app.package_info().version.to_string();
+    let available = is_newer(&manifest.version, &current_version)
+        && manifest.platforms.contains_key(&platform_key());
+    log::info!("Update check ({}): latest {}, running {}", channel.as_str(), manifest.version, current_version);
+    let info = UpdateInfo {
+        available,
+        version: manifest.version,
+        body: manifest.notes,
+        date: manifest.pub_date,
+        channel: channel.as_str().to_string(),
+        checked_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    };
+    *LAST_CHECK.lock().unwrap_or_else(|e| e.into_inner()) = Some(info.clone());
+    Ok(info)
+}
+
+/// How often the background task looks at the config to see whether a check is due
+const SCHEDULE_TICK: std::time::Duration = std::time::Duration::from_secs(60);
+/// After a failed background check, try again this soon; doubled per failure
+/// up to the configured interval
+const RETRY_AFTER: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+/// Background checks for long-running instances: once shortly after launch,
+/// then every `update_check_interval_hours` while `auto_update_check` is on.
+/// A failed check is retried after `RETRY_AFTER` (backing off) instead of
+/// waiting a whole interval. The config is re-read every tick, so changes
+/// apply without a restart.
+/// Emits "update-available" to the main window when a newer release is found.
+pub fn spawn_periodic_checks(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        let mut ticks = tokio::time::interval(SCHEDULE_TICK);
+        let mut last_attempt: Option<std::time::Instant> = None;
+        let mut failures = 0u32;
+        loop {
+            ticks.tick().await;
+            let config = Config::load(&app);
+            let every = std::time::Duration::from_secs(config.update_check_interval_hours.max(1) as u64 * 3600);
+            let wait = match failures {
+                0 => every,
+                n => RETRY_AFTER.saturating_mul(1 << (n - 1).min(8)).min(every),
+            };
+            if !config.auto_update_check || last_attempt.is_some_and(|t| t.elapsed() < wait) {
+                continue;
+            }
+            last_attempt = Some(std::time::Instant::now());
+            match check(&app).await {
+                Ok(info) => {
+                    failures = 0;
+                    if info.available {
+                        let _ = app.emit_to("main", "update-available", info);
+                    }
+                }
+                // Someone else's check (e.g. `check_for_updates`) is in flight; try next tick
+                Err(AppError::UpdateCheckInProgress) => last_attempt = None,
+                Err(e) => {
+                    failures += 1;
+                    log::warn!("Update check failed ({} in a row): {}", failures, e);
+                }
+            }
+        }
+    });
+}
+
+/// Last `check` result for the saved channel, without touching the network.
+/// `None` before the first check or after switching channels.
+pub fn cached(app: &AppHandle) -> Option<UpdateInfo> {
+    let channel = Channel::current(app);
+    LAST_CHECK
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .filter(|info| info.channel == channel.as_str())
+}
+
+/// Payload of "update-download-progress"
+#[derive(Clone, Serialize)]
+struct DownloadProgress {
+    bytes_downloaded: u64,
+    /// `None` when the server sent no Content-Length
+    content_length: Option<u64>,
+}
+
+/// Minimum time between two progress events
+const PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Clears `INSTALL_RUNNING` however `install` exits
+struct RunningGuard;
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        INSTALL_RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Ask a running `install` to stop. Only the download can be interrupted;
+/// returns false when nothing is being downloaded or installing has begun.
+pub fn cancel() -> bool {
+    if !INSTALL_RUNNING.load(Ordering::SeqCst) {
+        return false;
+    }
+    match INSTALL_PHASE.compare_exchange(DOWNLOADING, CANCELLED, Ordering::SeqCst, Ordering::SeqCst) {
+        Ok(_) => true,
+        Err(phase) => phase == CANCELLED,
+    }
+}
+
+fn cancelled() -> bool {
+    INSTALL_PHASE.load(Ordering::SeqCst) == CANCELLED
+}
+
+/// Download the latest release of the saved channel into `dir`, emitting
+/// throttled "update-download-progress" events to the main window.
+/// The partial file is deleted if the download fails or is cancelled.
+async fn download(app: &AppHandle, url: &str, dir: &Path) -> Result<PathBuf, AppError> {
+    let name = url.rsplit('/').next().filter(|n| !n.is_empty()).unwrap_or("update.bin");
+    let path = dir.join(name);
+
+    let result = async {
+        let mut response = http_client()
+            .map_err(|e| AppError::UpdateFailed(e.to_string()))?
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| AppError::UpdateFailed(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(AppError::UpdateFailed(format!("{} returned {}", url, response.status())));
+        }
+
+        let content_length = response.content_length();
+        let mut bytes_downloaded = 0u64;
+        let mut last_emit: Option<std::time::Instant> = None;
+        let emit = |bytes_downloaded| {
+            let _ = app.emit_to("main", "update-download-progress", DownloadProgress { bytes_downloaded, content_length });
+        };
+
+        let mut file = std::fs::File::create(&path).map_err(|e| AppError::Io(e.to_string()))?;
+        loop {
+            let chunk = tokio::time::timeout(READ_TIMEOUT, response.chunk())
+                .await
+                .map_err(|_| AppError::UpdateFailed(format!("no data from {} for {} s", url, READ_TIMEOUT.as_secs())))?
+                .map_err(|e| AppError::UpdateFailed(e.to_string()))?;
+            let Some(chunk) = chunk else { break };
+            if cancelled() {
+                return Err(AppError::UpdateCancelled);
+            }
+            file.write_all(&chunk).map_err(|e| AppError::Io(e.to_string()))?;
+            bytes_downloaded += chunk.len() as u64;
+            if last_emit.is_none_or(|t| t.elapsed() >= PROGRESS_INTERVAL) {
+                emit(bytes_downloaded);
+                last_emit = Some(std::time::Instant::now());
+            }
+        }
+        // Always report the final count, even if it fell inside the throttle window
+        emit(bytes_downloaded);
+        file.flush().map_err(|e| AppError::Io(e.to_string()))
+    }
+    .await;
+
+    match result {
+        Ok(()) => Ok(path),
+        Err(e) => {
+            let _ = std::fs::remove_file(&path);
+            Err(e)
+        }
+    }
+}
+
+/// Check the signature Tauri's bundler produced (base64 minisign files)
+fn verify_signature(data: &[u8], signature: &str, pubkey: &str) -> Result<(), AppError> {
+    let decode = |b64: &str| {
+        BASE64
+            .decode(b64.trim())
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(|| AppError::UpdateFailed("malformed signature or public key".to_string()))
+    };
+    let public_key = minisign_verify::PublicKey::decode(&decode(pubkey)?)
+        .map_err(|e| AppError::UpdateFailed(format!("invalid public key: {}", e)))?;
+    let signature = minisign_verify::Signature::decode(&decode(signature)?)
+        .map_err(|e| AppError::UpdateFailed(format!("invalid signature: {}", e)))?;
+    public_key
+        .verify(data, &signature, true)
+        .map_err(|_| AppError::UpdateFailed("signature verification failed".to_string()))
+}
+
+/// First file under `dir` (recursively) whose name ends with `suffix`
+fn find_file(dir: &Path, suffix: &str) -> Option<PathBuf> {
+    for entry in std::fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        if path.to_string_lossy().ends_with(suffix) {
+            return Some(path);
+        }
+        if path.is_dir() {
+            if let Some(found) = find_file(&path, suffix) {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
+
+/// Unpack a `.tar.gz` updater artifact (macOS `.app`, Linux `.AppImage`)
+#[cfg(not(target_os = "windows"))]
+fn extract_tar_gz(archive: &Path, dest: &Path) -> Result<(), AppError> {
+    let file = std::fs::File::open(archive).map_err(|e| AppError::Io(e.to_string()))?;
+    tar::Archive::new(flate2::read::GzDecoder::new(file))
+        .unpack(dest)
+        .map_err(|e| AppError::UpdateFailed(format!("could not extract update: {}", e)))
+}
+
+/// Windows: unzip the `.msi.zip` / `.nsis.zip`, launch the installer and quit
+#[cfg(target_os = "windows")]
+fn apply(app: &AppHandle, archive: &Path, work_dir: &Path) -> Result<(), AppError> {
+    let file = std::fs::File::open(archive).map_err(|e| AppError::Io(e.to_string()))?;
+    zip::ZipArchive::new(file)
+        .and_then(|mut zip| zip.extract(work_dir))
+        .map_err(|e| AppError::UpdateFailed(format!("could not extract update: {}", e)))?;
+
+    let mut command = if let Some(msi) = find_file(work_dir, ".msi") {
+        let mut c = std::process::Command::new("msiexec");
+        c.arg("/i").arg(msi).arg("/passive");
+        c
+    } else if let Some(exe) = find_file(work_dir, ".exe") {
+        let mut c = std::process::Command::new(exe);
+        c.arg("/S");
+        c
+    } else {
+        return Err(AppError::UpdateFailed("no installer found in update".to_string()));
+    };
+    command
+        .spawn()
+        .map_err(|e| AppError::UpdateFailed(format!("could not start installer: {}", e)))?;
+    app.exit(0);
+    Ok(())
+}
+
+/// macOS: swap the running `.app` bundle for the new one and restart
+#[cfg(target_os = "macos")]
+fn apply(app: &AppHandle, archive: &Path, work_dir: &Path) -> Result<(), AppError> {
+    extract_tar_gz(archive, work_dir)?;
+    let new_bundle = find_file(work_dir, ".app")
+        .ok_or_else(|| AppError::UpdateFailed("no .app found in update".to_string()))?;
+    replace_path(&install_target()?, &new_bundle)?;
+    app.restart();
+    Ok(())
+}
+
+/// The `.app` bundle we are running from
+#[cfg(target_os = "macos")]
+fn install_target() -> Result<PathBuf, AppError> {
+    // .../ScreenAI.app/Contents/MacOS/screenai-desktop → .../ScreenAI.app
+    let exe = std::env::current_exe().map_err(|e| AppError::Io(e.to_string()))?;
+    exe.ancestors()
+        .nth(3)
+        .filter(|p| p.extension().map(|e| e == "app").unwrap_or(false))
+        .map(Path::to_path_buf)
+        .ok_or_else(|| AppError::UpdateFailed("not running from an .app bundle".to_string()))
+}
+
+/// Linux: replace the AppImage we were launched from and restart
+#[cfg(target_os = "linux")]
+fn apply(app: &AppHandle, archive: &Path, work_dir: &Path) -> Result<(), AppError> {
+    use std::os::unix::fs::PermissionsExt;
+    let appimage = install_target()?;
+    extract_tar_gz(archive, work_dir)?;
+    let new_image = find_file(work_dir, ".AppImage")
+        .ok_or_else(|| AppError::UpdateFailed("no AppImage found in update".to_string()))?;
+    let _ = std::fs::set_permissions(&new_image, std::fs::Permissions::from_mode(0o755));
+    replace_path(&appimage, &new_image)?;
+    app.restart();
+    Ok(())
+}
+
+/// The AppImage we were launched from
+#[cfg(target_os = "linux")]
+fn install_target() -> Result<PathBuf, AppError> {
+    std::env::var_os("APPIMAGE")
+        .map(PathBuf::from)
+        .ok_or_else(|| AppError::UpdateFailed("self-update is only supported for the AppImage".to_string()))
+}
+
+/// Where `replace_path` keeps the previous version for `rollback`
+#[cfg(not(target_os = "windows"))]
+fn backup_path(target: &Path) -> PathBuf {
+    target.with_extension("screenai-old")
+}
+
+#[cfg(not(target_os = "windows"))]
+fn remove_path(path: &Path) {
+    let _ = std::fs::remove_dir_all(path);
+    let _ = std::fs::remove_file(path);
+}
+
+/// Move `replacement` to `target`. The old `target` is kept at `backup_path`
+/// (replacing any older backup) so the update can be rolled back.
+#[cfg(not(target_os = "windows"))]
+fn replace_path(target: &Path, replacement: &Path) -> Result<(), AppError> {
+    let backup = backup_path(target);
+    remove_path(&backup);
+    std::fs::rename(target, &backup).map_err(|e| AppError::UpdateFailed(format!("could not replace app: {}", e)))?;
+    if let Err(e) = std::fs::rename(replacement, target) {
+        let _ = std::fs::rename(&backup, target);
+        return Err(AppError::UpdateFailed(format!("could not install update: {}", e)));
+    }
+    Ok(())
+}
+
+/// Download, verify and install the latest release of the saved channel.
+/// Emits "update-downloaded" once the verified download is about to be installed,
+/// and "update-cancelled" if `cancel` stops the download; nothing is
+/// installed in that case and the downloaded data is removed.
+pub async fn install(app: &AppHandle) -> Result<(), AppError> {
+    if INSTALL_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err(AppError::InvalidArgument("An update is already being installed".to_string()));
+    }
+    let _running = RunningGuard;
+    INSTALL_PHASE.store(DOWNLOADING, Ordering::SeqCst);
+
+    let pubkey = app.config().tauri.updater.pubkey.clone();
+    if pubkey.trim().is_empty() {
+        return Err(AppError::UpdaterUnavailable);
+    }
+
+    let (_, manifest) = latest(app).await?;
+    if !is_newer(&manifest.version, &app.package_info().version.to_string()) {
+        return Err(AppError::UpdateFailed("already up to date".to_string()));
+    }
+    let release = manifest
+        .platforms
+        .get(&platform_key())
+        .ok_or_else(|| AppError::UpdateFailed(format!("no build for {}", platform_key())))?;
+
+    let work_dir = std::env::temp_dir().join("screenai-update");
+    let _ = std::fs::remove_dir_all(&work_dir);
+    std::fs::create_dir_all(&work_dir).map_err(|e| AppError::Io(e.to_string()))?;
+
+    log::info!("Downloading update {} from {}", manifest.version, release.url);
+    let result = async {
+        let archive = download(app, &release.url, &work_dir).await?;
+        let data = std::fs::read(&archive).map_err(|e| AppError::Io(e.to_string()))?;
+        verify_signature(&data, &release.signature, &pubkey)?;
+        // Last point where a cancel is honoured — installing is not interruptible,
+        // so from here on `cancel` answers false
+        if INSTALL_PHASE.compare_exchange(DOWNLOADING, COMMITTED, Ordering::SeqCst, Ordering::SeqCst).is_err() {
+            return Err(AppError::UpdateCancelled);
+        }
+        let _ = app.emit_to("main", "update-downloaded", &manifest.version);
+        log::info!("Installing update {}", manifest.version);
+        // Written first: `apply` restarts or exits the app when it succeeds
+        UpdateState {
+            previous_version: Some(app.package_info().version.to_string()),
+            installed_version: Some(manifest.version.clone()),
+            ..UpdateState::default()
+        }
+        .save(app)?;
+        apply(app, &archive, &work_dir)
+    }
+    .await;
+
+    if let Err(e) = &result {
+        if !matches!(e, AppError::UpdateCancelled) {
+            UpdateState::clear(app);
+        }
+        log::warn!("Update install failed: {}", e);
+        let _ = std::fs::remove_dir_all(&work_dir);
+        if matches!(e, AppError::UpdateCancelled) {
+            let _ = app.emit_all("update-cancelled", ());
+        }
+    }
+    result
+}
+
+// ---------- Failed updates and rollback ----------
+
+const STATE_FILE: &str = "update-state.json";
+/// A new version that quits before running this long counts as crashed
+const STABLE_AFTER: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// What `install` left behind for the next launch, in the app config dir
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct UpdateState {
+    previous_version: Option<String>,
+    installed_version: Option<String>,
+    /// Launches of `installed_version` that have not yet run for `STABLE_AFTER`
+    unconfirmed_launches: u32,
+    confirmed: bool,
+}
+
+impl UpdateState {
+    fn path(app: &AppHandle) -> Option<PathBuf> {
+        app.path_resolver().app_config_dir().map(|dir| dir.join(STATE_FILE))
+    }
+
+    fn load(app: &AppHandle) -> Option<UpdateState> {
+        let text = std::fs::read_to_string(Self::path(app)?).ok()?;
+        serde_json::from_str(&text).ok()
+    }
+
+    fn save(&self, app: &AppHandle) -> Result<(), AppError> {
+        let path = Self::path(app).ok_or_else(|| AppError::Io("No app config directory".to_string()))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| AppError::Io(e.to_string()))?;
+        }
+        let text = serde_json::to_string_pretty(self).map_err(|e| AppError::Io(e.to_string()))?;
+        std::fs::write(&path, text).map_err(|e| AppError::Io(e.to_string()))
+    }
+
+    fn clear(app: &AppHandle) {
+        if let Some(path) = Self::path(app) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    /// The running build is the update and an earlier launch of it died early
+    fn failed(&self, current_version: &str) -> bool {
+        !self.confirmed && self.installed_version.as_deref() == Some(current_version) && self.unconfirmed_launches > 1
+    }
+}
+
+/// Returned by `get_update_status`
+#[derive(Clone, Serialize)]
+pub struct UpdateStatus {
+    pub current_version: String,
+    /// Version that was running before the last in-app update
+    pub previous_version: Option<String>,
+    /// The last update crashed or quit within a minute of launching, at least once
+    pub failed: bool,
+    /// A copy of `previous_version` is kept and `rollback` can restore it
+    pub can_rollback: bool,
+}
+
+/// Previous version kept by the last update, if it's still on disk
+fn rollback_source() -> Option<(PathBuf, PathBuf)> {
+    #[cfg(not(target_os = "windows"))]
+    {
+        let target = install_target().ok()?;
+        let backup = backup_path(&target);
+        backup.exists().then_some((target, backup))
+    }
+    // The Windows installers replace the app in place and keep nothing to go back to
+    #[cfg(target_os = "windows")]
+    None
+}
+
+pub fn status(app: &AppHandle) -> UpdateStatus {
+    let current_version = app.package_info().version.to_string();
+    let state = UpdateState::load(app).unwrap_or_default();
+    UpdateStatus {
+        failed: state.failed(&current_version),
+        previous_version: state.previous_version,
+        can_rollback: rollback_source().is_some(),
+        current_version,
+    }
+}
+
+/// Call once at startup. Counts the launch of a freshly installed version and
+/// confirms it after `STABLE_AFTER`; a second launch before that confirmation
+/// means the previous one crashed or was quit straight away. Returns the
+/// status so the caller can offer `rollback` when `failed` is set.
+pub fn record_launch(app: &AppHandle) -> UpdateStatus {
+    let current_version = app.package_info().version.to_string();
+    if let Some(mut state) = UpdateState::load(app) {
+        if state.installed_version.as_deref() == Some(current_version.as_str()) && !state.confirmed {
+            state.unconfirmed_launches += 1;
+            let _ = state.save(app);
+            if state.failed(&current_version) {
+                log::warn!("Update {} did not run for {} s last time", current_version, STABLE_AFTER.as_secs());
+            } else {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    tokio::time::sleep(STABLE_AFTER).await;
+                    state.confirmed = true;
+                    let _ = state.save(&app);
+                });
+            }
+        }
+    }
+    status(app)
+}
+
+/// Put back the version the last update replaced and restart into it
+pub fn rollback(app: &AppHandle) -> Result<(), AppError> {
+    if INSTALL_RUNNING.load(Ordering::SeqCst) {
+        return Err(AppError::InvalidArgument("An update is being installed".to_string()));
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let _ = app;
+        Err(AppError::UpdateFailed(
+            "rollback is not supported by the Windows installer; reinstall the previous release".to_string(),
+        ))
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let (target, backup) =
+            rollback_source().ok_or_else(|| AppError::UpdateFailed("no previous version is kept".to_string()))?;
+        let previous = UpdateState::load(app).and_then(|s| s.previous_version).unwrap_or_default();
+        log::info!("Rolling back to {} from {}", previous, backup.display());
+        let discarded = target.with_extension("screenai-rollback");
+        remove_path(&discarded);
+        std::fs::rename(&target, &discarded).map_err(|e| AppError::UpdateFailed(format!("could not remove update: {}", e)))?;
+        if let Err(e) = std::fs::rename(&backup, &target) {
+            let _ = std::fs::rename(&discarded, &target);
+            return Err(AppError::UpdateFailed(format!("could not restore previous version: {}", e)));
+        }
+        remove_path(&discarded);
+        UpdateState::clear(app);
+        app.restart();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_newer;
+
+    #[test]
+    fn releases_compare_by_number() {
+        assert!(is_newer("1.10.0", "1.9.0"));
+        assert!(is_newer("v2.0", "1.9.9"));
+        assert!(!is_newer("1.5.0", "1.5.0"));
+        assert!(!is_newer("1.5", "1.5.0"));
+        assert!(!is_newer("1.4.9", "1.5.0"));
+    }
+
+    #[test]
+    fn pre_releases_follow_semver() {
+        assert!(is_newer("1.5.0-beta.10", "1.5.0-beta.9"));
+        assert!(!is_newer("1.5.0-beta.9", "1.5.0-beta.10"));
+        assert!(is_newer("1.5.0-beta", "1.5.0-alpha.3"));
+        assert!(is_newer("1.5.0-beta.1", "1.5.0-beta"));
+        assert!(is_newer("1.5.0-beta.rc", "1.5.0-beta.2"));
+    }
+
+    #[test]
+    fn release_is_newer_than_its_pre_releases() {
+        assert!(is_newer("1.5.0", "1.5.0-beta.10"));
+        assert!(!is_newer("1.5.0-beta.10", "1.5.0"));
+        assert!(is_newer("1.5.1-beta.1", "1.5.0"));
+    }
+
+    #[test]
+    fn build_metadata_is_ignored() {
+        assert!(!is_newer("1.5.0+build.7", "1.5.0"));
+        assert!(!is_newer("1.5.0", "1.5.0+build.7"));
+    }
+}
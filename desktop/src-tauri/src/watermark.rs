@@ -0,0 +1,205 @@
+// ============================================
+// ScreenAI Desktop — Watermarks
+// ============================================
+//
+// The watermark (text or a logo) is rendered into its own transparent layer,
+// the layer's alpha is scaled by the opacity, and the result is alpha-blended
+// into a corner of the capture. Timestamps use the same text layer, on an
+// optional background box so they stay readable on any content.
+
+use crate::annotate;
+use crate::error::AppError;
+use crate::imaging;
+use image::RgbaImage;
+use rusttype::{point, Scale};
+use serde::Deserialize;
+
+/// What to stamp, tagged by `type` in JSON
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WatermarkContent {
+    Text {
+        text: String,
+        #[serde(default = "default_size")]
+        size: f32,
+        #[serde(default = "default_color")]
+        color: String,
+    },
+    /// A logo as a data URL or bare base64, optionally scaled to `width` pixels
+    Image { data_url: String, width: Option<u32> },
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Position {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+    Center,
+}
+
+/// `{ "type": "text", "text": "ScreenAI", "size": 24, "color": "#ffffff",
+///    "position": "bottom_right", "margin": 16, "opacity": 0.5 }`
+#[derive(Clone, Debug, Deserialize)]
+pub struct WatermarkSpec {
+    #[serde(flatten)]
+    pub content: WatermarkContent,
+    #[serde(default)]
+    pub position: Position,
+    /// Distance from the edges, in image pixels
+    #[serde(default = "default_margin")]
+    pub margin: u32,
+    /// 0 (invisible) to 1 (opaque)
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+}
+
+fn default_size() -> f32 {
+    24.0
+}
+
+fn default_color() -> String {
+    "#ffffff".to_string()
+}
+
+fn default_margin() -> u32 {
+    16
+}
+
+fn default_opacity() -> f32 {
+    0.5
+}
+
+/// Render `text` onto a transparent layer just large enough to hold it
+fn text_layer(text: &str, size: f32, color: &str) -> Result<RgbaImage, AppError> {
+    let font = annotate::font().ok_or_else(|| AppError::InvalidArgument("No font available to draw text".to_string()))?;
+    let color = annotate::parse_color(color)?;
+    let scale = Scale::uniform(size.max(1.0));
+    let metrics = font.v_metrics(scale);
+    let width = font
+        .layout(text, scale, point(0.0, metrics.ascent))
+        .filter_map(|glyph| glyph.pixel_bounding_box().map(|b| b.max.x))
+        .max()
+        .unwrap_or(0);
+    let height = (metrics.ascent - metrics.descent).ceil() as u32;
+    if width <= 0 || height == 0 {
+        return Err(AppError::InvalidArgument("Watermark text is empty".to_string()));
+    }
+    let mut layer = RgbaImage::new(width as u32 + 1, height);
+    imageproc::drawing::draw_text_mut(&mut layer, color, 0, 0, scale, font, text);
+    // draw_text blends towards the transparent background; keep the color, use coverage as alpha
+    for pixel in layer.pixels_mut() {
+        if pixel.0[3] > 0 {
+            pixel.0 = [color.0[0], color.0[1], color.0[2], pixel.0[3]];
+        }
+    }
+    Ok(layer)
+}
+
+fn image_layer(data_url: &str, width: Option<u32>) -> Result<RgbaImage, AppError> {
+    let logo = imaging::decode_data_url(data_url)?;
+    match width {
+        Some(0) => Err(AppError::InvalidArgument("Watermark width must be at least 1 pixel".to_string())),
+        Some(w) if w != logo.width() => {
+            let h = ((logo.height() as f64 * w as f64 / logo.width().max(1) as f64).round() as u32).max(1);
+            Ok(image::imageops::resize(&logo, w, h, image::imageops::FilterType::Triangle))
+        }
+        _ => Ok(logo),
+    }
+}
+
+/// Stamp `spec` onto `image`
+pub fn apply(mut image: RgbaImage, spec: &WatermarkSpec) -> Result<RgbaImage, AppError> {
+    if !(0.0..=1.0).contains(&spec.opacity) {
+        return Err(AppError::InvalidArgument(format!("Opacity {} is outside 0-1", spec.opacity)));
+    }
+    let mut layer = match &spec.content {
+        WatermarkContent::Text { text, size, color } => text_layer(text, *size, color)?,
+        WatermarkContent::Image { data_url, width } => image_layer(data_url, *width)?,
+    };
+    for pixel in layer.pixels_mut() {
+        pixel.0[3] = (pixel.0[3] as f32 * spec.opacity).round() as u8;
+    }
+    place(&mut image, &layer, spec.position, spec.margin);
+    Ok(image)
+}
+
+/// Blend `layer` into `image` at `position`, `margin` pixels from the edges
+fn place(image: &mut RgbaImage, layer: &RgbaImage, position: Position, margin: u32) {
+    let (iw, ih) = (image.width() as i64, image.height() as i64);
+    let (lw, lh) = (layer.width() as i64, layer.height() as i64);
+    let m = margin as i64;
+    let (x, y) = match position {
+        Position::TopLeft => (m, m),
+        Position::TopRight => (iw - lw - m, m),
+        Position::BottomLeft => (m, ih - lh - m),
+        Position::BottomRight => (iw - lw - m, ih - lh - m),
+        Position::Center => ((iw - lw) / 2, (ih - lh) / 2),
+    };
+    image::imageops::overlay(image, layer, x, y);
+}
+
+/// `{ "format": "%Y-%m-%d %H:%M:%S", "utc_offset_minutes": 120,
+///    "position": "bottom_right", "size": 20, "color": "#ffffff", "background": "#00000099" }`
+#[derive(Clone, Debug, Deserialize)]
+pub struct TimestampSpec {
+    /// strftime-style: `%Y %m %d %H %M %S`, `%%` (see `autosave::expand`)
+    #[serde(default = "default_timestamp_format")]
+    pub format: String,
+    /// Time to show (unix seconds), e.g. when the capture was taken; default now
+    pub unix_secs: Option<u64>,
+    /// Times are UTC unless shifted by this, e.g. `-new Date().getTimezoneOffset()`
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+    #[serde(default)]
+    pub position: Position,
+    #[serde(default = "default_timestamp_size")]
+    pub size: f32,
+    #[serde(default = "default_color")]
+    pub color: String,
+    /// Box drawn behind the text (`#rrggbbaa` for translucency); `null` for none
+    #[serde(default = "default_timestamp_background")]
+    pub background: Option<String>,
+    #[serde(default = "default_margin")]
+    pub margin: u32,
+}
+
+fn default_timestamp_format() -> String {
+    "%Y-%m-%d %H:%M:%S".to_string()
+}
+
+fn default_timestamp_size() -> f32 {
+    20.0
+}
+
+fn default_timestamp_background() -> Option<String> {
+    Some("#00000099".to_string())
+}
+
+/// Space between the text and the edge of its background box
+const TIMESTAMP_PADDING: u32 = 6;
+
+/// Draw the date/time described by `spec` onto `image`
+pub fn burn_timestamp(mut image: RgbaImage, spec: &TimestampSpec) -> Result<RgbaImage, AppError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let local = spec.unix_secs.unwrap_or(now) as i64 + spec.utc_offset_minutes as i64 * 60;
+    let text = crate::autosave::expand_utc(&spec.format, local.max(0) as u64, Default::default());
+    let text_layer = text_layer(&text, spec.size, &spec.color)?;
+    let layer = match &spec.background {
+        Some(background) => {
+            let fill = annotate::parse_color(background)?;
+            let p = TIMESTAMP_PADDING;
+            let mut boxed = RgbaImage::from_pixel(text_layer.width() + 2 * p, text_layer.height() + 2 * p, fill);
+            image::imageops::overlay(&mut boxed, &text_layer, p as i64, p as i64);
+            boxed
+        }
+        None => text_layer,
+    };
+    place(&mut image, &layer, spec.position, spec.margin);
+    Ok(image)
+}
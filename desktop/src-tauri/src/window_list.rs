@@ -0,0 +1,237 @@
+// ============================================
+// ScreenAI Desktop — Top-level window enumeration
+// ============================================
+
+use serde::Serialize;
+
+/// A visible top-level window with its bounds in screen coordinates
+#[derive(Clone, Serialize)]
+pub struct WindowInfo {
+    /// HWND on Windows, CGWindowID on macOS, X window id on Linux
+    pub id: u64,
+    pub title: String,
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+    /// Stacking position, 0 = front-most
+    pub z_order: usize,
+    /// Owning process, used to skip ScreenAI's own windows
+    #[serde(skip)]
+    pub pid: u32,
+}
+
+/// List visible top-level windows, front-most first
+#[cfg(target_os = "windows")]
+pub fn list() -> Vec<WindowInfo> {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn EnumWindows(lpEnumFunc: unsafe extern "system" fn(isize, isize) -> i32, lParam: isize) -> i32;
+        fn IsWindowVisible(hWnd: isize) -> i32;
+        fn GetWindowTextW(hWnd: isize, lpString: *mut u16, nMaxCount: i32) -> i32;
+        fn GetWindowRect(hWnd: isize, lpRect: *mut [i32; 4]) -> i32;
+        fn GetWindowLongW(hWnd: isize, nIndex: i32) -> i32;
+        fn GetWindowThreadProcessId(hWnd: isize, lpdwProcessId: *mut u32) -> u32;
+    }
+
+    unsafe extern "system" fn enum_cb(hwnd: isize, lparam: isize) -> i32 {
+        let windows = &mut *(lparam as *mut Vec<WindowInfo>);
+        if IsWindowVisible(hwnd) == 0 { return 1; }
+
+        // Skip tool windows, popups, etc.
+        let ex_style = GetWindowLongW(hwnd, -20); // GWL_EXSTYLE
+        if ex_style & 0x00000080 != 0 { return 1; } // WS_EX_TOOLWINDOW
+
+        let mut title_buf = [0u16; 256];
+        let len = GetWindowTextW(hwnd, title_buf.as_mut_ptr(), 256);
+        if len <= 0 { return 1; }
+        let title = OsString::from_wide(&title_buf[..len as usize]).to_string_lossy().to_string();
+        if title.is_empty() { return 1; }
+
+        let mut rect = [0i32; 4];
+        GetWindowRect(hwnd, &mut rect);
+        let x = rect[0]; let y = rect[1];
+        let w = rect[2] - rect[0]; let h = rect[3] - rect[1];
+        if w <= 0 || h <= 0 { return 1; }
+
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, &mut pid);
+
+        let z_order = windows.len();
+        windows.push(WindowInfo { id: hwnd as u64, title, x, y, w, h, z_order, pid });
+        1
+    }
+
+    let mut windows: Vec<WindowInfo> = Vec::new();
+    unsafe {
+        EnumWindows(enum_cb, &mut windows as *mut Vec<WindowInfo> as isize);
+    }
+    windows
+}
+
+/// List visible top-level windows, front-most first
+#[cfg(target_os = "macos")]
+pub fn list() -> Vec<WindowInfo> {
+    use core_foundation::base::{CFType, TCFType};
+    use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+    use core_foundation::number::CFNumber;
+    use core_foundation::string::CFString;
+    use core_graphics::window::{
+        copy_window_info, kCGNullWindowID, kCGWindowBounds, kCGWindowLayer,
+        kCGWindowListOptionExcludeDesktopElements, kCGWindowListOptionOnScreenOnly, kCGWindowName,
+        kCGWindowNumber, kCGWindowOwnerName, kCGWindowOwnerPID,
+    };
+
+    let Some(info) = copy_window_info(
+        kCGWindowListOptionOnScreenOnly | kCGWindowListOptionExcludeDesktopElements,
+        kCGNullWindowID,
+    ) else {
+        return Vec::new();
+    };
+
+    let number = |dict: &CFDictionary<CFString, CFType>, key: &CFString| -> Option<i64> {
+        dict.find(key)?.downcast::<CFNumber>()?.to_i64()
+    };
+    let string = |dict: &CFDictionary<CFString, CFType>, key: &CFString| -> Option<String> {
+        Some(dict.find(key)?.downcast::<CFString>()?.to_string())
+    };
+
+    let mut windows = Vec::new();
+    for item in info.iter() {
+        let dict: CFDictionary<CFString, CFType> =
+            unsafe { CFDictionary::wrap_under_get_rule(*item as CFDictionaryRef) };
+        let key = |k| unsafe { CFString::wrap_under_get_rule(k) };
+
+        // Layer 0 = normal application windows (menu bar, dock, etc. live above)
+        if number(&dict, &key(unsafe { kCGWindowLayer })) != Some(0) { continue; }
+        let Some(id) = number(&dict, &key(unsafe { kCGWindowNumber })) else { continue };
+
+        // Window titles need Screen Recording permission — fall back to the app name
+        let title = string(&dict, &key(unsafe { kCGWindowName }))
+            .filter(|t| !t.is_empty())
+            .or_else(|| string(&dict, &key(unsafe { kCGWindowOwnerName })))
+            .unwrap_or_default();
+        if title.is_empty() { continue; }
+
+        let Some(bounds) = dict
+            .find(key(unsafe { kCGWindowBounds }))
+            .and_then(|b| b.downcast::<CFDictionary>())
+        else { continue };
+        let bounds: CFDictionary<CFString, CFType> =
+            unsafe { CFDictionary::wrap_under_get_rule(bounds.as_concrete_TypeRef()) };
+        let field = |name: &str| number(&bounds, &CFString::new(name)).unwrap_or(0) as i32;
+        let (x, y, w, h) = (field("X"), field("Y"), field("Width"), field("Height"));
+        if w <= 0 || h <= 0 { continue; }
+
+        let pid = number(&dict, &key(unsafe { kCGWindowOwnerPID })).unwrap_or(0) as u32;
+
+        let z_order = windows.len();
+        windows.push(WindowInfo { id: id as u64, title, x, y, w, h, z_order, pid });
+    }
+    windows
+}
+
+/// `list()` without ScreenAI's own windows (main, overlay, pins)
+pub fn others() -> Vec<WindowInfo> {
+    let own_pid = std::process::id();
+    list().into_iter().filter(|w| w.pid != own_pid).collect()
+}
+
+/// The window the user is working in: the OS foreground window, or if that
+/// belongs to ScreenAI (e.g. we were just clicked) the front-most other one.
+/// On Linux only the window manager's `_NET_ACTIVE_WINDOW`, so nothing when
+/// that is ScreenAI, and nothing on Wayland.
+pub fn active() -> Option<WindowInfo> {
+    #[cfg(target_os = "linux")]
+    {
+        net_active_window().filter(|w| w.pid != std::process::id())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let windows = others();
+
+        #[cfg(target_os = "windows")]
+        {
+            #[link(name = "user32")]
+            extern "system" {
+                fn GetForegroundWindow() -> isize;
+            }
+            let foreground = unsafe { GetForegroundWindow() } as u64;
+            if let Some(window) = windows.iter().find(|w| w.id == foreground) {
+                return Some(window.clone());
+            }
+        }
+
+        // `list()` is ordered front to back
+        windows.into_iter().next()
+    }
+}
+
+/// The window `_NET_ACTIVE_WINDOW` names, without its frame, in root-window
+/// (physical pixel) coordinates. `None` when the window manager doesn't set
+/// it, and on Wayland, where X clients don't see other apps' windows.
+#[cfg(target_os = "linux")]
+fn net_active_window() -> Option<WindowInfo> {
+    use xcb::{x, Xid};
+
+    let (conn, screen_num) = xcb::Connection::connect(None).ok()?;
+    let root = conn.get_setup().roots().nth(screen_num as usize)?.root();
+    let atom = |name: &[u8]| {
+        let cookie = conn.send_request(&x::InternAtom { only_if_exists: true, name });
+        conn.wait_for_reply(cookie).ok().map(|r| r.atom()).filter(|a| *a != x::ATOM_NONE)
+    };
+    let property = |window: x::Window, property: x::Atom, r#type: x::Atom| {
+        let cookie = conn.send_request(&x::GetProperty {
+            delete: false,
+            window,
+            property,
+            r#type,
+            long_offset: 0,
+            long_length: 1024,
+        });
+        conn.wait_for_reply(cookie).ok()
+    };
+
+    let active = *property(root, atom(b"_NET_ACTIVE_WINDOW")?, x::ATOM_WINDOW)?.value::<x::Window>().first()?;
+    if active.is_none() {
+        return None;
+    }
+    let geometry = conn.wait_for_reply(conn.send_request(&x::GetGeometry { drawable: x::Drawable::Window(active) })).ok()?;
+    let origin = conn
+        .wait_for_reply(conn.send_request(&x::TranslateCoordinates { src_window: active, dst_window: root, src_x: 0, src_y: 0 }))
+        .ok()?;
+
+    let utf8_title = atom(b"_NET_WM_NAME")
+        .zip(atom(b"UTF8_STRING"))
+        .and_then(|(name, utf8)| property(active, name, utf8))
+        .filter(|r| !r.value::<u8>().is_empty());
+    let title = utf8_title
+        .or_else(|| property(active, x::ATOM_WM_NAME, x::ATOM_STRING))
+        .map(|r| String::from_utf8_lossy(r.value::<u8>()).to_string())
+        .unwrap_or_default();
+    let pid = atom(b"_NET_WM_PID")
+        .and_then(|name| property(active, name, x::ATOM_CARDINAL))
+        .and_then(|r| r.value::<u32>().first().copied())
+        .unwrap_or(0);
+
+    Some(WindowInfo {
+        id: active.resource_id() as u64,
+        title,
+        x: origin.dst_x() as i32,
+        y: origin.dst_y() as i32,
+        w: geometry.width() as i32,
+        h: geometry.height() as i32,
+        z_order: 0,
+        pid,
+    })
+}
+
+/// Window enumeration is not implemented on Linux yet
+#[cfg(target_os = "linux")]
+pub fn list() -> Vec<WindowInfo> {
+    Vec::new()
+}
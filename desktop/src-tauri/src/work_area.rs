@@ -0,0 +1,146 @@
+// ============================================
+// ScreenAI Desktop — Display work areas
+// ============================================
+//
+// The part of a display not covered by the taskbar, dock or menu bar, in the
+// same global coordinates `display_info` reports for the display itself
+// (physical pixels on Windows and X11, points on macOS).
+
+use crate::capture::Rect;
+
+/// Work area of `display`, `None` when the OS can't tell us
+#[cfg(target_os = "windows")]
+pub fn query(display: &display_info::DisplayInfo) -> Option<Rect> {
+    #[repr(C)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+    #[repr(C)]
+    #[derive(Default)]
+    struct WinRect {
+        left: i32,
+        top: i32,
+        right: i32,
+        bottom: i32,
+    }
+    #[repr(C)]
+    #[derive(Default)]
+    struct MonitorInfo {
+        cb_size: u32,
+        rc_monitor: WinRect,
+        rc_work: WinRect,
+        dw_flags: u32,
+    }
+    const MONITOR_DEFAULTTONULL: u32 = 0;
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn MonitorFromPoint(pt: Point, dwFlags: u32) -> isize;
+        fn GetMonitorInfoW(hMonitor: isize, lpmi: *mut MonitorInfo) -> i32;
+    }
+
+    // Per monitor, unlike SPI_GETWORKAREA which only knows the primary display
+    let center = Point {
+        x: display.x + display.width as i32 / 2,
+        y: display.y + display.height as i32 / 2,
+    };
+    let monitor = unsafe { MonitorFromPoint(center, MONITOR_DEFAULTTONULL) };
+    if monitor == 0 {
+        return None;
+    }
+    let mut info = MonitorInfo { cb_size: std::mem::size_of::<MonitorInfo>() as u32, ..MonitorInfo::default() };
+    if unsafe { GetMonitorInfoW(monitor, &mut info) } == 0 {
+        return None;
+    }
+    let work = info.rc_work;
+    Some(Rect {
+        x: work.left,
+        y: work.top,
+        w: (work.right - work.left).max(0) as u32,
+        h: (work.bottom - work.top).max(0) as u32,
+    })
+}
+
+/// Work area of `display`, `None` when the OS can't tell us
+#[cfg(target_os = "macos")]
+pub fn query(display: &display_info::DisplayInfo) -> Option<Rect> {
+    use core_graphics::geometry::CGRect;
+    use objc::runtime::Object;
+    use objc::{class, msg_send, sel, sel_impl};
+
+    // The NSScreen and NSString objects below are autoreleased; this runs on
+    // command threads that have no pool of their own to drain them
+    objc::rc::autoreleasepool(|| unsafe {
+        let screens: *mut Object = msg_send![class!(NSScreen), screens];
+        let count: usize = if screens.is_null() { 0 } else { msg_send![screens, count] };
+        if count == 0 {
+            return None;
+        }
+        // AppKit's y axis points up from the bottom of the menu bar screen (the first one)
+        let first: *mut Object = msg_send![screens, objectAtIndex: 0usize];
+        let first_frame: CGRect = msg_send![first, frame];
+        let key: *mut Object = msg_send![class!(NSString), stringWithUTF8String: c"NSScreenNumber".as_ptr()];
+
+        for i in 0..count {
+            let screen: *mut Object = msg_send![screens, objectAtIndex: i];
+            let description: *mut Object = msg_send![screen, deviceDescription];
+            let number: *mut Object = msg_send![description, objectForKey: key];
+            if number.is_null() {
+                continue;
+            }
+            let id: u32 = msg_send![number, unsignedIntValue];
+            if id != display.id {
+                continue;
+            }
+            let visible: CGRect = msg_send![screen, visibleFrame];
+            return Some(Rect {
+                x: visible.origin.x.round() as i32,
+                y: (first_frame.size.height - visible.origin.y - visible.size.height).round() as i32,
+                w: visible.size.width.round() as u32,
+                h: visible.size.height.round() as u32,
+            });
+        }
+        None
+    })
+}
+
+/// Work area of `display`, from the window manager's `_NET_WORKAREA`. That's a
+/// single box for the whole X screen, so it's clipped to the display; panels
+/// between two displays aren't excluded. `None` on Wayland.
+#[cfg(target_os = "linux")]
+pub fn query(display: &display_info::DisplayInfo) -> Option<Rect> {
+    use xcb::x;
+
+    let (conn, screen_num) = xcb::Connection::connect(None).ok()?;
+    let root = conn.get_setup().roots().nth(screen_num as usize)?.root();
+    let atom = |name: &[u8]| {
+        let cookie = conn.send_request(&x::InternAtom { only_if_exists: true, name });
+        conn.wait_for_reply(cookie).ok().map(|r| r.atom()).filter(|a| *a != x::ATOM_NONE)
+    };
+    let cardinals = |property: x::Atom| {
+        let cookie = conn.send_request(&x::GetProperty {
+            delete: false,
+            window: root,
+            property,
+            r#type: x::ATOM_CARDINAL,
+            long_offset: 0,
+            long_length: 1024,
+        });
+        conn.wait_for_reply(cookie).ok().map(|r| r.value::<u32>().to_vec())
+    };
+
+    // One x, y, width, height quadruple per virtual desktop
+    let areas = cardinals(atom(b"_NET_WORKAREA")?)?;
+    let desktop = atom(b"_NET_CURRENT_DESKTOP")
+        .and_then(cardinals)
+        .and_then(|v| v.first().copied())
+        .unwrap_or(0) as usize;
+    let area = areas.chunks_exact(4).nth(desktop).or_else(|| areas.chunks_exact(4).next())?;
+
+    let left = (area[0] as i32).max(display.x);
+    let top = (area[1] as i32).max(display.y);
+    let right = (area[0] as i32 + area[2] as i32).min(display.x + display.width as i32);
+    let bottom = (area[1] as i32 + area[3] as i32).min(display.y + display.height as i32);
+    (right > left && bottom > top).then(|| Rect { x: left, y: top, w: (right - left) as u32, h: (bottom - top) as u32 })
+}
@@ -35,8 +35,10 @@ async function init() {
   // Detect which window we are
   const label = await invoke('get_window_label');
 
-  if (label === 'capture-overlay') {
+  if (typeof label === 'string' && label.startsWith('capture-overlay')) {
     await initCaptureOverlay();
+  } else if (typeof label === 'string' && label.startsWith('pin-')) {
+    await initPinWindow();
   } else {
     await initMainWindow();
   }
@@ -50,8 +52,6 @@ async function initMainWindow() {
   const app = new ScreenAIApp(appContainer);
   window.__SCREENAI_APP__ = app;
 
-  await event.emit('ready');
-
   await event.listen('show-capture-toolbar', () => {
     app.triggerCaptureFromShortcut();
   });
@@ -63,6 +63,17 @@ async function initMainWindow() {
     }
   });
 
+  // captureText shortcut: attach the area and put its text on the clipboard
+  await event.listen('capture-text', async (e: any) => {
+    const { capture, text } = e.payload || {};
+    if (capture?.data_url) {
+      app.attachScreenshotFromShortcut(capture.data_url, 'region');
+    }
+    if (text) {
+      await navigator.clipboard.writeText(text).catch((err) => console.error('Clipboard write failed:', err));
+    }
+  });
+
   await event.listen('capture-error', (e: any) => {
     const msg = typeof e.payload === 'string' ? e.payload : 'Screen capture failed';
     showErrorToast(msg);
@@ -73,9 +84,72 @@ async function initMainWindow() {
     showUpdateToast(info.version, info.body);
   });
 
+  await event.listen('update-download-progress', (e: any) => {
+    const { bytes_downloaded, content_length } = e.payload || {};
+    const btn = document.querySelector<HTMLButtonElement>('.update-toast [data-action="update-now"]');
+    if (!btn) return;
+    btn.textContent = content_length
+      ? `Downloading... ${Math.floor((bytes_downloaded / content_length) * 100)}%`
+      : `Downloading... ${(bytes_downloaded / 1048576).toFixed(1)} MB`;
+  });
+
+  await event.listen('update-downloaded', () => {
+    const btn = document.querySelector<HTMLButtonElement>('.update-toast [data-action="update-now"]');
+    if (btn) btn.textContent = 'Installing...';
+  });
+
+  // The last update keeps dying right after launch: offer to go back
+  await event.listen('update-failed', (e: any) => {
+    showUpdateFailedToast(e.payload || {});
+  });
+
+  await event.listen('shortcut-registration-failed', (e: any) => {
+    const accelerator = e.payload?.accelerator || 'shortcut';
+    showErrorToast(`${accelerator} is already used by another app`, 'Shortcut unavailable: ');
+  });
+
+  // Tray Quit waits up to 2 s for the answer, so reply right away: with an
+  // unsent message or attachment, cancel and ask instead of losing it
+  await event.listen('before-quit', async () => {
+    const cancel = app.hasUnsentInput();
+    await event.emit('before-quit-done', { cancel });
+    if (cancel) showQuitToast();
+  });
+
+  // Signal readiness last so the backend doesn't emit before listeners exist
+  await event.emit('ready');
+
   console.log('ScreenAI Desktop ready');
 }
 
+// ==========================================
+// Pinned Capture Mode
+// ==========================================
+async function initPinWindow() {
+  const appEl = document.getElementById('app');
+  if (appEl) appEl.style.display = 'none';
+  document.body.style.cssText = 'margin:0;padding:0;overflow:hidden;background:transparent;';
+
+  try {
+    const dataUrl: string = await invoke('get_pinned_capture');
+    // The whole image is a drag handle so the pin can be moved anywhere
+    const img = document.createElement('img');
+    img.src = dataUrl;
+    img.draggable = false;
+    img.setAttribute('data-tauri-drag-region', '');
+    img.style.cssText = 'display:block;width:100vw;height:100vh;object-fit:contain;cursor:move;user-select:none;';
+    document.body.appendChild(img);
+  } catch (err) {
+    console.error('Failed to load pinned capture:', err);
+    await invoke('close_pin');
+    return;
+  }
+
+  document.addEventListener('keydown', (e) => {
+    if (e.key === 'Escape') invoke('close_pin');
+  });
+}
+
 // ==========================================
 // Capture Overlay Mode
 // ==========================================
@@ -89,12 +163,13 @@ async function initCaptureOverlay() {
 
   try {
     const pending = await invoke('get_pending_capture');
-    const mode: string = pending.mode;      // "toolbar" or "region"
+    const mode: string = pending.mode;      // "toolbar", "region" or "text"
     const dataUrl: string = pending.dataUrl; // fullscreen screenshot data URL
 
-    if (mode === 'region') {
-      // Direct region capture: show region selector immediately
-      startRegionCapture(dataUrl);
+    if (mode === 'region' || mode === 'text') {
+      // Direct region capture: the backend captures the selected area live
+      // ("text" also runs OCR on it)
+      startRegionCapture(dataUrl, true, mode === 'text');
     } else {
       // Toolbar mode: show capture mode selection
       showCaptureToolbar(dataUrl);
@@ -166,10 +241,10 @@ function showCaptureToolbar(screenshotUrl: string) {
   const cleanup = () => { bar.remove(); dimmer.remove(); bg.remove(); document.removeEventListener('keydown', escHandler); };
 
   // Close
-  bar.querySelector('.scb-close')!.addEventListener('click', () => { cleanup(); invoke('close_capture_overlay'); });
+  bar.querySelector('.scb-close')!.addEventListener('click', () => { cleanup(); invoke('cancel_capture'); });
 
   // Escape
-  const escHandler = (e: KeyboardEvent) => { if (e.key === 'Escape') { cleanup(); invoke('close_capture_overlay'); } };
+  const escHandler = (e: KeyboardEvent) => { if (e.key === 'Escape') { cleanup(); invoke('cancel_capture'); } };
   document.addEventListener('keydown', escHandler);
 
   // Capture
@@ -215,11 +290,17 @@ function showCountdown(seconds: number, screenshotUrl: string): Promise<void> {
   });
 }
 
-function startRegionCapture(screenshotUrl: string) {
+function startRegionCapture(screenshotUrl: string, commit = false, extractText = false) {
   new RegionSelector(
     document.body,
     screenshotUrl,
     async (region) => {
+      if (commit) {
+        // Closes this overlay and sends the capture to the main window
+        await invoke('commit_region', { ...region, extractText })
+          .catch((err) => console.error('commit_region failed:', err));
+        return;
+      }
       const cropped = await cropScreenshot(
         screenshotUrl,
         region,
@@ -230,7 +311,7 @@ function startRegionCapture(screenshotUrl: string) {
     },
     () => {
       // User cancelled region selection
-      invoke('close_capture_overlay');
+      invoke('cancel_capture');
     }
   );
 }
@@ -245,7 +326,7 @@ function openAnnotationOverlay(screenshotUrl: string, mode: 'fullscreen' | 'regi
 
   // When overlay is closed (X button or Escape), close the overlay window
   overlay.onClose = () => {
-    invoke('close_capture_overlay');
+    invoke('cancel_capture');
   };
 }
 
@@ -267,25 +348,97 @@ function showUpdateToast(version: string, body: string) {
   `;
   document.body.appendChild(toast);
 
-  toast.querySelector('[data-action="update-now"]')?.addEventListener('click', async () => {
+  const installBtn = toast.querySelector<HTMLButtonElement>('[data-action="update-now"]');
+  const laterBtn = toast.querySelector<HTMLButtonElement>('[data-action="update-later"]');
+  let installing = false;
+
+  installBtn?.addEventListener('click', async () => {
+    installing = true;
+    installBtn.disabled = true;
+    installBtn.textContent = 'Downloading...';
+    if (laterBtn) laterBtn.textContent = 'Cancel';
     try {
       if (invoke) await invoke('install_update');
-    } catch (err) {
-      console.error('Update install failed:', err);
+    } catch (err: any) {
+      // Cancelled or failed: let the user try again
+      if (err?.code !== 'update_cancelled') console.error('Update install failed:', err);
+      installing = false;
+      installBtn.disabled = false;
+      installBtn.textContent = 'Install & Restart';
+      if (laterBtn) laterBtn.textContent = 'Later';
     }
   });
 
-  toast.querySelector('[data-action="update-later"]')?.addEventListener('click', () => {
-    toast.remove();
+  laterBtn?.addEventListener('click', () => {
+    if (installing) {
+      if (invoke) invoke('cancel_update');
+    } else {
+      toast.remove();
+    }
   });
 
-  setTimeout(() => { if (toast.parentNode) toast.remove(); }, 30000);
+  setTimeout(() => { if (toast.parentNode && !installing) toast.remove(); }, 30000);
+}
+
+function showUpdateFailedToast(status: { current_version?: string; previous_version?: string; can_rollback?: boolean }) {
+  const previous = status.previous_version || 'the previous version';
+  const toast = document.createElement('div');
+  toast.className = 'update-toast';
+  toast.innerHTML = `
+    <div class="update-toast-content">
+      <strong>ScreenAI ${status.current_version || ''} keeps closing after launch</strong>
+      <p>${status.can_rollback
+        ? `You can go back to ${previous}; ScreenAI will restart.`
+        : `Reinstall ${previous} to go back to it.`}</p>
+      <div class="update-toast-actions">
+        ${status.can_rollback ? `<button class="update-toast-btn primary" data-action="rollback">Restore ${previous}</button>` : ''}
+        <button class="update-toast-btn" data-action="dismiss">Dismiss</button>
+      </div>
+    </div>
+  `;
+  document.body.appendChild(toast);
+
+  const rollbackBtn = toast.querySelector<HTMLButtonElement>('[data-action="rollback"]');
+  rollbackBtn?.addEventListener('click', async () => {
+    rollbackBtn.disabled = true;
+    rollbackBtn.textContent = 'Restoring...';
+    try {
+      if (invoke) await invoke('rollback_update');
+    } catch (err: any) {
+      rollbackBtn.disabled = false;
+      rollbackBtn.textContent = `Restore ${previous}`;
+      showErrorToast(err?.message || String(err), 'Rollback failed: ');
+    }
+  });
+  toast.querySelector('[data-action="dismiss"]')?.addEventListener('click', () => toast.remove());
+}
+
+function showQuitToast() {
+  document.querySelector('.quit-toast')?.remove();
+  const toast = document.createElement('div');
+  toast.className = 'update-toast quit-toast';
+  toast.innerHTML = `
+    <div class="update-toast-content">
+      <strong>Quit ScreenAI?</strong>
+      <p>Your message and attachments haven't been sent and will be lost.</p>
+      <div class="update-toast-actions">
+        <button class="update-toast-btn primary" data-action="quit-anyway">Quit</button>
+        <button class="update-toast-btn" data-action="keep-editing">Keep Editing</button>
+      </div>
+    </div>
+  `;
+  document.body.appendChild(toast);
+
+  toast.querySelector('[data-action="quit-anyway"]')?.addEventListener('click', () => {
+    if (invoke) invoke('quit_app');
+  });
+  toast.querySelector('[data-action="keep-editing"]')?.addEventListener('click', () => toast.remove());
 }
 
-function showErrorToast(msg: string) {
+function showErrorToast(msg: string, prefix = 'Capture failed: ') {
   const toast = document.createElement('div');
   toast.style.cssText = 'position:fixed;bottom:20px;left:50%;transform:translateX(-50%);background:#3d0000;color:#ff6b6b;border:1px solid #ff6b6b;border-radius:8px;padding:10px 18px;font-size:13px;z-index:999;max-width:400px;text-align:center;';
-  toast.textContent = 'Capture failed: ' + msg;
+  toast.textContent = prefix + msg;
   document.body.appendChild(toast);
   setTimeout(() => toast.remove(), 4000);
 }
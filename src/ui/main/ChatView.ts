@@ -104,6 +104,12 @@ export class ChatView {
     this.updateAttachmentChip();
   }
 
+  /** A typed message or attachments that haven't been sent yet */
+  hasUnsentInput(): boolean {
+    const input = this.el.querySelector<HTMLTextAreaElement>('[data-input]');
+    return this.pendingAttachments.length > 0 || !!input?.value.trim();
+  }
+
   private render() {
     if (!this.conversation) return;
     const c = this.conversation;
@@ -83,6 +83,10 @@ export class MainView {
     this.chatView.attachScreenshot(dataUrl);
   }
 
+  hasUnsentInput(): boolean {
+    return this.chatView.hasUnsentInput();
+  }
+
   getSettingsContainer(): HTMLElement {
     return this.el;
   }
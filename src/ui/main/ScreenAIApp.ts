@@ -568,6 +568,12 @@ export class ScreenAIApp {
       await this.handleCaptureResult(dataUrl, 'fullscreen');
     }
   }
+
+  /** Called from desktop-main.ts before quitting, so a draft isn't lost silently */
+  hasUnsentInput(): boolean {
+    return this.mainView.hasUnsentInput();
+  }
+
   // --- Region Selector ---
 
   private async setWindowFullscreen(fs: boolean) {